@@ -2,9 +2,25 @@
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+    use super::config::*;
     use super::core::*;
+    use super::csv_io::*;
     use super::data_access_layer::*;
+    #[cfg(feature = "async")]
+    use super::async_api::*;
     use super::database::*;
+    use super::file_storage::*;
+    use super::history::*;
+    use super::hooks::*;
+    use super::journal::*;
+    use super::server::*;
+    use super::stats::*;
+    use super::storage::*;
+    use super::time_tracking::*;
+    use super::tui::*;
+    use super::views::*;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
 
     #[test]
     fn test_todo_add() {
@@ -45,6 +61,7 @@ mod tests {
         assert_eq!(&new_name, res.name());
     }
 
+    #[allow(clippy::bool_assert_comparison)]
     #[test]
     #[should_panic]
     fn test_todo_delete() {
@@ -62,18 +79,16 @@ mod tests {
 
     #[test]
     fn test_todo_all() {
-        Todo::init_table().unwrap();
+        let store = Store::in_memory().unwrap();
 
-        Todo::add("uno".into(), Some("the first number".to_string())).unwrap();
-        Todo::add(String::from("dos"), None).unwrap();
+        store.add_todo("uno".into(), Some("the first number".to_string())).unwrap();
+        store.add_todo(String::from("dos"), None).unwrap();
 
-        let todos = Todo::all();
+        let todos = store.todos();
 
-        /* NOTE: Other tests are executed in the same context,
-         * so, at least the number of todos in the list should be equal or
-         * greater to the number of todos added above.
-         * */
-        assert!(todos.len() >= 2);
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].name(), "uno");
+        assert_eq!(todos[1].name(), "dos");
     }
 
     #[test]
@@ -127,6 +142,7 @@ mod tests {
         assert_eq!(res.what(), "testing this task");
     }
 
+    #[allow(clippy::bool_assert_comparison)]
     #[test]
     #[should_panic]
     fn test_task_delete() {
@@ -145,23 +161,35 @@ mod tests {
 
     #[test]
     fn test_task_all() {
-        Todo::init_table().unwrap();
-        Task::init_table().unwrap();
+        let store = Store::in_memory().unwrap();
 
-        let todo1 = Todo::add("test tasks".into(), None).unwrap();
-        let todo2 = Todo::add("test tasks again".into(), None).unwrap();
+        let todo1 = store.add_todo("test tasks".into(), None).unwrap();
+        let todo2 = store.add_todo("test tasks again".into(), None).unwrap();
 
-        Task::add("test task model 1 times".into(), *todo1.id()).unwrap();
-        Task::add("test task model 2 times".into(), *todo1.id()).unwrap();
-        Task::add("test task model once again".into(), *todo2.id()).unwrap();
+        store.add_task("test task model 1 times".into(), *todo1.id()).unwrap();
+        store.add_task("test task model 2 times".into(), *todo1.id()).unwrap();
+        store.add_task("test task model once again".into(), *todo2.id()).unwrap();
 
-        let tasks = Task::all();
+        let tasks = store.tasks();
 
-        /* NOTE: Other tests are executed in the same context,
-         * so, at least the number of todos in the list should be equal or
-         * greater to the number of todos added above.
-         * */
-        assert!(tasks.len() >= 3);
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(store.todo_tasks(*todo2.id()).len(), 1);
+    }
+
+    #[test]
+    fn test_stores_are_isolated() {
+        let first = Store::in_memory().unwrap();
+        let second = Store::in_memory().unwrap();
+
+        let todo = first.add_todo("only in the first".into(), None).unwrap();
+
+        assert_eq!(first.todos().len(), 1);
+        assert!(second.todos().is_empty());
+        assert!(second.find_todo(*todo.id()).is_err());
+        assert_eq!(
+            second.add_task("orphan".into(), *todo.id()).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
     }
 
     #[test]
@@ -195,835 +223,8518 @@ mod tests {
         assert_eq!(task_s_todo.id(), todo.id());
         assert_eq!(task_s_todo.name(), todo.name());
     }
-}
 
-pub mod prelude {
-    pub use super::core::*;
-    pub use super::data_access_layer::*;
-}
+    #[test]
+    fn test_export_csv() {
+        let store = Store::in_memory().unwrap();
+
+        let todo = store.add_todo("test 'csv' export".into(), None).unwrap();
+        store.add_task("buy milk, eggs and \"bread\"".into(), *todo.id()).unwrap();
+        store.add_todo("nothing to do".into(), Some("yet".into())).unwrap();
+
+        let mut output: Vec<u8> = Vec::new();
+        let columns = [CsvColumn::TodoName, CsvColumn::TodoDescription, CsvColumn::What, CsvColumn::Status];
+        let rows = export_csv(&store, &mut output, &columns).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(rows, 2);
+        assert_eq!(
+            output,
+            "todo_name,todo_description,what,status\n\
+             test 'csv' export,,\"buy milk, eggs and \"\"bread\"\"\",todo\n\
+             nothing to do,yet,,\n"
+        );
+    }
 
-mod database {
-    use lazy_static::lazy_static;
-    use sqlite::{self, Connection};
-    use std::sync::{Arc, Mutex};
-    use std::thread;
+    #[test]
+    fn test_import_csv() {
+        let store = Store::in_memory().unwrap();
+
+        let input = "todo_id,todo_name,todo_description,what,status,completed_at,estimate\n\
+                     7,imported,from a file,\"first, task\",todo,,1.5\n\
+                     7,imported,from a file,second task,done,2022-06-01 10:00:00,\n\
+                     8,empty,,,,,\n";
+
+        let (todos, tasks) = import_csv(&store, input.as_bytes()).unwrap();
+
+        assert_eq!(todos, 2);
+        assert_eq!(tasks, 2);
+
+        let todos = store.todos();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].name(), "imported");
+        assert_eq!(todos[0].description(), Some(&String::from("from a file")));
+        assert_eq!(todos[1].name(), "empty");
+        assert_eq!(todos[1].description(), None);
+        assert!(store.todo_tasks(*todos[1].id()).is_empty());
+
+        let tasks = store.todo_tasks(*todos[0].id());
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].what(), "first, task");
+        assert_eq!(*tasks[0].status(), Status::Todo);
+        assert_eq!(tasks[0].estimate(), Some(1.5));
+        assert_eq!(tasks[1].what(), "second task");
+        assert_eq!(*tasks[1].status(), Status::Done("2022-06-01 10:00:00".into()));
+        assert_eq!(tasks[1].estimate(), None);
+
+        // The import is a single operation.
+        store.undo(1).unwrap();
+        assert!(store.todos().is_empty());
+        assert!(store.tasks().is_empty());
+    }
 
-    /// Database handler for the aplication
-    pub struct Database {
-        path: String,
-        connection: Connection,
+    #[test]
+    fn test_import_csv_rollback() {
+        let store = Store::in_memory().unwrap();
+
+        let input = "todo_name,what,estimate\n\
+                     first,valid task,2\n\
+                     second,invalid task,soon\n";
+
+        let e = import_csv(&store, input.as_bytes()).unwrap_err();
+
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(store.todos().is_empty());
+        assert!(store.tasks().is_empty());
+        assert!(store.history(&HistoryQuery::default()).is_empty());
     }
 
-    impl From<&str> for Database {
-        /// Initializes the database from a given path
-        fn from(path: &str) -> Self {
-            Self::new(path)
-        }
+    #[test]
+    fn test_csv_column_from_str() {
+        assert_eq!("what".parse::<CsvColumn>().unwrap(), CsvColumn::What);
+        assert!("whatever".parse::<CsvColumn>().is_err());
     }
 
-    impl Database {
-        pub fn new(path: &str) -> Self {
-            let path = String::from(path);
-            let connection = Connection::open(&path).unwrap();
-            Self { path, connection }
-        }
+    #[test]
+    fn test_api_todos() {
+        Todo::init_table().unwrap();
+        Task::init_table().unwrap();
 
-        /// References the path of the db
-        pub fn path(&self) -> &String {
-            &self.path
-        }
+        let res = handle_request("POST", "/todos", r#"{"name": "api", "description": "rest"}"#);
+        assert_eq!(res.status, 201);
 
-        /// References the connection to the database
-        pub fn connection(&self) -> &Connection {
-            &self.connection
-        }
+        let id = res.body.unwrap()["id"].as_u64().unwrap();
+        let url = format!("/todos/{}", id);
 
-        /// Executes a query command into the database
-        pub fn exec_sttmt(&self, statement: &str) -> Result<(), sqlite::Error> {
-            self.connection().execute(statement)
-        }
+        let res = handle_request("PATCH", &url, r#"{"name": "api v2"}"#);
+        assert_eq!(res.status, 200);
+        let body = res.body.unwrap();
+        assert_eq!((body["name"].as_str(), body["description"].as_str()), (Some("api v2"), Some("rest")));
 
-        /// Executes a select query and returns a cursor
-        pub fn select_query(&mut self, query: &str) -> Result<sqlite::Cursor, sqlite::Error> {
-            Ok(self.connection.prepare(query)?.into_cursor())
-        }
+        let res = handle_request("PATCH", &url, r#"{"description": null}"#);
+        assert_eq!(res.status, 200);
+        assert!(res.body.unwrap()["description"].is_null());
 
-        pub fn create_table(&mut self, sttmt: &str) -> Result<(), sqlite::Error> {
-            let statement = format!("CREATE TABLE IF NOT EXISTS {};", sttmt);
-            self.exec_sttmt(&statement)
-        }
+        let res = handle_request("POST", &format!("{}/tasks", url), r#"{"what": "document it"}"#);
+        assert_eq!(res.status, 201);
+        assert_eq!(res.body.unwrap()["status"], "todo");
+
+        let res = handle_request("GET", &format!("{}/tasks", url), "");
+        assert_eq!(res.body.unwrap().as_array().unwrap().len(), 1);
+
+        assert_eq!(handle_request("DELETE", &url, "").status, 204);
+        assert_eq!(handle_request("GET", &url, "").status, 404);
+
+        // A patch is a single change, undone at once.
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("patch".into(), Some("me".into())).unwrap();
+        let patched = store.patch_todo(*todo.id(), Some("patched".into()), Some(None)).unwrap();
+        assert_eq!((patched.name().as_str(), patched.description()), ("patched", None));
+        assert_eq!(store.undo(1).unwrap().len(), 1);
+        let todo = store.find_todo(*todo.id()).unwrap();
+        assert_eq!((todo.name().as_str(), todo.description().map(|d| d.as_str())), ("patch", Some("me")));
     }
 
-    pub trait DatabaseConnectorTrait {
-        fn table_name() -> &'static str;
-        fn init_table() -> Result<(), sqlite::Error>;
+    #[test]
+    fn test_api_tasks() {
+        Todo::init_table().unwrap();
+        Task::init_table().unwrap();
 
-        fn is_table_initialized() -> bool {
-            let statement = format!("SELECT * FROM {} LIMIT 1;", Self::table_name());
+        let todo = Todo::add("api tasks".into(), None).unwrap();
+        let task = Task::add("patch me".into(), *todo.id()).unwrap();
+        let url = format!("/tasks/{}", task.id());
+
+        let res = handle_request("PATCH", &url, r#"{"status": "done", "completed_at": "2022-06-01"}"#);
+        assert_eq!(res.status, 200);
+
+        let body = res.body.unwrap();
+        assert_eq!(body["status"], "done");
+        assert_eq!(body["completed_at"], "2022-06-01");
+
+        assert_eq!(handle_request("PATCH", &url, r#"{"status": "later"}"#).status, 422);
+        assert_eq!(handle_request("PATCH", &url, "not json").status, 400);
+        assert_eq!(handle_request("PUT", &url, "").status, 405);
+        assert_eq!(handle_request("GET", "/tasks/abc", "").status, 400);
+        assert_eq!(handle_request("GET", "/nothing", "").status, 404);
+        assert_eq!(handle_request("POST", "/todos/0/tasks", r#"{"what": "x"}"#).status, 404);
+
+        let url = format!("/todos/{}/tasks", todo.id());
+        let res = handle_request("POST", &url, r#"{"what": "done already", "status": "done", "estimate": 2.5}"#);
+        assert_eq!(res.status, 201);
+        let body = res.body.unwrap();
+        assert_eq!(body["status"], "done");
+        assert_eq!(body["estimate"], 2.5);
+
+        // Nothing is added when the task is refused.
+        assert_eq!(handle_request("POST", &url, r#"{"what": "x", "estimate": -1}"#).status, 422);
+        assert_eq!(handle_request("POST", &url, r#"{"what": "x", "status": "later"}"#).status, 422);
+        assert_eq!(todo.tasks().len(), 2);
+    }
 
-            DB.lock().unwrap().exec_sttmt(&statement).is_ok()
-        }
+    #[test]
+    fn test_store_transaction_rolls_back() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("keep".into(), None).unwrap();
+
+        let res: Result<(), InternalError> = store.transaction(|storage| {
+            storage.delete_todo(*todo.id())?;
+            storage.find_todo(*todo.id())?;
+            Ok(())
+        });
+
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(store.find_todo(*todo.id()).is_ok());
     }
 
-    lazy_static! {
-        pub static ref DB: Arc<Mutex<Database>> = Arc::new(Mutex::new(Database::new(":memory:")));
+    #[test]
+    fn test_store_query_tasks() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("query".into(), None).unwrap();
+        let other = store.add_todo("other".into(), None).unwrap();
+
+        let done = store.add_task("Write the docs".into(), *todo.id()).unwrap();
+        store.add_task("write the tests".into(), *todo.id()).unwrap();
+        store.add_task("write more".into(), *other.id()).unwrap();
+        store
+            .update_task(*done.id(), None, Some(Status::from("2022-06-20")), None)
+            .unwrap();
+
+        let query = TaskQuery {
+            todo_id: Some(*todo.id()),
+            text: Some("WRITE".into()),
+            ..TaskQuery::default()
+        };
+        assert_eq!(store.query_tasks(&query).len(), 2);
+
+        let query = TaskQuery {
+            done: Some(false),
+            ..query
+        };
+        let tasks = store.query_tasks(&query);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].what(), "write the tests");
     }
-}
 
-mod core {
-    pub use std::error::Error;
-    use std::fmt::{self, Display};
+    #[test]
+    fn test_store_history() {
+        let store = Store::in_memory().unwrap().with_actor("tester");
+        let todo = store.add_todo("history".into(), None).unwrap();
+        let task = store.add_task("first".into(), *todo.id()).unwrap();
+
+        store.update_todo(*todo.id(), None, Some("described".into())).unwrap();
+        store.update_task(*task.id(), Some("second".into()), None, None).unwrap();
+        store.update_task(*task.id(), None, None, None).unwrap();
+        store.delete_task(*task.id()).unwrap();
+
+        let entries = store.history(&HistoryQuery {
+            todo_id: Some(*todo.id()),
+            ..HistoryQuery::default()
+        });
+        let changes: Vec<(Action, &str)> = entries
+            .iter()
+            .map(|entry| (entry.action(), entry.field().as_str()))
+            .collect();
+
+        assert_eq!(
+            changes,
+            vec![
+                (Action::Add, "name"),
+                (Action::Add, "todo_id"),
+                (Action::Add, "what"),
+                (Action::Add, "status"),
+                (Action::Update, "description"),
+                (Action::Update, "what"),
+                (Action::Delete, "deleted_at"),
+            ]
+        );
+
+        let update = &entries[5];
+        assert_eq!(update.old_value(), Some(&String::from("first")));
+        assert_eq!(update.new_value(), Some(&String::from("second")));
+        assert_eq!(update.actor(), "tester");
+        assert!(entries.iter().all(|entry| entry.timestamp() != "CURRENT_TIMESTAMP"));
+
+        // A failed change leaves nothing in the history
+        assert!(store.add_task("orphan".into(), 99).is_err());
+        assert_eq!(store.history(&HistoryQuery::default()).len(), entries.len());
+    }
 
-    #[derive(Debug)]
-    pub struct InternalError {
-        details: String,
+    #[test]
+    fn test_store_undo_redo() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("undo".into(), None).unwrap();
+        let task = store.add_task("keep me".into(), *todo.id()).unwrap();
+        let done = store
+            .update_task(*task.id(), None, Some(Status::from("2022-06-20")), None)
+            .unwrap();
+
+        store.delete_todo(*todo.id()).unwrap();
+        assert!(store.find_task(*task.id()).is_err());
+
+        // The todo and its task come back together, as they were
+        let undone = store.undo(1).unwrap();
+        assert_eq!(undone.len(), 1);
+        assert_eq!(store.find_todo(*todo.id()).unwrap(), todo);
+        assert_eq!(store.find_task(*task.id()).unwrap(), done);
+
+        store.undo(1).unwrap();
+        assert_eq!(store.find_task(*task.id()).unwrap(), task);
+
+        let redone = store.redo(5).unwrap();
+        assert_eq!(redone.len(), 2);
+        assert!(store.find_todo(*todo.id()).is_err());
+        assert!(store.redo(1).unwrap().is_empty());
+
+        // A new change drops what was undone
+        store.undo(1).unwrap();
+        store.add_todo("new".into(), None).unwrap();
+        assert!(store.redo(1).unwrap().is_empty());
+        assert_eq!(store.todos().len(), 2);
     }
 
-    impl InternalError {
-        pub fn new(details: &str) -> Self {
-            Self {
-                details: String::from(details),
-            }
-        }
+    #[test]
+    fn test_store_trash() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("trash".into(), None).unwrap();
+        let task = store.add_task("first".into(), *todo.id()).unwrap();
+        let alone = store.add_task("second".into(), *todo.id()).unwrap();
+
+        store.delete_task(*alone.id()).unwrap();
+        store.delete_todo(*todo.id()).unwrap();
+
+        assert!(store.todos().is_empty());
+        assert!(store.tasks().is_empty());
+        assert_eq!(store.find_todo(*todo.id()).unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(store.trashed_todos().len(), 1);
+        assert_eq!(store.trashed_tasks().len(), 2);
+        assert_eq!(store.add_task("orphan".into(), *todo.id()).unwrap_err().kind(), ErrorKind::NotFound);
+
+        let res = store.restore_task(*task.id());
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        // Only the task hidden along with the todo comes back with it
+        store.restore_todo(*todo.id()).unwrap();
+        assert_eq!(store.find_task(*task.id()).unwrap(), task);
+        assert!(store.find_task(*alone.id()).is_err());
+
+        assert_eq!(store.purge(Duration::from_secs(30 * 24 * 60 * 60)).unwrap(), (0, 0));
+        assert_eq!(store.purge(Duration::ZERO).unwrap(), (0, 1));
+        assert!(store.trashed_tasks().is_empty());
+        assert!(store.backend().find_task(*alone.id()).is_err());
+    }
 
-        pub fn table_not_initialized(name: &str) -> Self {
-            let details = format!("table '{}' was not initialed!", name);
-            Self::new(&details)
-        }
+    #[test]
+    fn test_store_archive() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("archive".into(), None).unwrap();
+
+        let archived = store.archive_todo(*todo.id()).unwrap();
+        assert!(archived.archived_at().is_some());
+        assert!(store.todos().is_empty());
+        assert_eq!(store.archived_todos(), vec![archived.clone()]);
+        assert_eq!(store.find_todo(*todo.id()).unwrap(), archived);
+
+        store.unarchive_todo(*todo.id()).unwrap();
+        assert_eq!(store.todos().len(), 1);
+        assert!(store.archived_todos().is_empty());
     }
 
-    impl Display for InternalError {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{}", self.details)
-        }
+    #[test]
+    fn test_store_complete_todo() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("progress".into(), None).unwrap();
+        let id = *todo.id();
+
+        assert_eq!(store.todo_progress(id).percent(), 0.0);
+
+        let first = store.add_task("first".into(), id).unwrap();
+        store.add_task("second".into(), id).unwrap();
+        store.add_task("third".into(), id).unwrap();
+        store
+            .update_task(*first.id(), None, Some(Status::Done("CURRENT_TIMESTAMP".into())), None)
+            .unwrap();
+
+        let progress = store.todo_progress(id);
+        assert_eq!((progress.total, progress.done, progress.remaining()), (3, 1, 2));
+        assert_eq!(progress.bar(6), "[##----]");
+        assert!(!progress.is_complete());
+
+        assert_eq!(store.complete_todo(id).unwrap().len(), 2);
+        assert!(store.todo_progress(id).is_complete());
+
+        store.undo(1).unwrap();
+        assert_eq!(store.todo_progress(id).done, 1);
     }
 
-    impl Error for InternalError {
-        fn description(&self) -> &str {
-            &self.details
-        }
+    #[test]
+    fn test_task_status_transitions() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("lifecycle".into(), None).unwrap();
+        let id = *store.add_task("ship".into(), *todo.id()).unwrap().id();
+        let now = || String::from("CURRENT_TIMESTAMP");
+
+        let task = store.update_task(id, None, Some(Status::InProgress(now())), None).unwrap();
+        assert_eq!(task.status().name(), "in_progress");
+        assert_ne!(task.status().since().unwrap(), "CURRENT_TIMESTAMP");
+
+        let blocked = Status::Blocked { reason: "review".into(), since: "2022-06-20".into() };
+        let task = store.update_task(id, None, Some(blocked.clone()), None).unwrap();
+        assert_eq!(*task.status(), blocked);
+
+        // A blocked task is unblocked before it is done, and a status isn't set twice.
+        let res = store.update_task(id, None, Some(Status::Done(now())), None);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidInput);
+        let res = store.update_task(id, None, Some(blocked.clone()), None);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidInput);
+        let reviewed = Status::Blocked { reason: "second review".into(), since: "2022-06-21".into() };
+        store.update_task(id, None, Some(reviewed), None).unwrap();
+
+        assert!(!Status::Todo.can_become(&Status::Todo));
+        assert!(Status::Todo.can_become(&Status::Done(now())));
+        assert!(!Status::Done(now()).can_become(&Status::Cancelled(now())));
+        assert!(Status::Done(now()).can_become(&Status::Todo));
+
+        store.update_task(id, None, Some(Status::Cancelled(now())), None).unwrap();
+        let res = store.update_task(id, None, Some(Status::Done(now())), None);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        store.update_task(id, None, Some(Status::Todo), None).unwrap();
+        assert_eq!(*store.find_task(id).unwrap().status(), Status::Todo);
+
+        let json = serde_json::to_value(&blocked).unwrap();
+        assert_eq!(json["status"], "blocked");
+        assert_eq!(json["reason"], "review");
+        assert_eq!(serde_json::from_value::<Status>(json).unwrap(), blocked);
     }
 
-    /// The type of the IDs used on the program.
-    pub type IdType = u64;
+    #[test]
+    fn test_time_tracking() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("billing".into(), None).unwrap();
+        let task = store.add_task("invoice".into(), *todo.id()).unwrap();
+        let other = store.add_task("call".into(), *todo.id()).unwrap();
+        let id = *task.id();
+
+        store.start_timer(id).unwrap();
+        assert!(matches!(store.find_task(id).unwrap().status(), Status::InProgress(_)));
+        assert!(store.start_timer(id).is_err());
+        assert!(store.stop_timer(id).unwrap().ended_at().is_some());
+        assert!(store.stop_timer(id).is_err());
+
+        store.log_time(id, Duration::from_secs(90 * 60)).unwrap();
+        store.log_time(*other.id(), Duration::from_secs(45 * 60)).unwrap();
+        assert_eq!(format_duration(store.task_time(id)), "1h30m");
+        assert_eq!(format_duration(store.todo_time(*todo.id())), "2h15m");
+
+        store.start_timer(*other.id()).unwrap();
+        store.update_task(*other.id(), None, Some(Status::Done("CURRENT_TIMESTAMP".into())), None).unwrap();
+        let running = TimeQuery { running: Some(true), ..TimeQuery::default() };
+        assert!(store.time_entries(&running).is_empty());
+
+        let rows = store.timesheet("2000-01-01", "9999-12-31");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].task.id(), task.id());
+        assert!(store.timesheet("2000-01-01", "2000-12-31").is_empty());
+        assert!(store.log_time(99, Duration::from_secs(60)).is_err());
+
+        // Undoing the start of a task ends its timer, keeping the time spent.
+        let mail = store.add_task("mail".into(), *todo.id()).unwrap();
+        store.start_timer(*mail.id()).unwrap();
+        store.undo(1).unwrap();
+        assert_eq!(*store.find_task(*mail.id()).unwrap().status(), Status::Todo);
+        assert!(store.time_entries(&running).is_empty());
+        let entries = store.time_entries(&TimeQuery { task_id: Some(*mail.id()), ..TimeQuery::default() });
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].ended_at().is_some());
+        assert!(store.start_timer(*mail.id()).is_ok());
+    }
 
-    #[derive(Debug, PartialEq)]
-    /// Used to define the current status of
-    /// a task. The done pattern should be used to
-    /// store the date that the task was set as done.
-    pub enum Status {
-        Done(String),
-        Todo,
+    #[test]
+    fn test_estimates_and_velocity() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("sprint".into(), None).unwrap();
+        let first = store.add_task("design".into(), *todo.id()).unwrap();
+        let second = store.add_task("build".into(), *todo.id()).unwrap();
+        store.add_task("unestimated".into(), *todo.id()).unwrap();
+
+        let task = store.update_task(*first.id(), None, None, Some(3.0)).unwrap();
+        assert_eq!(task.estimate(), Some(3.0));
+        store.set_task_estimate(*second.id(), Some(5.0)).unwrap();
+        assert!(store.set_task_estimate(*second.id(), Some(-1.0)).is_err());
+        assert!(store.update_task(*second.id(), None, None, Some(-1.0)).is_err());
+
+        let done = Status::Done("2022-06-21 10:00:00".into());
+        store.update_task(*first.id(), None, Some(done), None).unwrap();
+        assert_eq!(store.find_task(*first.id()).unwrap().estimate(), Some(3.0));
+
+        let progress = store.todo_progress(*todo.id());
+        assert_eq!((progress.effort, progress.remaining_effort()), (8.0, 5.0));
+
+        let rows = store.velocity("2022-01-01");
+        assert_eq!(rows, vec![VelocityRow { week: "2022-W25".into(), tasks: 1, effort: 3.0 }]);
+        assert!(store.velocity("2023-01-01").is_empty());
     }
 
-    impl<T: AsRef<str>> From<T> for Status {
-        fn from(date_completed: T) -> Self {
-            Status::Done(String::from(date_completed.as_ref()))
-        }
+    #[test]
+    fn test_agenda() {
+        let store = Store::in_memory().unwrap();
+        let todo = store.add_todo("agenda".into(), None).unwrap();
+        let ids: Vec<IdType> = ["late", "urgent", "minor", "done", "someday", "started"]
+            .iter()
+            .map(|what| *store.add_task(what.to_string(), *todo.id()).unwrap().id())
+            .collect();
+
+        store.set_task_due(ids[0], Some("2022-06-19")).unwrap();
+        store.set_task_due(ids[1], Some("2022-06-20")).unwrap();
+        store.set_task_due(ids[2], Some("2022-06-20")).unwrap();
+        store.set_task_due(ids[3], Some("2022-06-20")).unwrap();
+        store.set_task_due(ids[5], Some("2022-06-20")).unwrap();
+        assert!(store.set_task_due(ids[4], Some("tomorrow")).is_err());
+        store.set_task_priority(ids[1], Some(Priority::High)).unwrap();
+        store.set_task_priority(ids[2], Some(Priority::Low)).unwrap();
+        store.update_task(ids[3], None, Some(Status::Done("CURRENT_TIMESTAMP".into())), None).unwrap();
+        store.update_task(ids[5], None, Some(Status::InProgress("CURRENT_TIMESTAMP".into())), None).unwrap();
+
+        let task = store.find_task(ids[1]).unwrap();
+        assert_eq!((task.due().map(String::as_str), task.priority()), (Some("2022-06-20"), Some(Priority::High)));
+        assert_eq!("H".parse::<Priority>().unwrap(), Priority::High);
+
+        let today = store.agenda(Some("2022-06-20"), "2022-06-20");
+        assert_eq!(today.len(), 1);
+        let order: Vec<IdType> = today[0].tasks.iter().map(|task| *task.id()).collect();
+        assert_eq!(order, vec![ids[1], ids[2]]);
+
+        let overdue = store.agenda(None, "2022-06-19");
+        assert_eq!((overdue[0].due.as_str(), overdue[0].tasks.len()), ("2022-06-19", 1));
+        assert_eq!(store.agenda(None, "2022-06-26").len(), 2);
     }
 
-    #[derive(Debug, PartialEq)]
-    /// Todo is a structure used to store
-    /// a set of task to be done.
-    pub struct Todo {
-        id: IdType,
-        name: String,
-        description: Option<String>,
-        created_at: String, // TODO: Convert to datetime format
-        updated_at: String,
+    #[test]
+    fn test_config() {
+        let mut config = Config::parse(
+            r#"
+            database = "/tmp/todo.db"
+            sort = "priority"
+
+            [aliases]
+            t = "today"
+
+            [hooks]
+            on-complete = "notify.sh"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.database, std::path::PathBuf::from("/tmp/todo.db"));
+        assert_eq!((config.sort, config.format, config.backend), (SortOrder::Priority, OutputFormat::Text, Backend::Sqlite));
+        assert_eq!(config.aliases["t"], "today");
+        assert_eq!(config.hooks.on_complete, Some("notify.sh".into()));
+        assert!(Config::parse("colour = \"never\"").is_err());
+        assert!(Config::parse("date-format = \"%Q\"").is_err());
+
+        let vars = [("TODO_SORT", "due"), ("TODO_DATE_FORMAT", "%d/%m/%Y"), ("HOME", "/root")];
+        config.apply_env(vars.map(|(name, value)| (name.into(), value.into()))).unwrap();
+        assert_eq!(config.sort, SortOrder::Due);
+        assert_eq!(config.format_date("2022-06-20 10:00:00"), "20/06/2022");
+        assert!(config.set("color", "sometimes").is_err());
+
+        let mut tasks = vec![
+            Task::new(1, 1, "later", "", "", Status::Todo),
+            Task::new(2, 1, "sooner", "", "", Status::Todo),
+            Task::new(3, 1, "whenever", "", "", Status::Todo),
+        ];
+        tasks[0].set_due(Some("2022-06-21"));
+        tasks[1].set_due(Some("2022-06-20"));
+        config.sort.sort(&mut tasks);
+        assert_eq!(tasks.iter().map(|task| *task.id()).collect::<Vec<_>>(), vec![2, 1, 3]);
     }
 
-    #[derive(Debug, PartialEq)]
-    /// A task is something the user
-    /// wants or have to do. They are stored
-    /// inside the Todos.
-    pub struct Task {
-        id: IdType,
-        todo_id: IdType,
-        what: String,
-        created_at: String, // TODO: Convert to datetime format
-        updated_at: String,
-        status: Status,
+    #[test]
+    fn test_workspaces() {
+        let mut config = Config::parse(
+            r#"
+            database = "/tmp/todo.db"
+            workspace = "work"
+
+            [workspaces.work]
+            database = "/tmp/work.db"
+
+            [workspaces.home]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.active_database(), std::path::PathBuf::from("/tmp/work.db"));
+        assert!(config.active_dir().ends_with("workspaces/work"));
+        config.use_workspace("home").unwrap();
+        assert!(config.active_database().ends_with("workspaces/home.db"));
+        let files = config.workspace_files("home");
+        assert_eq!(files.len(), 3);
+        assert!(files[0].ends_with("workspaces/home.db"));
+        assert!(files[1].ends_with("workspaces/home.db-wal"));
+        assert!(config.workspace_files("work").is_empty());
+        config.backend = Backend::Files;
+        assert_eq!(config.workspace_files("work"), [config.workspace_dir("work")]);
+        config.backend = Backend::Sqlite;
+        config.use_workspace(Config::DEFAULT_WORKSPACE).unwrap();
+        assert_eq!(config.active_database(), std::path::PathBuf::from("/tmp/todo.db"));
+        assert_eq!(config.use_workspace("club").unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(Config::parse("workspace = \"club\"").is_err());
+
+        let path = std::env::temp_dir().join(format!("todo-workspaces-{}", std::process::id())).join("config.toml");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "# my settings\nsort = \"due\"\n").unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        file.create_workspace("client-a", Some(std::path::Path::new("/tmp/a.db"))).unwrap();
+        assert_eq!(file.create_workspace("client-a", None).unwrap_err().kind(), ErrorKind::AlreadyExists);
+        assert!(file.create_workspace("two words", None).is_err());
+        file.use_workspace("client-a").unwrap();
+        file.save().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("# my settings"));
+        let config = ConfigFile::open(&path).unwrap().config().unwrap();
+        assert_eq!(config.workspace.as_deref(), Some("client-a"));
+        assert_eq!(config.active_database(), std::path::PathBuf::from("/tmp/a.db"));
+
+        assert!(file.delete_workspace("client-a").is_err());
+        file.use_workspace(Config::DEFAULT_WORKSPACE).unwrap();
+        file.delete_workspace("client-a").unwrap();
+        assert!(file.config().unwrap().workspaces.is_empty());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
     }
 
-    impl Task {
-        pub fn new(
-            id: IdType,
-            todo_id: IdType,
-            what: &str,
-            created_at: &str,
-            updated_at: &str,
-            status: Status,
-        ) -> Self {
-            Self {
-                id,
-                todo_id,
-                status,
-                what: String::from(what),
-                created_at: String::from(created_at),
-                updated_at: String::from(updated_at),
-            }
+    #[test]
+    fn test_move_and_copy_tasks() {
+        let dir = std::env::temp_dir().join(format!("todo-move-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let stores = [
+            Store::in_memory().unwrap(),
+            Store::with_backend(Box::new(FileStorage::open(&dir).unwrap())),
+        ];
+
+        for store in stores {
+            let from = store.add_todo("from".into(), None).unwrap();
+            let to = store.add_todo("to".into(), None).unwrap();
+            let first = store.add_task("first".into(), *from.id()).unwrap();
+            let second = store.add_task("second".into(), *from.id()).unwrap();
+            let third = store.add_task("third".into(), *from.id()).unwrap();
+            store.set_task_priority(*second.id(), Some(Priority::High)).unwrap();
+            store.set_task_priority(*third.id(), Some(Priority::High)).unwrap();
+
+            let moved = store.move_task(*first.id(), *to.id()).unwrap();
+            assert_eq!((moved.todo_id(), moved.what().as_str()), (to.id(), "first"));
+            assert_eq!(store.todo_tasks(*to.id()).len(), 1);
+            assert_eq!(store.move_task(*first.id(), 99).unwrap_err().kind(), ErrorKind::NotFound);
+
+            store.undo(1).unwrap();
+            assert_eq!(store.find_task(*first.id()).unwrap().todo_id(), from.id());
+
+            let copy = store.copy_task(*second.id(), *to.id()).unwrap();
+            assert_ne!(copy.id(), second.id());
+            assert_eq!((copy.priority(), copy.status()), (Some(Priority::High), &Status::Todo));
+            assert_eq!(store.todo_tasks(*from.id()).len(), 3);
+
+            let query = TaskQuery {
+                todo_id: Some(*from.id()),
+                priority: Some(Priority::High),
+                ..TaskQuery::default()
+            };
+            assert_eq!(store.move_tasks(&query, *to.id()).unwrap().len(), 2);
+            assert_eq!(store.todo_tasks(*from.id()).len(), 1);
+            assert_eq!(store.todo_tasks(*to.id()).len(), 3);
+            store.delete_todo(*from.id()).unwrap();
+            assert!(store.move_tasks(&TaskQuery::default(), *from.id()).is_err());
         }
 
-        pub fn set_status(&mut self, status: Status) {
-            self.status = status;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_views_and_filters() {
+        let dir = std::env::temp_dir().join(format!("todo-views-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let stores = [
+            Store::in_memory().unwrap(),
+            Store::with_backend(Box::new(FileStorage::open(&dir).unwrap())),
+        ];
+
+        for store in stores {
+            store.save_view("stale", "--status todo --older-than 30d").unwrap();
+            store.save_view("urgent", "--priority high").unwrap();
+            store.save_view("stale", "--status todo").unwrap();
+            assert!(store.save_view("2", "--status done").is_err());
+            assert!(store.save_view("two words", "--status done").is_err());
+
+            assert_eq!(store.views().len(), 2);
+            assert_eq!(store.find_view("stale").unwrap().args(), "--status todo");
+            store.delete_view("urgent").unwrap();
+            assert_eq!(store.find_view("urgent").unwrap_err().kind(), ErrorKind::NotFound);
+            assert!(store.delete_view("urgent").is_err());
+
+            let todo = store.add_todo("filters".into(), None).unwrap();
+            let first = store.add_task("first".into(), *todo.id()).unwrap();
+            let second = store.add_task("second".into(), *todo.id()).unwrap();
+            store.set_task_priority(*first.id(), Some(Priority::High)).unwrap();
+            store.update_task(*second.id(), None, Some(Status::InProgress("CURRENT_TIMESTAMP".into())), None).unwrap();
+
+            let find = |query: TaskQuery| -> Vec<IdType> {
+                store.query_tasks(&query).iter().map(|task| *task.id()).collect()
+            };
+            assert_eq!(find(TaskQuery { status: Some("in_progress".into()), ..TaskQuery::default() }), vec![*second.id()]);
+            assert_eq!(find(TaskQuery { priority: Some(Priority::High), ..TaskQuery::default() }), vec![*first.id()]);
+            assert!(find(TaskQuery { created_before: Some("2000-01-01".into()), ..TaskQuery::default() }).is_empty());
         }
 
-        pub fn set_updated_at(&mut self, datetime: &str) {
-            self.updated_at = String::from(datetime);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("todo-hooks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let script = |name: &str, body: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            Some(path)
+        };
+        let hooks = Hooks {
+            // Prints the new task, shouting the text of the one to ship.
+            on_add: script("add", r#"sed -e 's/.*"new":\(.*\)}$/\1/' -e 's/"what":"ship it"/"what":"SHIP IT"/'"#),
+            on_complete: script("complete", "grep -q '\"what\":\"SHIP IT\"' && exit 0; echo not ready; exit 1"),
+            on_delete: script("delete", "echo keep it; exit 2"),
+            on_modify: script("modify", r#"grep -q '"archived_at":"' && { echo not yet; exit 1; }; exit 0"#),
+        };
+
+        let store = Store::in_memory().unwrap().with_hooks(hooks);
+        let todo = store.add_todo("hooked".into(), None).unwrap();
+        let task = store.add_task("ship it".into(), *todo.id()).unwrap();
+        assert_eq!(task.what(), "SHIP IT");
+        assert_eq!(store.find_task(*task.id()).unwrap().what(), "SHIP IT");
+
+        let err = store.delete_task(*task.id()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("keep it"));
+        assert!(store.find_task(*task.id()).is_ok());
+
+        let other = store.add_task("wait".into(), *todo.id()).unwrap();
+        let done = Status::Done("CURRENT_TIMESTAMP".into());
+        assert!(store.update_task(*other.id(), None, Some(done.clone()), None).is_err());
+        assert_eq!(store.find_task(*other.id()).unwrap().status(), &Status::Todo);
+        assert!(store.update_task(*task.id(), None, Some(done), None).is_ok());
+
+        assert!(store.archive_todo(*todo.id()).unwrap_err().to_string().contains("not yet"));
+        assert!(store.find_todo(*todo.id()).unwrap().archived_at().is_none());
+
+        // The hook may set another status the task could have become, but not move it.
+        let hooks = Hooks {
+            on_complete: script(
+                "cancel",
+                r#"sed -e 's/.*"new":\(.*\)}$/\1/' -e 's/"status":"done"/"status":"cancelled"/' -e 's/"completed_at"/"cancelled_at"/'"#,
+            ),
+            on_modify: script("steal", r#"sed -e 's/.*"new":\(.*\)}$/\1/' -e 's/"todo_id":[0-9]*/"todo_id":999/'"#),
+            ..Hooks::default()
+        };
+        let store = Store::in_memory().unwrap().with_hooks(hooks);
+        let todo = store.add_todo("rewritten".into(), None).unwrap();
+        let task = store.add_task("maybe".into(), *todo.id()).unwrap();
+        let task = store.update_task(*task.id(), None, Some(Status::Done("CURRENT_TIMESTAMP".into())), None).unwrap();
+        assert_eq!(task.status().name(), "cancelled");
+        let err = store.update_task(*task.id(), Some("mine".into()), None, None).unwrap_err();
+        assert!(err.to_string().contains("ids or dates"), "{}", err);
+        assert_eq!(store.find_task(*task.id()).unwrap().todo_id(), todo.id());
+
+        // Long input and output at once don't leave both sides waiting.
+        let hooks = Hooks {
+            on_modify: script("flood", r"head -c 200000 /dev/zero | tr '\0' x; echo; cat > /dev/null; exit 1"),
+            ..Hooks::default()
+        };
+        let store = Store::in_memory().unwrap().with_hooks(hooks);
+        let todo = store.add_todo("flooded".into(), None).unwrap();
+        let task = store.add_task("y".repeat(200000), *todo.id()).unwrap();
+        let err = store.update_task(*task.id(), Some("z".repeat(200000)), None, None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stats() {
+        let task = |id, created_at: &str, status| {
+            Task::new(id, 1, "task", created_at, created_at, status)
+        };
+        let tasks = vec![
+            task(1, "2022-06-20 08:00:00", Status::Done("2022-06-20 10:00:00".into())),
+            task(2, "2022-06-20 09:00:00", Status::Done("2022-06-22 09:00:00".into())),
+            task(3, "2022-06-21 09:00:00", Status::Todo),
+            task(4, "2022-06-22 09:00:00", Status::Cancelled("2022-06-22 10:00:00".into())),
+        ];
+        let todos = vec![Todo::new(1, "stats".into(), None, "".into(), "".into())];
+
+        let stats = Stats::compute(&todos, &tasks, Period::Day, 1);
+        assert_eq!(stats.per_period.len(), 3);
+        assert_eq!((stats.per_period[0].created, stats.per_period[0].completed), (2, 1));
+        assert_eq!(stats.average_completion(), Some(Duration::from_secs(25 * 3600)));
+        assert_eq!(stats.oldest_open, vec![tasks[2].clone()]);
+        assert_eq!((stats.todos[0].done, stats.todos[0].total), (2, 3));
+
+        let weekly = Stats::compute(&todos, &tasks, Period::Week, 5);
+        assert_eq!(weekly.per_period[0].period, "2022-W25");
+
+        let today = chrono::NaiveDate::from_ymd_opt(2022, 6, 23).unwrap();
+        let points = burndown(&tasks, Period::Day, today);
+        let remaining: Vec<usize> = points.iter().map(|p| p.remaining).collect();
+        assert_eq!(remaining, vec![1, 2, 1, 1]);
+        assert!(burndown_chart(&points, 2).starts_with("   2 | #  \n"));
+    }
+
+    #[test]
+    fn test_sqlite_adds_new_columns() {
+        let path = temp_db_path("columns");
+        remove_db(&path);
+
+        let db = Database::new(&path);
+        db.write(|c| {
+            c.execute(
+                "CREATE TABLE todos(id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL,
+                description TEXT, created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP);
+                INSERT INTO todos(name) VALUES ('old');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        drop(db);
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.todos()[0].name(), "old");
+        store.delete_todo(1).unwrap();
+        assert_eq!(store.trashed_todos().len(), 1);
+
+        drop(store);
+        remove_db(&path);
+    }
+
+    /// A storage keeping everything in vectors, to check
+    /// that other backends can be plugged into a store.
+    #[derive(Default)]
+    struct VecStorage {
+        todos: std::sync::Mutex<Vec<Todo>>,
+        tasks: std::sync::Mutex<Vec<Task>>,
+        history: std::sync::Mutex<Vec<HistoryEntry>>,
+        journal: std::sync::Mutex<Vec<JournalEntry>>,
+    }
+
+    impl Storage for VecStorage {
+        fn init(&self) -> Result<(), InternalError> {
+            Ok(())
         }
 
-        pub fn set_what(&mut self, what_new: &str) {
-            self.what = String::from(what_new);
+        fn todos(&self) -> Result<Vec<Todo>, InternalError> {
+            Ok(self.todos.lock().unwrap().clone())
         }
 
-        /// References the task's id.
-        pub fn id(&self) -> &IdType {
-            &self.id
+        fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            let todos = self.todos.lock().unwrap();
+            let todo = todos.iter().find(|t| *t.id() == id).cloned();
+            todo.ok_or_else(|| InternalError::not_found("todo", id))
         }
 
-        pub fn todo_id(&self) -> &IdType {
-            &self.todo_id
+        fn insert_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            let mut todos = self.todos.lock().unwrap();
+            let id = todos.len() as IdType + 1;
+            let todo = Todo::new(id, todo.name().clone(), None, "then".into(), "now".into());
+            todos.push(todo.clone());
+            Ok(todo)
         }
 
-        pub fn created_at(&self) -> &String {
-            &self.created_at
+        fn update_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.delete_todo(*todo.id())?;
+            self.todos.lock().unwrap().push(todo.clone());
+            Ok(todo)
         }
 
-        pub fn updated_at(&self) -> &String {
-            &self.updated_at
+        fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            self.find_todo(id)?;
+            self.todos.lock().unwrap().retain(|t| *t.id() != id);
+            Ok(())
         }
 
-        /// Reference the status of the task.
-        pub fn status(&self) -> &Status {
-            &self.status
+        fn tasks(&self) -> Result<Vec<Task>, InternalError> {
+            Ok(self.tasks.lock().unwrap().clone())
         }
 
-        /// References the task of the Task struct.
-        pub fn what(&self) -> &String {
-            &self.what
+        fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            let tasks = self.tasks.lock().unwrap();
+            let task = tasks.iter().find(|t| *t.id() == id).cloned();
+            task.ok_or_else(|| InternalError::not_found("task", id))
         }
-    }
 
-    impl Todo {
-        pub fn new(
-            id: IdType,
-            name: String,
-            description: Option<String>,
-            created_at: String,
-            updated_at: String,
-        ) -> Self {
-            Self {
-                id,
-                name,
-                description,
-                created_at,
-                updated_at,
-            }
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError> {
+            let mut tasks = self.tasks.lock().unwrap();
+            let id = tasks.len() as IdType + 1;
+            let task = Task::new(id, *task.todo_id(), task.what(), "then", "now", Status::Todo);
+            tasks.push(task.clone());
+            Ok(task)
         }
 
-        /// Sets a new value to the name.
-        pub fn set_name(&mut self, name: &str) {
-            self.name = String::from(name);
+        fn update_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.delete_task(*task.id())?;
+            self.tasks.lock().unwrap().push(task.clone());
+            Ok(task)
         }
 
-        /// Sets a new value to the description.
-        pub fn set_description(&mut self, description: &str) {
-            self.description = Some(String::from(description));
+        fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            self.find_task(id)?;
+            self.tasks.lock().unwrap().retain(|t| *t.id() != id);
+            Ok(())
         }
 
-        pub fn set_updated_at(&mut self, datetime: &str) {
-            self.updated_at = String::from(datetime);
+        fn insert_history(&self, entry: HistoryEntry) -> Result<HistoryEntry, InternalError> {
+            let mut history = self.history.lock().unwrap();
+            let entry = entry.with_id(history.len() as IdType + 1);
+            history.push(entry.clone());
+            Ok(entry)
         }
 
-        /// Reference the id.
-        pub fn id(&self) -> &IdType {
-            &self.id
+        fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, InternalError> {
+            let history = self.history.lock().unwrap();
+            Ok(history.iter().filter(|entry| query.matches(entry)).cloned().collect())
         }
 
-        /// References the name.
-        pub fn name(&self) -> &String {
-            &self.name
+        fn insert_journal(&self, entry: JournalEntry) -> Result<JournalEntry, InternalError> {
+            let mut journal = self.journal.lock().unwrap();
+            let entry = entry.with_id(journal.len() as IdType + 1);
+            journal.push(entry.clone());
+            Ok(entry)
         }
 
-        /// References the description.
-        pub fn description(&self) -> Option<&String> {
-            self.description.as_ref()
+        fn journal(&self) -> Result<Vec<JournalEntry>, InternalError> {
+            Ok(self.journal.lock().unwrap().clone())
+        }
+
+        fn transaction(
+            &self,
+            operation: &mut dyn FnMut(&dyn Storage) -> Result<(), InternalError>,
+        ) -> Result<(), InternalError> {
+            operation(self)
         }
     }
-}
 
-mod data_access_layer {
-    use super::core::*;
-    use super::database::*;
+    #[test]
+    fn test_store_with_backend() {
+        let store = Store::with_backend(Box::<VecStorage>::default());
+
+        let todo = store.add_todo("vec".into(), None).unwrap();
+        store.add_task("in a vec".into(), *todo.id()).unwrap();
+        let renamed = store.update_todo(*todo.id(), Some("vector".into()), None).unwrap();
+
+        assert_eq!(renamed.name(), "vector");
+        assert_eq!(store.todo_tasks(*todo.id()).len(), 1);
+        assert!(store.add_task("orphan".into(), 42).is_err());
+
+        // The changes are recorded by the storage
+        let history = store.history(&HistoryQuery { entity_id: Some(*todo.id()), ..HistoryQuery::default() });
+        assert!(history.iter().any(|entry| entry.new_value().is_some_and(|value| value == "vector")));
+        // Found in the journal, but the storage can't restore todos
+        assert!(store.undo(1).is_err());
+    }
 
-    trait BasicDataModelTrait: DatabaseConnectorTrait {
-        type ObjType;
+    /// A path for a database file, unique to the test and the process.
+    #[test]
+    fn test_file_storage() {
+        let dir = std::env::temp_dir().join(format!("todo-files-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let store = Store::with_backend(Box::new(FileStorage::open(&dir).unwrap()));
+        let todo = store.add_todo("files".into(), Some("kept in json".into())).unwrap();
+        let task = store.add_task("write".into(), *todo.id()).unwrap();
+        let other = store.add_todo("other".into(), None).unwrap();
+        store.add_task("read".into(), *other.id()).unwrap();
+
+        let task = store
+            .update_task(*task.id(), None, Some(Status::Done("CURRENT_TIMESTAMP".into())), None)
+            .unwrap();
+        assert_ne!(task.status(), &Status::Done("CURRENT_TIMESTAMP".into()));
+        assert!(store.add_task("orphan".into(), 99).is_err());
+
+        let res: Result<(), InternalError> = store.transaction(|storage| {
+            storage.delete_todo(*todo.id())?;
+            Err(InternalError::new("abort"))
+        });
+        assert!(res.is_err());
+
+        // Everything must be read back from the files
+        let store = Store::with_backend(Box::new(FileStorage::open(&dir).unwrap()));
+        assert_eq!(store.todos().len(), 2);
+        assert_eq!(store.find_task(*task.id()).unwrap(), task);
+        assert_eq!(store.todo_tasks(*todo.id()).len(), 1);
+
+        store.delete_task(*task.id()).unwrap();
+        store.delete_todo(*other.id()).unwrap();
+        assert!(store.todo_tasks(*todo.id()).is_empty());
+        assert_eq!(store.tasks().len(), 0);
+
+        let new = store.add_todo("new".into(), None).unwrap();
+        assert!(new.id() > other.id());
+
+        store.undo(2).unwrap();
+        assert_eq!(store.find_todo(*other.id()).unwrap(), other);
+        assert_eq!(store.todo_tasks(*other.id()).len(), 1);
+
+        let history = store.history(&HistoryQuery {
+            entity: Some(Entity::Task),
+            entity_id: Some(*task.id()),
+            ..HistoryQuery::default()
+        });
+        let actions: Vec<Action> = history.iter().map(|entry| entry.action()).collect();
+        assert_eq!(actions.first(), Some(&Action::Add));
+        assert_eq!(actions.last(), Some(&Action::Delete));
+        assert!(history.iter().all(|entry| entry.timestamp() != "CURRENT_TIMESTAMP"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-        fn all() -> Vec<Self::ObjType>;
-        fn find(id: IdType) -> Result<Self::ObjType, InternalError>;
-        fn add(obj: Self::ObjType) -> Result<Self::ObjType, InternalError>;
-        fn update(obj: Self::ObjType) -> Result<Self::ObjType, InternalError>;
-        fn delete(id: IdType) -> Result<(), InternalError>;
+    #[test]
+    fn test_file_storage_recovery() {
+        let dir = std::env::temp_dir().join(format!("todo-recovery-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let store = Store::with_backend(Box::new(FileStorage::open(&dir).unwrap()));
+        let first = store.add_todo("first".into(), None).unwrap();
+        let second = store.add_todo("second".into(), None).unwrap();
+        let task = store.add_task("moved".into(), *first.id()).unwrap();
+        let before = std::fs::read(dir.join(format!("{}.json", first.id()))).unwrap();
+        let history_len = std::fs::metadata(dir.join("history.jsonl")).unwrap().len();
+        store.move_task(*task.id(), *second.id()).unwrap();
+
+        // A commit stopped before the first todo was replaced, leaving the task in both
+        let name = format!("{}.json", first.id());
+        std::fs::rename(dir.join(&name), dir.join(".first.tmp")).unwrap();
+        std::fs::write(dir.join(&name), before).unwrap();
+        let commit = serde_json::json!({
+            "files": [[".first.tmp", name]],
+            "removed": [],
+            "history_len": history_len,
+            "history": "",
+        });
+        std::fs::write(dir.join(".commit.json"), commit.to_string()).unwrap();
+
+        let store = Store::with_backend(Box::new(FileStorage::open(&dir).unwrap()));
+        assert!(store.todo_tasks(*first.id()).is_empty());
+        assert_eq!(store.todo_tasks(*second.id()).len(), 1);
+        assert!(!dir.join(".commit.json").exists());
+
+        // The ids of the history keep following each other
+        store.add_task("more".into(), *first.id()).unwrap();
+        let ids: Vec<IdType> = store
+            .history(&HistoryQuery::default())
+            .iter()
+            .map(|entry| *entry.id())
+            .collect();
+        assert_eq!(ids, (1..=ids.len() as IdType).collect::<Vec<_>>());
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
-    struct TodoModel;
-    struct TaskModel;
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_store() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let store = std::sync::Arc::new(Store::in_memory().unwrap());
+        let store = AsyncStore::with_queue_size(store, 1);
+
+        runtime.block_on(async {
+            let todo = store.add_todo("async".into(), None).await.unwrap();
+            // More operations than the queue holds, waiting for each other
+            let (a, b, c) = tokio::join!(
+                store.add_task("a".into(), *todo.id()),
+                store.add_task("b".into(), *todo.id()),
+                store.add_task("c".into(), *todo.id()),
+            );
+            assert!(a.is_ok() && b.is_ok() && c.is_ok());
 
-    impl DatabaseConnectorTrait for TodoModel {
-        fn table_name() -> &'static str {
-            "todos"
+            assert_eq!(store.todo_tasks(*todo.id()).await.len(), 3);
+            assert_eq!(
+                store.add_task("orphan".into(), 99).await.unwrap_err().kind(),
+                ErrorKind::NotFound
+            );
+
+            let id = *todo.id();
+            let res: Result<(), InternalError> = store
+                .transaction(move |storage| {
+                    storage.delete_todo(id)?;
+                    Err(InternalError::new("abort"))
+                })
+                .await;
+            assert!(res.is_err());
+            assert!(store.find_todo(*todo.id()).await.is_ok());
+        });
+    }
+
+    fn temp_db_path(name: &str) -> String {
+        let file = format!("todo-{}-{}.db", name, std::process::id());
+        std::env::temp_dir().join(file).to_string_lossy().into()
+    }
+
+    fn remove_db(path: &str) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, suffix));
         }
+    }
 
-        fn init_table() -> Result<(), sqlite::Error> {
-            if !Self::is_table_initialized() {
-                let sttmt = format!(
-                    "{}(
-                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
-                    name TEXT NOT NULL,
-                    description TEXT,
-                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP);",
-                    Self::table_name()
-                );
+    #[test]
+    fn test_database_write_rolls_back() {
+        let db = Database::new(":memory:");
+        db.write(|c| Ok(c.execute("CREATE TABLE t(x INTEGER);")?)).unwrap();
+
+        let res: Result<(), InternalError> = db.write(|c| {
+            execute(c, "INSERT INTO t(x) VALUES (?);", &[sqlite::Value::Integer(1)])?;
+            Err(InternalError::new("abort"))
+        });
+        assert!(res.is_err());
+
+        let rows = db.read(|c| Ok(query(c, "SELECT x FROM t;", &[])?)).unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn test_database_pool() {
+        let path = temp_db_path("pool");
+        let db = Database::with_pool_size(&path, 3);
+
+        let connections: Vec<_> = (0..3).map(|_| db.connection().unwrap()).collect();
+        let mode = query(&connections[2], "PRAGMA journal_mode;", &[]).unwrap();
+        assert_eq!(mode[0][0].as_string(), Some("wal"));
+
+        drop(connections);
+        assert_eq!(db.pool_size(), 3);
+        assert_eq!(Database::new(":memory:").pool_size(), 1);
+
+        remove_db(&path);
+    }
+
+    /// Multi-threaded load test of the connection pool, run it with
+    /// `cargo test --release -- --ignored --nocapture test_pool_load`.
+    #[test]
+    #[ignore]
+    fn test_pool_load() {
+        use std::sync::Arc;
+        use std::time::Instant;
+
+        const THREADS: usize = 8;
+        const OPERATIONS: usize = 2000;
+
+        for size in [1, POOL_SIZE] {
+            let path = temp_db_path(&format!("load-{}", size));
+            let db = Arc::new(Database::with_pool_size(&path, size));
+
+            db.write(|c| Ok(c.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, x TEXT);")?))
+                .unwrap();
+
+            let start = Instant::now();
+            let workers: Vec<_> = (0..THREADS)
+                .map(|n| {
+                    let db = Arc::clone(&db);
+                    std::thread::spawn(move || {
+                        for i in 0..OPERATIONS {
+                            // One write for every nine reads.
+                            if i % 10 == 0 {
+                                db.write(|c| {
+                                    let value = sqlite::Value::String(format!("{}-{}", n, i));
+                                    Ok(execute(c, "INSERT INTO t(x) VALUES (?);", &[value])?)
+                                })
+                                .unwrap();
+                            } else {
+                                db.read(|c| Ok(query(c, "SELECT COUNT(*) FROM t;", &[])?))
+                                    .unwrap();
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            for worker in workers {
+                worker.join().unwrap();
+            }
+
+            let elapsed = start.elapsed();
+            println!(
+                "pool size {:>2}: {} operations in {:?} ({:.0} ops/s)",
+                size,
+                THREADS * OPERATIONS,
+                elapsed,
+                (THREADS * OPERATIONS) as f64 / elapsed.as_secs_f64()
+            );
+
+            drop(db);
+            remove_db(&path);
+        }
+    }
+
+    fn press(state: &mut TuiState, keys: &str) {
+        for c in keys.chars() {
+            state.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_tui_filter_and_toggle() {
+        let store = std::sync::Arc::new(Store::in_memory().unwrap());
+        let todo = store.add_todo("tui filtering".into(), None).unwrap();
+        let task = store.add_task("toggle in the tui".into(), *todo.id()).unwrap();
+        store.add_todo("filtered out".into(), None).unwrap();
+
+        let mut state = TuiState::with_store(store.clone());
+        assert_eq!(state.todos().len(), 2);
+        press(&mut state, "/tui filtering");
+        state.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(*state.mode(), Mode::Normal);
+        assert_eq!(state.todos().len(), 1);
+        assert_eq!(state.tasks().len(), 1);
+
+        state.handle_key(KeyEvent::from(KeyCode::Tab));
+        assert_eq!(state.focus(), Pane::Tasks);
+
+        press(&mut state, " ");
+        assert_ne!(*store.find_task(*task.id()).unwrap().status(), Status::Todo);
+
+        press(&mut state, " ");
+        assert_eq!(*store.find_task(*task.id()).unwrap().status(), Status::Todo);
+    }
+
+    #[test]
+    fn test_tui_edit_and_delete() {
+        let store = std::sync::Arc::new(Store::in_memory().unwrap());
+        let todo = store.add_todo("tui editing".into(), Some("soon".into())).unwrap();
+
+        let mut state = TuiState::with_store(store.clone());
+        press(&mut state, "/tui editing");
+        state.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        press(&mut state, "e!");
+        state.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(store.find_todo(*todo.id()).unwrap().name(), "tui editing!");
+
+        // Emptying the description removes it
+        press(&mut state, "E");
+        for _ in 0.."soon".len() {
+            state.handle_key(KeyEvent::from(KeyCode::Backspace));
+        }
+        state.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(store.find_todo(*todo.id()).unwrap().description(), None);
+
+        press(&mut state, "dn");
+        assert!(store.find_todo(*todo.id()).is_ok());
+
+        press(&mut state, "dy");
+        assert!(store.find_todo(*todo.id()).is_err());
+    }
+}
+
+pub mod prelude {
+    #[cfg(feature = "async")]
+    pub use super::async_api::*;
+    pub use super::config::*;
+    pub use super::core::*;
+    pub use super::csv_io::*;
+    pub use super::data_access_layer::*;
+    pub use super::database::Database;
+    pub use super::file_storage::*;
+    pub use super::history::*;
+    pub use super::hooks::*;
+    pub use super::journal::*;
+    pub use super::storage::*;
+    pub use super::server::*;
+    pub use super::stats::*;
+    pub use super::time_tracking::*;
+    pub use super::tui::{run_tui, TuiState};
+    pub use super::views::*;
+}
+
+mod database {
+    use super::core::*;
+    use sqlite::{self, Connection, Value};
+    use std::ops::Deref;
+    use std::sync::{Condvar, Mutex};
+    use std::thread;
+
+    /// Number of connections kept by a file-backed database.
+    pub const POOL_SIZE: usize = 8;
+
+    /// How long a connection waits for a lock held by another one.
+    const BUSY_TIMEOUT_MS: usize = 5000;
 
-                DB.lock().unwrap().create_table(&sttmt)
+    struct Pool {
+        idle: Vec<Connection>,
+        opened: usize,
+    }
+
+    /// Database handler for the aplication. It keeps a pool
+    /// of connections, so readers don't block each other.
+    pub struct Database {
+        path: String,
+        size: usize,
+        pool: Mutex<Pool>,
+        released: Condvar,
+    }
+
+    /// A connection borrowed from the pool of a database,
+    /// it is given back to the pool when dropped.
+    pub struct PooledConnection<'a> {
+        database: &'a Database,
+        connection: Option<Connection>,
+    }
+
+    impl Deref for PooledConnection<'_> {
+        type Target = Connection;
+
+        fn deref(&self) -> &Connection {
+            self.connection.as_ref().unwrap()
+        }
+    }
+
+    impl Drop for PooledConnection<'_> {
+        fn drop(&mut self) {
+            if let Some(connection) = self.connection.take() {
+                if thread::panicking() {
+                    // Don't give back a connection in the middle of a transaction.
+                    let _ = connection.execute("ROLLBACK;");
+                }
+
+                self.database.release(connection);
+            }
+        }
+    }
+
+    impl From<&str> for Database {
+        /// Initializes the database from a given path
+        fn from(path: &str) -> Self {
+            Self::new(path)
+        }
+    }
+
+    impl From<sqlite::Error> for InternalError {
+        fn from(e: sqlite::Error) -> Self {
+            InternalError::new(&e.to_string())
+        }
+    }
+
+    impl Database {
+        pub fn new(path: &str) -> Self {
+            Self::with_pool_size(path, Self::default_pool_size(path))
+        }
+
+        /// Initializes the database keeping at most `size` connections open.
+        pub fn with_pool_size(path: &str, size: usize) -> Self {
+            Self::open(path, size).unwrap()
+        }
+
+        /// Opens the database keeping at most `size` connections open,
+        /// failing if the first connection can't be opened.
+        pub fn open(path: &str, size: usize) -> Result<Self, sqlite::Error> {
+            let database = Self {
+                path: String::from(path),
+                size: size.max(1),
+                pool: Mutex::new(Pool {
+                    idle: Vec::new(),
+                    opened: 1,
+                }),
+                released: Condvar::new(),
+            };
+
+            let connection = database.open_connection()?;
+            database.pool.lock().unwrap().idle.push(connection);
+            Ok(database)
+        }
+
+        /// The number of connections used by default for the given path.
+        pub fn default_pool_size(path: &str) -> usize {
+            // Each connection to an in-memory database sees a different
+            // database, so only one connection can be used for them.
+            if Self::is_in_memory(path) {
+                1
             } else {
-                Ok(())
+                POOL_SIZE
+            }
+        }
+
+        fn is_in_memory(path: &str) -> bool {
+            path.is_empty() || path == ":memory:"
+        }
+
+        /// References the path of the db
+        pub fn path(&self) -> &String {
+            &self.path
+        }
+
+        /// The maximum number of connections opened at the same time.
+        pub fn pool_size(&self) -> usize {
+            self.size
+        }
+
+        fn open_connection(&self) -> Result<Connection, sqlite::Error> {
+            let mut connection = Connection::open(&self.path)?;
+            connection.set_busy_timeout(BUSY_TIMEOUT_MS)?;
+
+            if !Self::is_in_memory(&self.path) {
+                connection.execute("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+            }
+
+            Ok(connection)
+        }
+
+        fn release(&self, connection: Connection) {
+            self.pool.lock().unwrap().idle.push(connection);
+            self.released.notify_one();
+        }
+
+        /// Takes a connection from the pool, waiting
+        /// for one to be released if all are in use.
+        pub fn connection(&self) -> Result<PooledConnection<'_>, sqlite::Error> {
+            let mut pool = self.pool.lock().unwrap();
+
+            loop {
+                if let Some(connection) = pool.idle.pop() {
+                    return Ok(PooledConnection {
+                        database: self,
+                        connection: Some(connection),
+                    });
+                }
+
+                if pool.opened < self.size {
+                    pool.opened += 1;
+                    drop(pool);
+
+                    return match self.open_connection() {
+                        Ok(connection) => Ok(PooledConnection {
+                            database: self,
+                            connection: Some(connection),
+                        }),
+                        Err(e) => {
+                            self.pool.lock().unwrap().opened -= 1;
+                            Err(e)
+                        }
+                    };
+                }
+
+                pool = self.released.wait(pool).unwrap();
+            }
+        }
+
+        /// Runs a read only operation on a connection of the pool.
+        pub fn read<T, F>(&self, operation: F) -> Result<T, InternalError>
+        where
+            F: FnOnce(&Connection) -> Result<T, InternalError>,
+        {
+            let connection = self.connection()?;
+            operation(&connection)
+        }
+
+        /// Runs an operation inside of a transaction, holding the same
+        /// connection until it finishes. The changes are rolled back if
+        /// the operation fails.
+        ///
+        /// The operation must only use the connection it is given, asking
+        /// the pool for another one may deadlock.
+        pub fn write<T, F>(&self, operation: F) -> Result<T, InternalError>
+        where
+            F: FnOnce(&Connection) -> Result<T, InternalError>,
+        {
+            let connection = self.connection()?;
+            connection.execute("BEGIN IMMEDIATE;")?;
+
+            let res = operation(&connection)
+                .and_then(|value| connection.execute("COMMIT;").map(|_| value).map_err(From::from));
+
+            if res.is_err() {
+                let _ = connection.execute("ROLLBACK;");
+            }
+
+            res
+        }
+    }
+
+    /// Runs a statement with the given values bound to its
+    /// parameters and collects the rows of its result.
+    pub fn query(
+        connection: &Connection,
+        statement: &str,
+        values: &[Value],
+    ) -> Result<Vec<Vec<Value>>, sqlite::Error> {
+        let mut cursor = connection.prepare(statement)?.into_cursor();
+        cursor.bind(values)?;
+
+        let mut rows = Vec::new();
+
+        while let Some(row) = cursor.next()? {
+            rows.push(row.to_vec());
+        }
+
+        Ok(rows)
+    }
+
+    /// Runs a statement with the given values bound to its parameters.
+    pub fn execute(
+        connection: &Connection,
+        statement: &str,
+        values: &[Value],
+    ) -> Result<(), sqlite::Error> {
+        query(connection, statement, values).map(|_| ())
+    }
+
+    /// The id given to the last row inserted through the connection.
+    pub fn last_insert_id(connection: &Connection) -> Result<IdType, sqlite::Error> {
+        let rows = query(connection, "SELECT last_insert_rowid();", &[])?;
+        Ok(rows[0][0].as_integer().unwrap_or_default() as IdType)
+    }
+
+    /// Converts an optional text into a value that can be bound.
+    pub fn text(value: Option<&String>) -> Value {
+        value
+            .map(|value| Value::String(value.clone()))
+            .unwrap_or(Value::Null)
+    }
+
+    pub trait DatabaseConnectorTrait {
+        fn table_name() -> &'static str;
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error>;
+
+        fn is_table_initialized(connection: &Connection) -> bool {
+            let statement = format!("SELECT * FROM {} LIMIT 1;", Self::table_name());
+
+            connection.execute(&statement).is_ok()
+        }
+
+        /// Adds a column to a table created by an older version, which lacks it.
+        fn ensure_column(
+            connection: &Connection,
+            column: &str,
+            declaration: &str,
+        ) -> Result<(), sqlite::Error> {
+            let statement = format!("PRAGMA table_info({});", Self::table_name());
+            let columns = query(connection, &statement, &[])?;
+
+            if columns.iter().any(|row| row[1].as_string() == Some(column)) {
+                return Ok(());
+            }
+
+            let statement = format!(
+                "ALTER TABLE {} ADD COLUMN {} {};",
+                Self::table_name(),
+                column,
+                declaration
+            );
+            connection.execute(&statement)
+        }
+    }
+}
+
+mod core {
+    use serde::{Deserialize, Serialize};
+    pub use std::error::Error;
+    use std::fmt::{self, Display};
+    use std::str::FromStr;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    /// Broad category of an error, used by the
+    /// front-ends to decide how to report it.
+    pub enum ErrorKind {
+        NotFound,
+        AlreadyExists,
+        InvalidInput,
+        Other,
+    }
+
+    #[derive(Debug)]
+    pub struct InternalError {
+        kind: ErrorKind,
+        details: String,
+    }
+
+    impl InternalError {
+        pub fn new(details: &str) -> Self {
+            Self::with_kind(ErrorKind::Other, details)
+        }
+
+        pub fn with_kind(kind: ErrorKind, details: &str) -> Self {
+            Self {
+                kind,
+                details: String::from(details),
+            }
+        }
+
+        pub fn table_not_initialized(name: &str) -> Self {
+            let details = format!("table '{}' was not initialed!", name);
+            Self::new(&details)
+        }
+
+        pub fn not_found(what: &str, id: IdType) -> Self {
+            let details = format!("{} with id = {} was not found in the database.", what, id);
+            Self::with_kind(ErrorKind::NotFound, &details)
+        }
+
+        pub fn already_exists(what: &str, id: IdType) -> Self {
+            let details = format!("{} with id = {}, is already in use in the table", what, id);
+            Self::with_kind(ErrorKind::AlreadyExists, &details)
+        }
+
+        pub fn invalid_input(details: &str) -> Self {
+            Self::with_kind(ErrorKind::InvalidInput, details)
+        }
+
+        /// The category of the error.
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl Display for InternalError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.details)
+        }
+    }
+
+    impl Error for InternalError {
+        fn description(&self) -> &str {
+            &self.details
+        }
+    }
+
+    /// The type of the IDs used on the program.
+    pub type IdType = u64;
+
+    /// Format of the dates, the same used by sqlite's `CURRENT_TIMESTAMP`.
+    pub const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    /// The current date, in UTC like sqlite's `CURRENT_TIMESTAMP`.
+    pub(crate) fn now() -> String {
+        chrono::Utc::now().format(DATE_FORMAT).to_string()
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(into = "StatusFields", try_from = "StatusFields")]
+    /// Used to define the current status of a task. Every
+    /// status but `Todo` keeps the date the task got into it,
+    /// e.g. the done pattern stores the date the task was set as done.
+    ///
+    /// The statuses a task may go to are listed in `Status::can_become`,
+    /// e.g. a task that is done or cancelled can only be reopened.
+    pub enum Status {
+        Done(String),
+        Todo,
+        /// Started at the given date.
+        InProgress(String),
+        /// Blocked since the given date, for a reason.
+        Blocked { reason: String, since: String },
+        /// Cancelled at the given date.
+        Cancelled(String),
+    }
+
+    impl<T: AsRef<str>> From<T> for Status {
+        fn from(date_completed: T) -> Self {
+            Status::Done(String::from(date_completed.as_ref()))
+        }
+    }
+
+    impl Status {
+        /// The name of the status, as stored.
+        pub fn name(&self) -> &'static str {
+            match self {
+                Status::Todo => "todo",
+                Status::InProgress(_) => "in_progress",
+                Status::Blocked { .. } => "blocked",
+                Status::Done(_) => "done",
+                Status::Cancelled(_) => "cancelled",
+            }
+        }
+
+        /// The date the task got into the status, if it keeps one.
+        pub fn since(&self) -> Option<&String> {
+            match self {
+                Status::Todo => None,
+                Status::InProgress(date)
+                | Status::Blocked { since: date, .. }
+                | Status::Done(date)
+                | Status::Cancelled(date) => Some(date),
+            }
+        }
+
+        /// A short mark for listings, e.g. `[x]` for done.
+        pub fn mark(&self) -> &'static str {
+            match self {
+                Status::Todo => "[ ]",
+                Status::InProgress(_) => "[~]",
+                Status::Blocked { .. } => "[!]",
+                Status::Done(_) => "[x]",
+                Status::Cancelled(_) => "[-]",
+            }
+        }
+
+        /// Tells if the task is done or cancelled.
+        pub fn is_closed(&self) -> bool {
+            matches!(self, Status::Done(_) | Status::Cancelled(_))
+        }
+
+        /// Tells if a task in this status may be set to `next`:
+        ///
+        /// | from        | to                                    |
+        /// |-------------|---------------------------------------|
+        /// | todo        | in_progress, blocked, done, cancelled |
+        /// | in_progress | todo, blocked, done, cancelled        |
+        /// | blocked     | todo, in_progress, cancelled          |
+        /// | done        | todo                                  |
+        /// | cancelled   | todo                                  |
+        ///
+        /// A status may also have its details changed, e.g. the date a
+        /// task was completed or the reason it is blocked, but setting
+        /// the very same status again isn't a transition.
+        pub fn can_become(&self, next: &Status) -> bool {
+            match (self, next) {
+                (current, next) if current == next => false,
+                (Status::Todo, Status::InProgress(_))
+                | (Status::Todo, Status::Blocked { .. })
+                | (Status::Todo, Status::Done(_))
+                | (Status::Todo, Status::Cancelled(_)) => true,
+                (Status::InProgress(_), Status::Todo)
+                | (Status::InProgress(_), Status::Blocked { .. })
+                | (Status::InProgress(_), Status::Done(_))
+                | (Status::InProgress(_), Status::Cancelled(_)) => true,
+                (Status::Blocked { .. }, Status::Todo)
+                | (Status::Blocked { .. }, Status::InProgress(_))
+                | (Status::Blocked { .. }, Status::Cancelled(_)) => true,
+                (Status::Done(_), Status::Todo) | (Status::Cancelled(_), Status::Todo) => true,
+                // The same status with other details.
+                (Status::InProgress(_), Status::InProgress(_))
+                | (Status::Blocked { .. }, Status::Blocked { .. })
+                | (Status::Done(_), Status::Done(_))
+                | (Status::Cancelled(_), Status::Cancelled(_)) => true,
+                _ => false,
+            }
+        }
+
+        /// Replaces the `CURRENT_TIMESTAMP` placeholder of the date by `now`.
+        pub(crate) fn stamped(self, now: &str) -> Status {
+            let stamp = |date: String| {
+                if date == "CURRENT_TIMESTAMP" {
+                    String::from(now)
+                } else {
+                    date
+                }
+            };
+
+            match self {
+                Status::Todo => Status::Todo,
+                Status::InProgress(date) => Status::InProgress(stamp(date)),
+                Status::Blocked { reason, since } => Status::Blocked { reason, since: stamp(since) },
+                Status::Done(date) => Status::Done(stamp(date)),
+                Status::Cancelled(date) => Status::Cancelled(stamp(date)),
             }
         }
     }
 
-    impl BasicDataModelTrait for TodoModel {
-        type ObjType = Todo;
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    /// The fields a status is written with: its name, and
+    /// the date and reason of the statuses keeping them.
+    pub struct StatusFields {
+        pub status: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub started_at: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub blocked_at: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub reason: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub completed_at: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub cancelled_at: Option<String>,
+    }
+
+    impl StatusFields {
+        /// The fields as name and value pairs, in the order they are declared.
+        pub fn pairs(&self) -> Vec<(&'static str, Option<String>)> {
+            vec![
+                ("status", Some(self.status.clone())),
+                ("started_at", self.started_at.clone()),
+                ("blocked_at", self.blocked_at.clone()),
+                ("reason", self.reason.clone()),
+                ("completed_at", self.completed_at.clone()),
+                ("cancelled_at", self.cancelled_at.clone()),
+            ]
+        }
+    }
+
+    impl From<Status> for StatusFields {
+        fn from(status: Status) -> Self {
+            let mut fields = StatusFields {
+                status: String::from(status.name()),
+                ..StatusFields::default()
+            };
+
+            match status {
+                Status::Todo => {}
+                Status::InProgress(date) => fields.started_at = Some(date),
+                Status::Blocked { reason, since } => {
+                    fields.reason = Some(reason);
+                    fields.blocked_at = Some(since);
+                }
+                Status::Done(date) => fields.completed_at = Some(date),
+                Status::Cancelled(date) => fields.cancelled_at = Some(date),
+            }
+
+            fields
+        }
+    }
+
+    impl TryFrom<StatusFields> for Status {
+        type Error = InternalError;
+
+        fn try_from(fields: StatusFields) -> Result<Self, Self::Error> {
+            let missing = |field: &str| {
+                InternalError::invalid_input(&format!(
+                    "the status '{}' needs the field '{}'",
+                    fields.status, field
+                ))
+            };
+
+            match fields.status.as_str() {
+                "todo" => Ok(Status::Todo),
+                "in_progress" => fields
+                    .started_at
+                    .clone()
+                    .map(Status::InProgress)
+                    .ok_or_else(|| missing("started_at")),
+                "blocked" => Ok(Status::Blocked {
+                    reason: fields.reason.clone().ok_or_else(|| missing("reason"))?,
+                    since: fields.blocked_at.clone().ok_or_else(|| missing("blocked_at"))?,
+                }),
+                "done" => fields
+                    .completed_at
+                    .clone()
+                    .map(Status::Done)
+                    .ok_or_else(|| missing("completed_at")),
+                "cancelled" => fields
+                    .cancelled_at
+                    .clone()
+                    .map(Status::Cancelled)
+                    .ok_or_else(|| missing("cancelled_at")),
+                other => Err(InternalError::invalid_input(&format!(
+                    "unknown status '{}', expected one of todo, in_progress, blocked, done or cancelled",
+                    other
+                ))),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// How urgent a task is, ordered from the least to the most urgent.
+    pub enum Priority {
+        Low,
+        Medium,
+        High,
+    }
+
+    impl Priority {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Priority::Low => "low",
+                Priority::Medium => "medium",
+                Priority::High => "high",
+            }
+        }
+    }
+
+    impl FromStr for Priority {
+        type Err = InternalError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "low" | "l" => Ok(Priority::Low),
+                "medium" | "m" => Ok(Priority::Medium),
+                "high" | "h" => Ok(Priority::High),
+                _ => Err(InternalError::invalid_input(&format!(
+                    "unknown priority '{}', expected low, medium or high",
+                    s
+                ))),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+    /// How many of the tasks of a todo are done, and
+    /// how much of their estimated effort.
+    pub struct Progress {
+        pub total: usize,
+        pub done: usize,
+        pub effort: f64,
+        pub effort_done: f64,
+    }
+
+    impl Progress {
+        /// Counts the tasks done among the given ones,
+        /// leaving out the cancelled tasks.
+        pub fn of(tasks: &[Task]) -> Self {
+            let counted: Vec<&Task> = tasks
+                .iter()
+                .filter(|task| !matches!(task.status(), Status::Cancelled(_)))
+                .collect();
+            let done: Vec<&Task> = counted
+                .iter()
+                .copied()
+                .filter(|task| matches!(task.status(), Status::Done(_)))
+                .collect();
+            let effort = |tasks: &[&Task]| tasks.iter().filter_map(|task| task.estimate()).fold(0.0, |a, b| a + b);
+
+            Self {
+                total: counted.len(),
+                done: done.len(),
+                effort: effort(&counted),
+                effort_done: effort(&done),
+            }
+        }
+
+        /// The number of tasks not done yet.
+        pub fn remaining(&self) -> usize {
+            self.total - self.done
+        }
+
+        /// The estimated effort of the tasks not done yet.
+        pub fn remaining_effort(&self) -> f64 {
+            self.effort - self.effort_done
+        }
+
+        /// The percentage of the tasks done, 0 for a todo without tasks.
+        pub fn percent(&self) -> f64 {
+            if self.total == 0 {
+                0.0
+            } else {
+                self.done as f64 * 100.0 / self.total as f64
+            }
+        }
+
+        /// Tells if there are tasks, and all of them are done.
+        pub fn is_complete(&self) -> bool {
+            self.total > 0 && self.done == self.total
+        }
+
+        /// Draws the progress as a bar of the given width, e.g. `[###-------]`.
+        pub fn bar(&self, width: usize) -> String {
+            let filled = (self.percent() * width as f64 / 100.0).round() as usize;
+            format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+        }
+    }
+
+    impl Display for Progress {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} {:>3.0}% {}/{}", self.bar(10), self.percent(), self.done, self.total)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    /// The tasks to do due on a day, the most urgent first.
+    pub struct AgendaGroup {
+        pub due: String,
+        pub tasks: Vec<Task>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    /// The tasks completed during a week, e.g. `2022-W25`, and their estimated effort.
+    pub struct VelocityRow {
+        pub week: String,
+        pub tasks: usize,
+        pub effort: f64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// Todo is a structure used to store
+    /// a set of task to be done.
+    pub struct Todo {
+        id: IdType,
+        name: String,
+        description: Option<String>,
+        created_at: String, // TODO: Convert to datetime format
+        updated_at: String,
+        /// Set while the todo is in the trash.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        deleted_at: Option<String>,
+        /// Set while the todo is archived.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        archived_at: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// A task is something the user
+    /// wants or have to do. They are stored
+    /// inside the Todos.
+    pub struct Task {
+        id: IdType,
+        todo_id: IdType,
+        what: String,
+        created_at: String, // TODO: Convert to datetime format
+        updated_at: String,
+        #[serde(flatten)]
+        status: Status,
+        /// The effort the task is expected to take,
+        /// in the unit the team plans with (points, hours...).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        estimate: Option<f64>,
+        /// The day the task should be done by, e.g. `2022-06-20`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        due: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<Priority>,
+        /// Set while the task is in the trash.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        deleted_at: Option<String>,
+    }
+
+    impl Task {
+        pub fn new(
+            id: IdType,
+            todo_id: IdType,
+            what: &str,
+            created_at: &str,
+            updated_at: &str,
+            status: Status,
+        ) -> Self {
+            Self {
+                id,
+                todo_id,
+                status,
+                what: String::from(what),
+                created_at: String::from(created_at),
+                updated_at: String::from(updated_at),
+                estimate: None,
+                due: None,
+                priority: None,
+                deleted_at: None,
+            }
+        }
+
+        /// Sets the status as it is stored, without checking the
+        /// transition; the other changes go through `transition`.
+        pub(crate) fn set_status(&mut self, status: Status) {
+            self.status = status;
+        }
+
+        /// Sets the status, if the current one can become it.
+        pub fn transition(&mut self, status: Status) -> Result<(), InternalError> {
+            if self.status == status {
+                return Err(InternalError::invalid_input(&format!(
+                    "task {} is already {}",
+                    self.id,
+                    status.name()
+                )));
+            }
+
+            if !self.status.can_become(&status) {
+                let hint = if self.status.is_closed() { ", it has to be reopened first" } else { "" };
+
+                return Err(InternalError::invalid_input(&format!(
+                    "task {} can't go from {} to {}{}",
+                    self.id,
+                    self.status.name(),
+                    status.name(),
+                    hint
+                )));
+            }
+
+            self.status = status;
+            Ok(())
+        }
+
+        pub fn set_updated_at(&mut self, datetime: &str) {
+            self.updated_at = String::from(datetime);
+        }
+
+        pub fn set_what(&mut self, what_new: &str) {
+            self.what = String::from(what_new);
+        }
+
+        /// Moves the task to another todo.
+        pub fn set_todo_id(&mut self, todo_id: IdType) {
+            self.todo_id = todo_id;
+        }
+
+        /// Moves the task to the trash, or out of it with `None`.
+        pub fn set_deleted_at(&mut self, datetime: Option<&str>) {
+            self.deleted_at = datetime.map(String::from);
+        }
+
+        pub fn set_estimate(&mut self, estimate: Option<f64>) {
+            self.estimate = estimate;
+        }
+
+        pub fn set_due(&mut self, date: Option<&str>) {
+            self.due = date.map(String::from);
+        }
+
+        pub fn set_priority(&mut self, priority: Option<Priority>) {
+            self.priority = priority;
+        }
+
+        /// References the task's id.
+        pub fn id(&self) -> &IdType {
+            &self.id
+        }
+
+        pub fn todo_id(&self) -> &IdType {
+            &self.todo_id
+        }
+
+        pub fn created_at(&self) -> &String {
+            &self.created_at
+        }
+
+        pub fn updated_at(&self) -> &String {
+            &self.updated_at
+        }
+
+        /// Reference the status of the task.
+        pub fn status(&self) -> &Status {
+            &self.status
+        }
+
+        /// References the task of the Task struct.
+        pub fn what(&self) -> &String {
+            &self.what
+        }
+
+        /// When the task was moved to the trash, if it is there.
+        pub fn deleted_at(&self) -> Option<&String> {
+            self.deleted_at.as_ref()
+        }
+
+        /// The effort the task is expected to take, if it was estimated.
+        pub fn estimate(&self) -> Option<f64> {
+            self.estimate
+        }
+
+        /// The day the task should be done by, if it has one.
+        pub fn due(&self) -> Option<&String> {
+            self.due.as_ref()
+        }
+
+        pub fn priority(&self) -> Option<Priority> {
+            self.priority
+        }
+    }
+
+    impl Todo {
+        pub fn new(
+            id: IdType,
+            name: String,
+            description: Option<String>,
+            created_at: String,
+            updated_at: String,
+        ) -> Self {
+            Self {
+                id,
+                name,
+                description,
+                created_at,
+                updated_at,
+                deleted_at: None,
+                archived_at: None,
+            }
+        }
+
+        /// Sets a new value to the name.
+        pub fn set_name(&mut self, name: &str) {
+            self.name = String::from(name);
+        }
+
+        /// Sets a new value to the description.
+        pub fn set_description(&mut self, description: &str) {
+            self.description = Some(String::from(description));
+        }
+
+        pub fn clear_description(&mut self) {
+            self.description = None;
+        }
+
+        pub fn set_updated_at(&mut self, datetime: &str) {
+            self.updated_at = String::from(datetime);
+        }
+
+        /// Moves the todo to the trash, or out of it with `None`.
+        pub fn set_deleted_at(&mut self, datetime: Option<&str>) {
+            self.deleted_at = datetime.map(String::from);
+        }
+
+        /// Archives the todo, or brings it back with `None`.
+        pub fn set_archived_at(&mut self, datetime: Option<&str>) {
+            self.archived_at = datetime.map(String::from);
+        }
+
+        /// Reference the id.
+        pub fn id(&self) -> &IdType {
+            &self.id
+        }
+
+        /// References the name.
+        pub fn name(&self) -> &String {
+            &self.name
+        }
+
+        /// References the description.
+        pub fn description(&self) -> Option<&String> {
+            self.description.as_ref()
+        }
+
+        pub fn created_at(&self) -> &String {
+            &self.created_at
+        }
+
+        pub fn updated_at(&self) -> &String {
+            &self.updated_at
+        }
+
+        /// When the todo was moved to the trash, if it is there.
+        pub fn deleted_at(&self) -> Option<&String> {
+            self.deleted_at.as_ref()
+        }
+
+        /// When the todo was archived, if it is.
+        pub fn archived_at(&self) -> Option<&String> {
+            self.archived_at.as_ref()
+        }
+    }
+}
+
+mod history {
+    use super::core::*;
+    use serde::{Deserialize, Serialize};
+    use std::fmt::{self, Display};
+    use std::str::FromStr;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// The kind of object changed.
+    pub enum Entity {
+        Todo,
+        Task,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// What was done to the object.
+    pub enum Action {
+        Add,
+        Update,
+        /// Deleted for good, or moved to the trash.
+        Delete,
+        /// Brought back from the trash.
+        Restore,
+    }
+
+    impl Entity {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Entity::Todo => "todo",
+                Entity::Task => "task",
+            }
+        }
+    }
+
+    impl Action {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Action::Add => "add",
+                Action::Update => "update",
+                Action::Delete => "delete",
+                Action::Restore => "restore",
+            }
+        }
+    }
+
+    impl FromStr for Entity {
+        type Err = InternalError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "todo" => Ok(Entity::Todo),
+                "task" => Ok(Entity::Task),
+                _ => Err(InternalError::invalid_input(&format!("unknown entity '{}'", s))),
+            }
+        }
+    }
+
+    impl FromStr for Action {
+        type Err = InternalError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "add" => Ok(Action::Add),
+                "update" => Ok(Action::Update),
+                "delete" => Ok(Action::Delete),
+                "restore" => Ok(Action::Restore),
+                _ => Err(InternalError::invalid_input(&format!("unknown action '{}'", s))),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// The change of one field of a todo or task. The history
+    /// is append-only: the entries are never changed or removed.
+    pub struct HistoryEntry {
+        id: IdType,
+        entity: Entity,
+        entity_id: IdType,
+        todo_id: IdType,
+        action: Action,
+        field: String,
+        old_value: Option<String>,
+        new_value: Option<String>,
+        timestamp: String,
+        actor: String,
+    }
+
+    impl HistoryEntry {
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(
+            id: IdType,
+            entity: Entity,
+            entity_id: IdType,
+            todo_id: IdType,
+            action: Action,
+            field: &str,
+            old_value: Option<String>,
+            new_value: Option<String>,
+            timestamp: &str,
+            actor: &str,
+        ) -> Self {
+            Self {
+                id,
+                entity,
+                entity_id,
+                todo_id,
+                action,
+                old_value,
+                new_value,
+                field: String::from(field),
+                timestamp: String::from(timestamp),
+                actor: String::from(actor),
+            }
+        }
+
+        /// The entries recording the changes of a todo, one per changed
+        /// field. `old` is `None` when it was added and `new` when deleted.
+        pub(crate) fn todo_changes(old: Option<&Todo>, new: Option<&Todo>, actor: &str) -> Vec<Self> {
+            let fields = |todo: &Todo| {
+                vec![
+                    ("name", Some(todo.name().clone())),
+                    ("description", todo.description().cloned()),
+                    ("archived_at", todo.archived_at().cloned()),
+                    ("deleted_at", todo.deleted_at().cloned()),
+                ]
+            };
+
+            let id = *old.or(new).map(|todo| todo.id()).unwrap_or(&0);
+            Self::changes(Entity::Todo, id, id, old.map(fields), new.map(fields), actor)
+        }
+
+        /// The entries recording the changes of a task, as `todo_changes`.
+        pub(crate) fn task_changes(old: Option<&Task>, new: Option<&Task>, actor: &str) -> Vec<Self> {
+            let fields = |task: &Task| {
+                let mut fields = vec![
+                    ("todo_id", Some(task.todo_id().to_string())),
+                    ("what", Some(task.what().clone())),
+                ];
+                fields.extend(StatusFields::from(task.status().clone()).pairs());
+                fields.push(("estimate", task.estimate().map(|e| e.to_string())));
+                fields.push(("due", task.due().cloned()));
+                fields.push(("priority", task.priority().map(|p| String::from(p.name()))));
+                fields.push(("deleted_at", task.deleted_at().cloned()));
+                fields
+            };
+
+            let task = old.or(new);
+            let id = *task.map(|task| task.id()).unwrap_or(&0);
+            let todo_id = *new.or(old).map(|task| task.todo_id()).unwrap_or(&0);
+            Self::changes(Entity::Task, id, todo_id, old.map(fields), new.map(fields), actor)
+        }
+
+        fn changes(
+            entity: Entity,
+            entity_id: IdType,
+            todo_id: IdType,
+            old: Option<Vec<(&str, Option<String>)>>,
+            new: Option<Vec<(&str, Option<String>)>>,
+            actor: &str,
+        ) -> Vec<Self> {
+            let deleted = |values: &Vec<(&str, Option<String>)>| {
+                values
+                    .iter()
+                    .any(|(field, value)| *field == "deleted_at" && value.is_some())
+            };
+
+            let action = match (&old, &new) {
+                (None, _) => Action::Add,
+                (_, None) => Action::Delete,
+                (Some(old), Some(new)) => match (deleted(old), deleted(new)) {
+                    (false, true) => Action::Delete,
+                    (true, false) => Action::Restore,
+                    _ => Action::Update,
+                },
+            };
+
+            let fields = old.as_ref().or(new.as_ref()).cloned().unwrap_or_default();
+
+            fields
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (field, _))| {
+                    let old_value = old.as_ref().and_then(|values| values[i].1.clone());
+                    let new_value = new.as_ref().and_then(|values| values[i].1.clone());
+
+                    if old_value == new_value {
+                        return None;
+                    }
+
+                    Some(Self::new(
+                        0,
+                        entity,
+                        entity_id,
+                        todo_id,
+                        action,
+                        field,
+                        old_value,
+                        new_value,
+                        "CURRENT_TIMESTAMP",
+                        actor,
+                    ))
+                })
+                .collect()
+        }
+
+        pub fn id(&self) -> &IdType {
+            &self.id
+        }
+
+        /// The kind of the object changed.
+        pub fn entity(&self) -> Entity {
+            self.entity
+        }
+
+        /// The id of the object changed.
+        pub fn entity_id(&self) -> &IdType {
+            &self.entity_id
+        }
+
+        /// The todo changed, or the one of the task changed.
+        pub fn todo_id(&self) -> &IdType {
+            &self.todo_id
+        }
+
+        pub fn action(&self) -> Action {
+            self.action
+        }
+
+        pub fn field(&self) -> &String {
+            &self.field
+        }
+
+        pub fn old_value(&self) -> Option<&String> {
+            self.old_value.as_ref()
+        }
+
+        pub fn new_value(&self) -> Option<&String> {
+            self.new_value.as_ref()
+        }
+
+        /// When the change was made.
+        pub fn timestamp(&self) -> &String {
+            &self.timestamp
+        }
+
+        /// Who made the change.
+        pub fn actor(&self) -> &String {
+            &self.actor
+        }
+
+        /// The same entry, with the id given by a storage.
+        pub(crate) fn with_id(self, id: IdType) -> Self {
+            Self { id, ..self }
+        }
+
+        pub fn set_timestamp(&mut self, datetime: &str) {
+            self.timestamp = String::from(datetime);
+        }
+    }
+
+    impl Display for HistoryEntry {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let value = |value: Option<&String>| match value {
+                Some(value) => format!("{:?}", value),
+                None => String::from("none"),
+            };
+
+            write!(
+                f,
+                "{} {} {} {} {} {}: ",
+                self.timestamp,
+                self.actor,
+                self.action.name(),
+                self.entity.name(),
+                self.entity_id,
+                self.field
+            )?;
+
+            match (self.action, self.old_value(), self.new_value()) {
+                (Action::Update, old, new) => write!(f, "{} -> {}", value(old), value(new)),
+                (_, old, None) => write!(f, "{}", value(old)),
+                (_, _, new) => write!(f, "{}", value(new)),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    /// Filters used to look for history entries, as `TaskQuery`.
+    pub struct HistoryQuery {
+        /// Only the changes of this kind of object.
+        pub entity: Option<Entity>,
+        /// Only the changes of the object with this id.
+        pub entity_id: Option<IdType>,
+        /// Only the changes of this todo and of its tasks.
+        pub todo_id: Option<IdType>,
+    }
+
+    impl HistoryQuery {
+        /// Checks if an entry passes every filter of the query.
+        pub fn matches(&self, entry: &HistoryEntry) -> bool {
+            self.entity.is_none_or(|entity| entity == entry.entity)
+                && self.entity_id.is_none_or(|id| id == entry.entity_id)
+                && self.todo_id.is_none_or(|id| id == entry.todo_id)
+        }
+    }
+
+    /// The name recorded as the author of the changes, taken
+    /// from `TODO_ACTOR` or else from the user of the system.
+    pub fn default_actor() -> String {
+        ["TODO_ACTOR", "USER", "USERNAME"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_else(|| String::from("unknown"))
+    }
+}
+
+mod journal {
+    use super::core::*;
+    use serde::{Deserialize, Serialize};
+    use std::fmt::{self, Display};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// A copy of an object, as it was at some point.
+    pub enum Snapshot {
+        Todo(Todo),
+        Task(Task),
+    }
+
+    impl Snapshot {
+        fn describe(&self) -> String {
+            match self {
+                Snapshot::Todo(todo) => format!("todo {} {:?}", todo.id(), todo.name()),
+                Snapshot::Task(task) => format!("task {} {:?}", task.id(), task.what()),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// A change recorded so that it can be undone and redone. The
+    /// object is kept whole before and after the change: `before`
+    /// is `None` when it was added and `after` when it was deleted.
+    pub struct JournalEntry {
+        id: IdType,
+        operation: IdType,
+        before: Option<Snapshot>,
+        after: Option<Snapshot>,
+        undone: bool,
+    }
+
+    impl JournalEntry {
+        pub fn new(
+            id: IdType,
+            operation: IdType,
+            before: Option<Snapshot>,
+            after: Option<Snapshot>,
+            undone: bool,
+        ) -> Self {
+            Self {
+                id,
+                operation,
+                before,
+                after,
+                undone,
+            }
+        }
+
+        /// The same entry, with the id given by a storage. The operation
+        /// of an entry without one is the entry itself.
+        pub(crate) fn with_id(self, id: IdType) -> Self {
+            let operation = if self.operation == 0 { id } else { self.operation };
+            Self {
+                id,
+                operation,
+                ..self
+            }
+        }
+
+        pub fn id(&self) -> &IdType {
+            &self.id
+        }
+
+        /// The id of the first entry of the operation, the changes
+        /// of one operation are undone and redone together.
+        pub fn operation(&self) -> &IdType {
+            &self.operation
+        }
+
+        pub fn before(&self) -> Option<&Snapshot> {
+            self.before.as_ref()
+        }
+
+        pub fn after(&self) -> Option<&Snapshot> {
+            self.after.as_ref()
+        }
+
+        /// Tells if the change is currently undone.
+        pub fn undone(&self) -> bool {
+            self.undone
+        }
+
+        pub(crate) fn set_undone(&mut self, undone: bool) {
+            self.undone = undone;
+        }
+    }
+
+    impl Display for JournalEntry {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match (&self.before, &self.after) {
+                (None, Some(after)) => write!(f, "add {}", after.describe()),
+                (Some(before), None) => write!(f, "delete {}", before.describe()),
+                (Some(_), Some(after)) => write!(f, "update {}", after.describe()),
+                (None, None) => write!(f, "nothing"),
+            }
+        }
+    }
+}
+
+mod time_tracking {
+    use super::core::*;
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// A span of time spent on a task. The entry of
+    /// a running timer has no end date yet.
+    pub struct TimeEntry {
+        id: IdType,
+        task_id: IdType,
+        started_at: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ended_at: Option<String>,
+    }
+
+    impl TimeEntry {
+        pub fn new(id: IdType, task_id: IdType, started_at: &str, ended_at: Option<&str>) -> Self {
+            Self {
+                id,
+                task_id,
+                started_at: String::from(started_at),
+                ended_at: ended_at.map(String::from),
+            }
+        }
+
+        /// The same entry, with the id given by a storage.
+        pub(crate) fn with_id(self, id: IdType) -> Self {
+            Self { id, ..self }
+        }
+
+        pub fn id(&self) -> &IdType {
+            &self.id
+        }
+
+        pub fn task_id(&self) -> &IdType {
+            &self.task_id
+        }
+
+        pub fn started_at(&self) -> &String {
+            &self.started_at
+        }
+
+        pub fn ended_at(&self) -> Option<&String> {
+            self.ended_at.as_ref()
+        }
+
+        pub(crate) fn set_ended_at(&mut self, datetime: &str) {
+            self.ended_at = Some(String::from(datetime));
+        }
+
+        /// Tells if the timer of the entry is still running.
+        pub fn is_running(&self) -> bool {
+            self.ended_at.is_none()
+        }
+
+        /// The time spent, up to now if the timer is still running.
+        pub fn duration(&self) -> Duration {
+            let parse = |date: &str| NaiveDateTime::parse_from_str(date, DATE_FORMAT).ok();
+            let end = self.ended_at.clone().unwrap_or_else(now);
+
+            match (parse(&self.started_at), parse(&end)) {
+                (Some(start), Some(end)) => (end - start).to_std().unwrap_or_default(),
+                _ => Duration::ZERO,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    /// Filters used to look for time entries, as `TaskQuery`.
+    pub struct TimeQuery {
+        /// Only the time spent on this task.
+        pub task_id: Option<IdType>,
+        /// Only the entries of running timers (or of stopped ones).
+        pub running: Option<bool>,
+        /// Only the entries started at or after this date.
+        pub from: Option<String>,
+        /// Only the entries started up to this date, a day
+        /// such as `2022-06-20` includes the whole day.
+        pub to: Option<String>,
+    }
+
+    impl TimeQuery {
+        /// Checks if an entry passes every filter of the query.
+        pub fn matches(&self, entry: &TimeEntry) -> bool {
+            let started_at = entry.started_at.as_str();
+
+            self.task_id.is_none_or(|id| id == entry.task_id)
+                && self.running.is_none_or(|running| running == entry.is_running())
+                && self.from.as_ref().is_none_or(|from| started_at >= from.as_str())
+                && self.to.as_ref().is_none_or(|to| {
+                    started_at.get(..to.len()).unwrap_or(started_at) <= to.as_str()
+                })
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// The time spent on a task during a day.
+    pub struct TimesheetRow {
+        pub day: String,
+        pub task: Task,
+        pub duration: Duration,
+    }
+
+    /// Writes a duration in hours and minutes, e.g. `1h30m`.
+    pub fn format_duration(duration: Duration) -> String {
+        let minutes = duration.as_secs() / 60;
+
+        match (minutes / 60, minutes % 60) {
+            (0, minutes) => format!("{}m", minutes),
+            (hours, 0) => format!("{}h", hours),
+            (hours, minutes) => format!("{}h{}m", hours, minutes),
+        }
+    }
+}
+
+mod stats {
+    use super::core::*;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use serde::Serialize;
+    use std::fmt::Write;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    fn parse_date(date: &str) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(date, DATE_FORMAT).ok()
+    }
+
+    /// The date a task was completed, if it is done.
+    fn completed_at(task: &Task) -> Option<NaiveDateTime> {
+        match task.status() {
+            Status::Done(date) => parse_date(date),
+            _ => None,
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+    #[serde(rename_all = "lowercase")]
+    /// The span of time the statistics are grouped by.
+    pub enum Period {
+        Day,
+        Week,
+    }
+
+    impl Period {
+        /// The name of the period a date is in, e.g. `2022-06-20` or `2022-W25`.
+        pub fn of(&self, date: NaiveDate) -> String {
+            match self {
+                Period::Day => date.to_string(),
+                Period::Week => date.format("%G-W%V").to_string(),
+            }
+        }
+
+        /// The first day of the next period.
+        fn next(&self, date: NaiveDate) -> NaiveDate {
+            match self {
+                Period::Day => date + chrono::Days::new(1),
+                Period::Week => date.week(chrono::Weekday::Mon).last_day() + chrono::Days::new(1),
+            }
+        }
+    }
+
+    impl FromStr for Period {
+        type Err = InternalError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "day" => Ok(Period::Day),
+                "week" => Ok(Period::Week),
+                _ => Err(InternalError::invalid_input(&format!("unknown period '{}'", s))),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    /// How many tasks were created and completed during a period.
+    pub struct PeriodCount {
+        pub period: String,
+        pub created: usize,
+        pub completed: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    /// How many of the tasks of a todo are done.
+    pub struct TodoCompletion {
+        pub todo_id: IdType,
+        pub name: String,
+        pub total: usize,
+        pub done: usize,
+        /// The part of the tasks done, from 0 to 1.
+        pub rate: f64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    /// Metrics computed from the dates the tasks were created and completed.
+    pub struct Stats {
+        pub period: Period,
+        /// The tasks created and completed per period, oldest first.
+        pub per_period: Vec<PeriodCount>,
+        /// The average time between creating a task and completing it.
+        pub average_completion_secs: Option<u64>,
+        /// The tasks not done yet, oldest first.
+        pub oldest_open: Vec<Task>,
+        pub todos: Vec<TodoCompletion>,
+    }
+
+    impl Stats {
+        /// Computes the statistics of the given todos and tasks,
+        /// keeping at most `oldest` of the open tasks.
+        pub fn compute(todos: &[Todo], tasks: &[Task], period: Period, oldest: usize) -> Self {
+            let mut per_period: Vec<PeriodCount> = Vec::new();
+            let mut count = |date: Option<NaiveDateTime>, completed: bool| {
+                let name = match date {
+                    Some(date) => period.of(date.date()),
+                    None => return,
+                };
+
+                let row = match per_period.iter_mut().position(|row| row.period == name) {
+                    Some(i) => &mut per_period[i],
+                    None => {
+                        per_period.push(PeriodCount {
+                            period: name,
+                            created: 0,
+                            completed: 0,
+                        });
+                        per_period.last_mut().unwrap()
+                    }
+                };
+
+                if completed {
+                    row.completed += 1;
+                } else {
+                    row.created += 1;
+                }
+            };
+
+            for task in tasks {
+                count(parse_date(task.created_at()), false);
+                count(completed_at(task), true);
+            }
+            per_period.sort_by(|a, b| a.period.cmp(&b.period));
+
+            let times: Vec<u64> = tasks
+                .iter()
+                .filter_map(|task| Some((parse_date(task.created_at())?, completed_at(task)?)))
+                .map(|(created, completed)| (completed - created).num_seconds().max(0) as u64)
+                .collect();
+            let average_completion_secs = match times.len() {
+                0 => None,
+                n => Some(times.iter().sum::<u64>() / n as u64),
+            };
+
+            let mut oldest_open: Vec<Task> = tasks
+                .iter()
+                .filter(|task| !task.status().is_closed())
+                .cloned()
+                .collect();
+            oldest_open.sort_by(|a, b| a.created_at().cmp(b.created_at()).then(a.id().cmp(b.id())));
+            oldest_open.truncate(oldest);
+
+            let todos = todos
+                .iter()
+                .map(|todo| {
+                    let tasks: Vec<Task> = tasks
+                        .iter()
+                        .filter(|task| task.todo_id() == todo.id())
+                        .cloned()
+                        .collect();
+                    let progress = Progress::of(&tasks);
+
+                    TodoCompletion {
+                        todo_id: *todo.id(),
+                        name: todo.name().clone(),
+                        total: progress.total,
+                        done: progress.done,
+                        rate: progress.percent() / 100.0,
+                    }
+                })
+                .collect();
+
+            Self {
+                period,
+                per_period,
+                average_completion_secs,
+                oldest_open,
+                todos,
+            }
+        }
+
+        /// The average time between creating a task and completing it.
+        pub fn average_completion(&self) -> Option<Duration> {
+            self.average_completion_secs.map(Duration::from_secs)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    /// The number of tasks left at the end of a period.
+    pub struct BurndownPoint {
+        pub period: String,
+        pub remaining: usize,
+    }
+
+    /// The number of tasks left at the end of each period, from the
+    /// one the first task was created in up to the one of `today`.
+    /// Cancelled tasks are left out.
+    pub fn burndown(tasks: &[Task], period: Period, today: NaiveDate) -> Vec<BurndownPoint> {
+        let tasks: Vec<(NaiveDate, Option<NaiveDate>)> = tasks
+            .iter()
+            .filter(|task| !matches!(task.status(), Status::Cancelled(_)))
+            .filter_map(|task| {
+                let created = parse_date(task.created_at())?.date();
+                Some((created, completed_at(task).map(|date| date.date())))
+            })
+            .collect();
+
+        let mut day = match tasks.iter().map(|(created, _)| *created).min() {
+            Some(day) => day,
+            None => return Vec::new(),
+        };
+        let mut points = Vec::new();
+
+        while day <= today {
+            let end = period.next(day);
+            let remaining = tasks
+                .iter()
+                .filter(|(created, completed)| {
+                    *created < end && completed.is_none_or(|completed| completed >= end)
+                })
+                .count();
+
+            points.push(BurndownPoint {
+                period: period.of(day),
+                remaining,
+            });
+            day = end;
+        }
+
+        points
+    }
+
+    /// Draws a burndown as a chart of `height` lines, one column per point.
+    pub fn burndown_chart(points: &[BurndownPoint], height: usize) -> String {
+        let max = points.iter().map(|point| point.remaining).max().unwrap_or(0).max(1);
+        let mut chart = String::new();
+
+        for line in (1..=height).rev() {
+            if line == height {
+                let _ = write!(chart, "{:>4} |", max);
+            } else {
+                chart.push_str("     |");
+            }
+
+            for point in points {
+                let filled = point.remaining * height >= line * max;
+                chart.push(if filled { '#' } else { ' ' });
+            }
+            chart.push('\n');
+        }
+
+        let _ = writeln!(chart, "   0 +{}", "-".repeat(points.len()));
+
+        if let (Some(first), Some(last)) = (points.first(), points.last()) {
+            let _ = writeln!(chart, "      {} .. {}", first.period, last.period);
+        }
+
+        chart
+    }
+}
+
+mod views {
+    use super::core::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    /// A named list of filters, saved to be run again,
+    /// e.g. `stale` for `--status todo --older-than 30d`.
+    pub struct View {
+        name: String,
+        args: String,
+    }
+
+    impl View {
+        pub fn new(name: &str, args: &str) -> Self {
+            Self {
+                name: String::from(name),
+                args: String::from(args),
+            }
+        }
+
+        /// Checks that the name can be told apart from the
+        /// other arguments of the command line: it is made of
+        /// letters, digits, `-` and `_`, and isn't a number.
+        pub fn check_name(name: &str) -> Result<(), InternalError> {
+            let valid = name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+                && !name.starts_with('-')
+                && name.parse::<IdType>().is_err();
+
+            if name.is_empty() || !valid {
+                return Err(InternalError::invalid_input(&format!("'{}' is not a valid name for a view", name)));
+            }
+
+            Ok(())
+        }
+
+        pub fn name(&self) -> &String {
+            &self.name
+        }
+
+        /// The filters of the view, as given in the command line.
+        pub fn args(&self) -> &String {
+            &self.args
+        }
+    }
+}
+
+mod storage {
+    use super::core::*;
+    use super::history::*;
+    use super::journal::*;
+    use super::time_tracking::*;
+    use super::views::*;
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    /// Filters used to look for tasks. Each field left
+    /// as `None` doesn't restrict the tasks found.
+    pub struct TaskQuery {
+        /// Only the tasks of this todo.
+        pub todo_id: Option<IdType>,
+        /// Only the tasks that are done (or not).
+        pub done: Option<bool>,
+        /// Only the tasks containing this text, ignoring the case.
+        pub text: Option<String>,
+        /// Only the tasks neither done nor cancelled (or the closed ones).
+        pub open: Option<bool>,
+        /// Only the tasks due on this day or later.
+        pub due_from: Option<String>,
+        /// Only the tasks due on this day or before.
+        pub due_to: Option<String>,
+        /// Only the tasks with this status, see `Status::name`.
+        pub status: Option<String>,
+        /// Only the tasks with this priority.
+        pub priority: Option<Priority>,
+        /// Only the tasks created before this date.
+        pub created_before: Option<String>,
+    }
+
+    impl TaskQuery {
+        /// Checks if a task passes every filter of the query.
+        pub fn matches(&self, task: &Task) -> bool {
+            let done = matches!(task.status(), Status::Done(_));
+            let due = |check: &dyn Fn(&str) -> bool| task.due().is_some_and(|due| check(due));
+
+            self.todo_id.is_none_or(|id| id == *task.todo_id())
+                && self.done.is_none_or(|d| d == done)
+                && self.text.as_ref().is_none_or(|text| {
+                    task.what().to_lowercase().contains(&text.to_lowercase())
+                })
+                && self.open.is_none_or(|open| open != task.status().is_closed())
+                && self.due_from.as_ref().is_none_or(|from| due(&|due| due >= from.as_str()))
+                && self.due_to.as_ref().is_none_or(|to| due(&|due| due <= to.as_str()))
+                && self.status.as_ref().is_none_or(|status| status == task.status().name())
+                && self.priority.is_none_or(|priority| Some(priority) == task.priority())
+                && self.created_before.as_ref().is_none_or(|date| task.created_at() < date)
+        }
+    }
+
+    /// A place where todos and tasks are kept. Implement it to
+    /// use the library with another storage, and give it to
+    /// `Store::with_backend`.
+    ///
+    /// The ids of new objects are given by the storage, the ones
+    /// of the objects passed to the `insert_*` methods are ignored.
+    /// The `update_*` methods keep every field given, including the
+    /// dates of the trash and archive, and the `delete_*` ones delete
+    /// for good: `Store` only uses them to purge the trash.
+    pub trait Storage {
+        /// Prepares the storage to be used, e.g. creating tables.
+        fn init(&self) -> Result<(), InternalError>;
+
+        fn todos(&self) -> Result<Vec<Todo>, InternalError>;
+        fn find_todo(&self, id: IdType) -> Result<Todo, InternalError>;
+        fn insert_todo(&self, todo: Todo) -> Result<Todo, InternalError>;
+        fn update_todo(&self, todo: Todo) -> Result<Todo, InternalError>;
+        fn delete_todo(&self, id: IdType) -> Result<(), InternalError>;
+
+        fn tasks(&self) -> Result<Vec<Task>, InternalError>;
+        fn find_task(&self, id: IdType) -> Result<Task, InternalError>;
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError>;
+        fn update_task(&self, task: Task) -> Result<Task, InternalError>;
+        fn delete_task(&self, id: IdType) -> Result<(), InternalError>;
+
+        /// Lists the tasks of a todo.
+        fn todo_tasks(&self, todo_id: IdType) -> Result<Vec<Task>, InternalError> {
+            let query = TaskQuery {
+                todo_id: Some(todo_id),
+                ..TaskQuery::default()
+            };
+
+            self.query_tasks(&query)
+        }
+
+        /// Lists the tasks matching the query.
+        fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>, InternalError> {
+            Ok(self
+                .tasks()?
+                .into_iter()
+                .filter(|task| query.matches(task))
+                .collect())
+        }
+
+        /// Appends an entry to the history of changes, giving it an id.
+        fn insert_history(&self, entry: HistoryEntry) -> Result<HistoryEntry, InternalError>;
+
+        /// Lists the entries of the history matching the query, oldest first.
+        fn history(&self, _query: &HistoryQuery) -> Result<Vec<HistoryEntry>, InternalError> {
+            Ok(Vec::new())
+        }
+
+        /// Writes the todo as given, keeping its id and dates, adding
+        /// it back if it was deleted. Used to undo and redo changes.
+        fn restore_todo(&self, _todo: Todo) -> Result<Todo, InternalError> {
+            Err(InternalError::new("the storage can't restore todos"))
+        }
+
+        /// Writes the task as given, as `restore_todo`.
+        fn restore_task(&self, _task: Task) -> Result<Task, InternalError> {
+            Err(InternalError::new("the storage can't restore tasks"))
+        }
+
+        /// Appends a change to the journal used to undo and redo, giving
+        /// it an id. Without it, the changes could not be undone.
+        fn insert_journal(&self, entry: JournalEntry) -> Result<JournalEntry, InternalError>;
+
+        /// Lists the entries of the journal, oldest first.
+        fn journal(&self) -> Result<Vec<JournalEntry>, InternalError> {
+            Ok(Vec::new())
+        }
+
+        /// Marks every change of an operation as undone, or not.
+        fn set_undone(&self, _operation: IdType, _undone: bool) -> Result<(), InternalError> {
+            Ok(())
+        }
+
+        /// Forgets the undone changes, which can't be redone after new ones.
+        fn clear_undone(&self) -> Result<(), InternalError> {
+            Ok(())
+        }
+
+        /// Adds an entry of time spent on a task, giving it an id.
+        fn insert_time_entry(&self, _entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            Err(InternalError::new("the storage can't track time"))
+        }
+
+        /// Writes the end date of a time entry.
+        fn update_time_entry(&self, _entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            Err(InternalError::new("the storage can't track time"))
+        }
+
+        /// Lists the time entries matching the query, oldest first.
+        fn time_entries(&self, _query: &TimeQuery) -> Result<Vec<TimeEntry>, InternalError> {
+            Ok(Vec::new())
+        }
+
+        /// Keeps a view, replacing the one with the same name.
+        fn save_view(&self, _view: View) -> Result<View, InternalError> {
+            Err(InternalError::new("the storage can't keep views"))
+        }
+
+        /// Lists the views, by name.
+        fn views(&self) -> Result<Vec<View>, InternalError> {
+            Ok(Vec::new())
+        }
+
+        /// Deletes the view with the given name, if there is one.
+        fn delete_view(&self, _name: &str) -> Result<(), InternalError> {
+            Err(InternalError::new("the storage can't keep views"))
+        }
+
+        /// Runs the operation atomically: either every change made
+        /// through the storage it is given is kept, or none is, if
+        /// it fails.
+        fn transaction(
+            &self,
+            operation: &mut dyn FnMut(&dyn Storage) -> Result<(), InternalError>,
+        ) -> Result<(), InternalError>;
+    }
+}
+
+mod file_storage {
+    use super::core::*;
+    use super::history::*;
+    use super::journal::*;
+    use super::storage::*;
+    use super::time_tracking::*;
+    use super::views::*;
+    use fs2::FileExt;
+    use serde::{Deserialize, Serialize};
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+    use std::path::{Path, PathBuf};
+
+    /// Name of the file locked while the directory is used.
+    const LOCK_FILE: &str = ".lock";
+
+    /// Name of the file listing the files of a commit, see `Commit`.
+    const COMMIT_FILE: &str = ".commit.json";
+
+    /// Name of the file keeping the next ids to be given.
+    const META_FILE: &str = ".meta.json";
+
+    /// Name of the file where the history is appended, one entry per line.
+    const HISTORY_FILE: &str = "history.jsonl";
+
+    /// Name of the file keeping the changes that can be undone.
+    const JOURNAL_FILE: &str = ".journal.json";
+
+    /// Name of the file keeping the time spent on the tasks.
+    const TIME_FILE: &str = "time.json";
+
+    /// Name of the file keeping the saved views.
+    const VIEWS_FILE: &str = "views.json";
+
+    fn io_error(e: io::Error) -> InternalError {
+        InternalError::new(&e.to_string())
+    }
+
+    fn json_error(e: serde_json::Error) -> InternalError {
+        InternalError::new(&e.to_string())
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    /// The content of the file of a todo.
+    struct TodoFile {
+        #[serde(flatten)]
+        todo: Todo,
+        tasks: Vec<Task>,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    struct Meta {
+        next_todo_id: IdType,
+        next_task_id: IdType,
+        /// Left at 0 by the stores older than it, until the history is counted.
+        #[serde(default)]
+        next_history_id: IdType,
+    }
+
+    /// The temporary file a file is written to before replacing it.
+    fn temp_path(path: &Path) -> PathBuf {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()))
+    }
+
+    fn write_synced(path: &Path, content: &[u8]) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(content)?;
+        file.sync_all()
+    }
+
+    /// Writes a file atomically: the content goes to a temporary
+    /// file first, which then replaces the old one.
+    fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+        let temp = temp_path(path);
+        write_synced(&temp, content)?;
+        fs::rename(&temp, path)
+    }
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    /// The changes of the files written by a transaction. Every file is
+    /// first written to a temporary file, then the commit is written to
+    /// `COMMIT_FILE` and the temporary files replace the others. A commit
+    /// stopped halfway, e.g. by a crash, is finished the next time the
+    /// directory is used, so that the files change all together.
+    struct Commit {
+        /// The temporary files, with the names of the files they replace.
+        files: Vec<(String, String)>,
+        /// The names of the files removed.
+        removed: Vec<String>,
+        /// The length of the history before the entries appended.
+        history_len: u64,
+        /// The lines appended to the history.
+        history: String,
+    }
+
+    impl Commit {
+        /// Replaces the files, which may be done twice when a commit is recovered.
+        fn apply(&self, dir: &Path) -> io::Result<()> {
+            for (temp, name) in &self.files {
+                match fs::rename(dir.join(temp), dir.join(name)) {
+                    // Already replaced before the commit was stopped.
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                    res => res?,
+                }
+            }
+
+            for name in &self.removed {
+                match fs::remove_file(dir.join(name)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => (),
+                }
+            }
+
+            if !self.history.is_empty() {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(dir.join(HISTORY_FILE))?;
+
+                // The lines may already have been appended, at least partly.
+                file.set_len(self.history_len)?;
+                file.seek(SeekFrom::End(0))?;
+                file.write_all(self.history.as_bytes())?;
+                file.sync_all()?;
+            }
+
+            fs::remove_file(dir.join(COMMIT_FILE))
+        }
+    }
+
+    /// Finishes the commit stopped halfway, if there is one. The
+    /// directory must be locked exclusively.
+    fn recover(dir: &Path) -> Result<(), InternalError> {
+        let commit: Commit = match fs::read(dir.join(COMMIT_FILE)) {
+            Ok(content) => serde_json::from_slice(&content).map_err(json_error)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        commit.apply(dir).map_err(io_error)
+    }
+
+    /// The changes made to the directory by an operation, they
+    /// are only written to the files when it succeeds.
+    struct FileTransaction<'a> {
+        dir: &'a Path,
+        meta: RefCell<Meta>,
+        changes: RefCell<BTreeMap<IdType, Option<TodoFile>>>,
+        history: RefCell<Vec<HistoryEntry>>,
+        /// The journal, only read once it is used.
+        journal: RefCell<Option<Vec<JournalEntry>>>,
+        /// The time entries, only read once they are used.
+        time: RefCell<Option<Vec<TimeEntry>>>,
+        /// The views, only read once they are used.
+        views: RefCell<Option<Vec<View>>>,
+    }
+
+    impl<'a> FileTransaction<'a> {
+        fn begin(dir: &'a Path) -> Result<Self, InternalError> {
+            let transaction = Self {
+                dir,
+                meta: RefCell::new(Meta::default()),
+                changes: RefCell::new(BTreeMap::new()),
+                history: RefCell::new(Vec::new()),
+                journal: RefCell::new(None),
+                time: RefCell::new(None),
+                views: RefCell::new(None),
+            };
+
+            let meta = match fs::read(dir.join(META_FILE)) {
+                Ok(content) => serde_json::from_slice(&content).map_err(json_error)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    let files = transaction.files()?;
+                    Meta {
+                        next_todo_id: files.keys().max().map_or(1, |id| id + 1),
+                        next_task_id: files
+                            .values()
+                            .flat_map(|f| f.tasks.iter().map(|t| *t.id()))
+                            .max()
+                            .map_or(1, |id| id + 1),
+                        next_history_id: 0,
+                    }
+                }
+                Err(e) => return Err(io_error(e)),
+            };
+
+            *transaction.meta.borrow_mut() = meta;
+            Ok(transaction)
+        }
+
+        fn path(&self, id: IdType) -> PathBuf {
+            self.dir.join(format!("{}.json", id))
+        }
+
+        /// Reads every todo file, with the changes not written yet.
+        fn files(&self) -> Result<BTreeMap<IdType, TodoFile>, InternalError> {
+            let mut files = BTreeMap::new();
+
+            for entry in fs::read_dir(self.dir).map_err(io_error)? {
+                let path = entry.map_err(io_error)?.path();
+                let id = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|id| id.parse::<IdType>().ok());
+
+                if let Some(id) = id {
+                    let content = fs::read(&path).map_err(io_error)?;
+                    let file: TodoFile = serde_json::from_slice(&content).map_err(|e| {
+                        InternalError::new(&format!("{}: {}", path.display(), e))
+                    })?;
+                    files.insert(id, file);
+                }
+            }
+
+            for (id, change) in self.changes.borrow().iter() {
+                match change {
+                    Some(file) => files.insert(*id, file.clone()),
+                    None => files.remove(id),
+                };
+            }
+
+            Ok(files)
+        }
+
+        fn file(&self, id: IdType) -> Result<TodoFile, InternalError> {
+            if let Some(change) = self.changes.borrow().get(&id) {
+                return change
+                    .clone()
+                    .ok_or_else(|| InternalError::not_found("todo", id));
+            }
+
+            match fs::read(self.path(id)) {
+                Ok(content) => serde_json::from_slice(&content).map_err(json_error),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    Err(InternalError::not_found("todo", id))
+                }
+                Err(e) => Err(io_error(e)),
+            }
+        }
+
+        /// Finds the file of the todo holding a task.
+        fn file_of_task(&self, id: IdType) -> Result<TodoFile, InternalError> {
+            self.files()?
+                .into_values()
+                .find(|file| file.tasks.iter().any(|t| *t.id() == id))
+                .ok_or_else(|| InternalError::not_found("task", id))
+        }
+
+        /// Reads the history, with the entries not written yet.
+        fn read_history(&self) -> Result<Vec<HistoryEntry>, InternalError> {
+            let file = match File::open(self.dir.join(HISTORY_FILE)) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Ok(self.history.borrow().clone())
+                }
+                Err(e) => return Err(io_error(e)),
+            };
+
+            let mut entries = Vec::new();
+
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(io_error)?;
+
+                if !line.trim().is_empty() {
+                    entries.push(serde_json::from_str(&line).map_err(json_error)?);
+                }
+            }
+
+            entries.extend(self.history.borrow().iter().cloned());
+            Ok(entries)
+        }
+
+        /// Runs the function on the journal, reading it first if needed.
+        fn with_journal<T>(
+            &self,
+            function: impl FnOnce(&mut Vec<JournalEntry>) -> T,
+        ) -> Result<T, InternalError> {
+            let mut journal = self.journal.borrow_mut();
+
+            if journal.is_none() {
+                *journal = Some(match fs::read(self.dir.join(JOURNAL_FILE)) {
+                    Ok(content) => serde_json::from_slice(&content).map_err(json_error)?,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                    Err(e) => return Err(io_error(e)),
+                });
+            }
+
+            Ok(function(journal.as_mut().unwrap()))
+        }
+
+        /// Runs the function on the time entries, reading them first if needed.
+        fn with_time<T>(
+            &self,
+            function: impl FnOnce(&mut Vec<TimeEntry>) -> T,
+        ) -> Result<T, InternalError> {
+            let mut time = self.time.borrow_mut();
+
+            if time.is_none() {
+                *time = Some(match fs::read(self.dir.join(TIME_FILE)) {
+                    Ok(content) => serde_json::from_slice(&content).map_err(json_error)?,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                    Err(e) => return Err(io_error(e)),
+                });
+            }
+
+            Ok(function(time.as_mut().unwrap()))
+        }
+
+        /// Runs the function on the views, reading them first if needed.
+        fn with_views<T>(
+            &self,
+            function: impl FnOnce(&mut Vec<View>) -> T,
+        ) -> Result<T, InternalError> {
+            let mut views = self.views.borrow_mut();
+
+            if views.is_none() {
+                *views = Some(match fs::read(self.dir.join(VIEWS_FILE)) {
+                    Ok(content) => serde_json::from_slice(&content).map_err(json_error)?,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                    Err(e) => return Err(io_error(e)),
+                });
+            }
+
+            Ok(function(views.as_mut().unwrap()))
+        }
+
+        fn save(&self, file: TodoFile) {
+            self.changes.borrow_mut().insert(*file.todo.id(), Some(file));
+        }
+
+        /// Writes the changes, all of them or none, see `Commit`.
+        fn commit(self) -> Result<(), InternalError> {
+            let mut commit = Commit::default();
+            let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+
+            for (id, change) in self.changes.take() {
+                let name = format!("{}.json", id);
+
+                match change {
+                    Some(file) => files.push((name, serde_json::to_vec_pretty(&file).map_err(json_error)?)),
+                    None => commit.removed.push(name),
+                }
+            }
+
+            let meta = serde_json::to_vec_pretty(&self.meta.take()).map_err(json_error)?;
+            files.push((String::from(META_FILE), meta));
+
+            if let Some(journal) = self.journal.take() {
+                files.push((String::from(JOURNAL_FILE), serde_json::to_vec_pretty(&journal).map_err(json_error)?));
+            }
+
+            if let Some(time) = self.time.take() {
+                files.push((String::from(TIME_FILE), serde_json::to_vec_pretty(&time).map_err(json_error)?));
+            }
+
+            if let Some(views) = self.views.take() {
+                files.push((String::from(VIEWS_FILE), serde_json::to_vec_pretty(&views).map_err(json_error)?));
+            }
+
+            for entry in self.history.take() {
+                commit.history.push_str(&serde_json::to_string(&entry).map_err(json_error)?);
+                commit.history.push('\n');
+            }
+
+            commit.history_len = match fs::metadata(self.dir.join(HISTORY_FILE)) {
+                Ok(metadata) => metadata.len(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+                Err(e) => return Err(io_error(e)),
+            };
+
+            for (name, content) in files {
+                let temp = temp_path(&self.dir.join(&name));
+                write_synced(&temp, &content).map_err(io_error)?;
+                let temp = temp.file_name().unwrap().to_string_lossy().into_owned();
+                commit.files.push((temp, name));
+            }
+
+            // Once the commit is written, the changes are made even if it stops.
+            let content = serde_json::to_vec_pretty(&commit).map_err(json_error)?;
+            write_atomic(&self.dir.join(COMMIT_FILE), &content).map_err(io_error)?;
+            commit.apply(self.dir).map_err(io_error)
+        }
+    }
+
+    impl Storage for FileTransaction<'_> {
+        fn init(&self) -> Result<(), InternalError> {
+            Ok(())
+        }
+
+        fn todos(&self) -> Result<Vec<Todo>, InternalError> {
+            Ok(self.files()?.into_values().map(|file| file.todo).collect())
+        }
+
+        fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            self.file(id).map(|file| file.todo)
+        }
+
+        fn insert_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            let now = now();
+            let id = self.meta.borrow().next_todo_id;
+            self.meta.borrow_mut().next_todo_id += 1;
+
+            let todo = Todo::new(
+                id,
+                todo.name().clone(),
+                todo.description().cloned(),
+                now.clone(),
+                now,
+            );
+
+            self.save(TodoFile {
+                todo: todo.clone(),
+                tasks: Vec::new(),
+            });
+            Ok(todo)
+        }
+
+        fn update_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            let mut file = self.file(*todo.id())?;
+
+            if file.todo != todo {
+                file.todo = todo;
+                file.todo.set_updated_at(&now());
+            }
+
+            let todo = file.todo.clone();
+            self.save(file);
+            Ok(todo)
+        }
+
+        fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            self.file(id)?;
+            self.changes.borrow_mut().insert(id, None);
+            Ok(())
+        }
+
+        fn tasks(&self) -> Result<Vec<Task>, InternalError> {
+            Ok(self
+                .files()?
+                .into_values()
+                .flat_map(|file| file.tasks)
+                .collect())
+        }
+
+        fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            let file = self.file_of_task(id)?;
+            let task = file.tasks.into_iter().find(|t| *t.id() == id);
+            task.ok_or_else(|| InternalError::not_found("task", id))
+        }
+
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError> {
+            let mut file = self.file(*task.todo_id())?;
+            let now = now();
+            let id = self.meta.borrow().next_task_id;
+            self.meta.borrow_mut().next_task_id += 1;
+
+            let status = task.status().clone().stamped(&now);
+            let mut new = Task::new(id, *file.todo.id(), task.what(), &now, &now, status);
+            new.set_estimate(task.estimate());
+            new.set_due(task.due().map(|due| due.as_str()));
+            new.set_priority(task.priority());
+            let task = new;
+
+            file.tasks.push(task.clone());
+            self.save(file);
+            Ok(task)
+        }
+
+        fn update_task(&self, task: Task) -> Result<Task, InternalError> {
+            let mut file = self.file_of_task(*task.id())?;
+            let position = file.tasks.iter().position(|t| t.id() == task.id()).unwrap();
+            let old = file.tasks.remove(position);
+            let mut task = task;
+
+            if old != task {
+                let now = now();
+                task.set_status(task.status().clone().stamped(&now));
+                task.set_updated_at(&now);
+            } else {
+                task = old;
+            }
+
+            // A task moved to another todo goes to the file of that todo.
+            if task.todo_id() == file.todo.id() {
+                file.tasks.insert(position, task.clone());
+            } else {
+                let mut target = self.file(*task.todo_id())?;
+                target.tasks.push(task.clone());
+                self.save(target);
+            }
+
+            self.save(file);
+            Ok(task)
+        }
+
+        fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            let mut file = self.file_of_task(id)?;
+            file.tasks.retain(|t| *t.id() != id);
+            self.save(file);
+            Ok(())
+        }
+
+        fn todo_tasks(&self, todo_id: IdType) -> Result<Vec<Task>, InternalError> {
+            match self.file(todo_id) {
+                Ok(file) => Ok(file.tasks),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+                Err(e) => Err(e),
+            }
+        }
+
+        fn insert_history(&self, entry: HistoryEntry) -> Result<HistoryEntry, InternalError> {
+            let id = match self.meta.borrow().next_history_id {
+                // The history is never shortened, so its length is the last id given.
+                0 => self.read_history()?.len() as IdType + 1,
+                id => id,
+            };
+            self.meta.borrow_mut().next_history_id = id + 1;
+            let mut entry = entry.with_id(id);
+
+            if entry.timestamp() == "CURRENT_TIMESTAMP" {
+                entry.set_timestamp(&now());
+            }
+
+            self.history.borrow_mut().push(entry.clone());
+            Ok(entry)
+        }
+
+        fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, InternalError> {
+            Ok(self
+                .read_history()?
+                .into_iter()
+                .filter(|entry| query.matches(entry))
+                .collect())
+        }
+
+        fn restore_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            let id = *todo.id();
+            let tasks = match self.file(id) {
+                Ok(file) => file.tasks,
+                Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e),
+            };
+
+            let mut meta = self.meta.borrow_mut();
+            meta.next_todo_id = meta.next_todo_id.max(id + 1);
+
+            self.save(TodoFile {
+                todo: todo.clone(),
+                tasks,
+            });
+            Ok(todo)
+        }
+
+        fn restore_task(&self, task: Task) -> Result<Task, InternalError> {
+            let id = *task.id();
+            let mut file = self.file(*task.todo_id())?;
+
+            match self.file_of_task(id) {
+                Ok(mut old) if old.todo.id() != file.todo.id() => {
+                    old.tasks.retain(|t| *t.id() != id);
+                    self.save(old);
+                }
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => (),
+            }
+
+            file.tasks.retain(|t| *t.id() != id);
+            file.tasks.push(task.clone());
+            file.tasks.sort_by_key(|t| *t.id());
+
+            let mut meta = self.meta.borrow_mut();
+            meta.next_task_id = meta.next_task_id.max(id + 1);
+
+            self.save(file);
+            Ok(task)
+        }
+
+        fn insert_journal(&self, entry: JournalEntry) -> Result<JournalEntry, InternalError> {
+            self.with_journal(|journal| {
+                let id = journal.last().map_or(1, |last| last.id() + 1);
+                let entry = entry.with_id(id);
+                journal.push(entry.clone());
+                entry
+            })
+        }
+
+        fn journal(&self) -> Result<Vec<JournalEntry>, InternalError> {
+            self.with_journal(|journal| journal.clone())
+        }
+
+        fn set_undone(&self, operation: IdType, undone: bool) -> Result<(), InternalError> {
+            self.with_journal(|journal| {
+                for entry in journal.iter_mut().filter(|e| *e.operation() == operation) {
+                    entry.set_undone(undone);
+                }
+            })
+        }
+
+        fn clear_undone(&self) -> Result<(), InternalError> {
+            self.with_journal(|journal| journal.retain(|entry| !entry.undone()))
+        }
+
+        fn insert_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            self.with_time(|time| {
+                let id = time.last().map_or(1, |last| last.id() + 1);
+                let entry = entry.with_id(id);
+                time.push(entry.clone());
+                entry
+            })
+        }
+
+        fn update_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            self.with_time(|time| match time.iter_mut().find(|e| e.id() == entry.id()) {
+                Some(old) => {
+                    *old = entry.clone();
+                    Ok(entry)
+                }
+                None => Err(InternalError::not_found("time entry", *entry.id())),
+            })?
+        }
+
+        fn time_entries(&self, query: &TimeQuery) -> Result<Vec<TimeEntry>, InternalError> {
+            let mut entries: Vec<TimeEntry> =
+                self.with_time(|time| time.iter().filter(|e| query.matches(e)).cloned().collect())?;
+            entries.sort_by(|a, b| a.started_at().cmp(b.started_at()).then(a.id().cmp(b.id())));
+            Ok(entries)
+        }
+
+        fn save_view(&self, view: View) -> Result<View, InternalError> {
+            self.with_views(|views| {
+                views.retain(|v| v.name() != view.name());
+                views.push(view.clone());
+                views.sort_by(|a, b| a.name().cmp(b.name()));
+                view
+            })
+        }
+
+        fn views(&self) -> Result<Vec<View>, InternalError> {
+            self.with_views(|views| views.clone())
+        }
+
+        fn delete_view(&self, name: &str) -> Result<(), InternalError> {
+            self.with_views(|views| views.retain(|v| v.name() != name))
+        }
+
+        fn transaction(
+            &self,
+            operation: &mut dyn FnMut(&dyn Storage) -> Result<(), InternalError>,
+        ) -> Result<(), InternalError> {
+            let changes = self.changes.borrow().clone();
+            let meta = self.meta.borrow().clone();
+            let history = self.history.borrow().len();
+            let journal = self.journal.borrow().clone();
+            let time = self.time.borrow().clone();
+            let views = self.views.borrow().clone();
+
+            let res = operation(self);
+
+            if res.is_err() {
+                *self.changes.borrow_mut() = changes;
+                *self.meta.borrow_mut() = meta;
+                self.history.borrow_mut().truncate(history);
+                *self.journal.borrow_mut() = journal;
+                *self.time.borrow_mut() = time;
+                *self.views.borrow_mut() = views;
+            }
+
+            res
+        }
+    }
+
+    /// Storage keeping each todo, with its tasks, as a json file
+    /// inside of a directory. Every operation locks the directory,
+    /// and the files are replaced atomically.
+    pub struct FileStorage {
+        dir: PathBuf,
+    }
+
+    impl FileStorage {
+        /// Uses the given directory, creating it if needed.
+        pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, InternalError> {
+            let dir = dir.as_ref().to_path_buf();
+            fs::create_dir_all(&dir).map_err(io_error)?;
+            Ok(Self { dir })
+        }
+
+        /// References the directory of the files.
+        pub fn dir(&self) -> &Path {
+            &self.dir
+        }
+
+        fn lock(&self, exclusive: bool) -> Result<File, InternalError> {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(self.dir.join(LOCK_FILE))
+                .map_err(io_error)?;
+
+            if exclusive {
+                file.lock_exclusive().map_err(io_error)?;
+            } else {
+                FileExt::lock_shared(&file).map_err(io_error)?;
+            }
+
+            Ok(file)
+        }
+
+        fn read<T>(
+            &self,
+            operation: impl FnOnce(&FileTransaction) -> Result<T, InternalError>,
+        ) -> Result<T, InternalError> {
+            if self.dir.join(COMMIT_FILE).exists() {
+                let _lock = self.lock(true)?;
+                recover(&self.dir)?;
+            }
+
+            let _lock = self.lock(false)?;
+            operation(&FileTransaction::begin(&self.dir)?)
+        }
+
+        fn write<T>(
+            &self,
+            operation: impl FnOnce(&FileTransaction) -> Result<T, InternalError>,
+        ) -> Result<T, InternalError> {
+            let _lock = self.lock(true)?;
+            recover(&self.dir)?;
+            let transaction = FileTransaction::begin(&self.dir)?;
+            let value = operation(&transaction)?;
+            transaction.commit()?;
+            Ok(value)
+        }
+    }
+
+    impl Storage for FileStorage {
+        fn init(&self) -> Result<(), InternalError> {
+            Ok(())
+        }
+
+        fn todos(&self) -> Result<Vec<Todo>, InternalError> {
+            self.read(|files| files.todos())
+        }
+
+        fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            self.read(|files| files.find_todo(id))
+        }
+
+        fn insert_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.write(|files| files.insert_todo(todo))
+        }
+
+        fn update_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.write(|files| files.update_todo(todo))
+        }
+
+        fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            self.write(|files| files.delete_todo(id))
+        }
+
+        fn tasks(&self) -> Result<Vec<Task>, InternalError> {
+            self.read(|files| files.tasks())
+        }
+
+        fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            self.read(|files| files.find_task(id))
+        }
+
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.write(|files| files.insert_task(task))
+        }
+
+        fn update_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.write(|files| files.update_task(task))
+        }
+
+        fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            self.write(|files| files.delete_task(id))
+        }
+
+        fn todo_tasks(&self, todo_id: IdType) -> Result<Vec<Task>, InternalError> {
+            self.read(|files| files.todo_tasks(todo_id))
+        }
+
+        fn insert_history(&self, entry: HistoryEntry) -> Result<HistoryEntry, InternalError> {
+            self.write(|files| files.insert_history(entry))
+        }
+
+        fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, InternalError> {
+            self.read(|files| files.history(query))
+        }
+
+        fn restore_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.write(|files| files.restore_todo(todo))
+        }
+
+        fn restore_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.write(|files| files.restore_task(task))
+        }
+
+        fn insert_journal(&self, entry: JournalEntry) -> Result<JournalEntry, InternalError> {
+            self.write(|files| files.insert_journal(entry))
+        }
+
+        fn journal(&self) -> Result<Vec<JournalEntry>, InternalError> {
+            self.read(|files| files.journal())
+        }
+
+        fn set_undone(&self, operation: IdType, undone: bool) -> Result<(), InternalError> {
+            self.write(|files| files.set_undone(operation, undone))
+        }
+
+        fn clear_undone(&self) -> Result<(), InternalError> {
+            self.write(|files| files.clear_undone())
+        }
+
+        fn insert_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            self.write(|files| files.insert_time_entry(entry))
+        }
+
+        fn update_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            self.write(|files| files.update_time_entry(entry))
+        }
+
+        fn time_entries(&self, query: &TimeQuery) -> Result<Vec<TimeEntry>, InternalError> {
+            self.read(|files| files.time_entries(query))
+        }
+
+        fn save_view(&self, view: View) -> Result<View, InternalError> {
+            self.write(|files| files.save_view(view))
+        }
+
+        fn views(&self) -> Result<Vec<View>, InternalError> {
+            self.read(|files| files.views())
+        }
+
+        fn delete_view(&self, name: &str) -> Result<(), InternalError> {
+            self.write(|files| files.delete_view(name))
+        }
+
+        fn transaction(
+            &self,
+            operation: &mut dyn FnMut(&dyn Storage) -> Result<(), InternalError>,
+        ) -> Result<(), InternalError> {
+            self.write(|files| operation(files))
+        }
+    }
+}
+
+mod data_access_layer {
+    use super::core::*;
+    use super::database::*;
+    use super::history::*;
+    use super::hooks::*;
+    use super::journal::*;
+    use super::stats::*;
+    use super::storage::*;
+    use super::time_tracking::*;
+    use super::views::*;
+    use lazy_static::lazy_static;
+    use sqlite::{Connection, Value};
+    use std::sync::{Arc, RwLock};
+    use std::time::Duration;
+
+    trait BasicDataModelTrait: DatabaseConnectorTrait {
+        type ObjType;
+
+        fn all(connection: &Connection) -> Vec<Self::ObjType>;
+        fn find(connection: &Connection, id: IdType) -> Result<Self::ObjType, InternalError>;
+        fn add(connection: &Connection, obj: Self::ObjType)
+            -> Result<Self::ObjType, InternalError>;
+        fn update(
+            connection: &Connection,
+            obj: Self::ObjType,
+        ) -> Result<Self::ObjType, InternalError>;
+        fn delete(connection: &Connection, id: IdType) -> Result<(), InternalError>;
+    }
+
+    struct TodoModel;
+    struct TaskModel;
+
+    /// Columns read from the todos table, in the order used by `TodoModel::from_row`.
+    const TODO_COLUMNS: &str =
+        "id, name, description, created_at, updated_at, deleted_at, archived_at";
+
+    /// Columns read from the tasks table, in the order used by `TaskModel::from_row`.
+    const TASK_COLUMNS: &str =
+        "id, what, todo_id, created_at, updated_at, completed_at, deleted_at, status, status_at, reason, estimate, due, priority";
+
+    /// Sets a date from a bound parameter, the text
+    /// `CURRENT_TIMESTAMP` is replaced by the current date.
+    const DATE_OR_NOW: &str =
+        "CASE ?1 WHEN 'CURRENT_TIMESTAMP' THEN CURRENT_TIMESTAMP ELSE ?1 END";
+
+    impl TodoModel {
+        fn from_row(row: &[Value]) -> Todo {
+            let id: IdType = row[0].as_integer().unwrap() as IdType;
+            let name = row[1].as_string().unwrap();
+            let description = row[2].as_string().map(String::from);
+            let created_at = row[3].as_string().unwrap();
+            let updated_at = row[4].as_string().unwrap();
+
+            let mut todo = Todo::new(
+                id,
+                name.into(),
+                description,
+                created_at.into(),
+                updated_at.into(),
+            );
+
+            todo.set_deleted_at(row[5].as_string());
+            todo.set_archived_at(row[6].as_string());
+            todo
+        }
+
+        /// Writes the todo as given, with its id and dates.
+        fn restore(connection: &Connection, todo: Todo) -> Result<Todo, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let statement = format!(
+                "INSERT OR REPLACE INTO {}(id, name, description, created_at, updated_at, deleted_at, archived_at) VALUES (?, ?, ?, ?, ?, ?, ?);",
+                Self::table_name()
+            );
+
+            execute(
+                connection,
+                &statement,
+                &[
+                    Value::Integer(*todo.id() as i64),
+                    Value::String(todo.name().clone()),
+                    text(todo.description()),
+                    Value::String(todo.created_at().clone()),
+                    Value::String(todo.updated_at().clone()),
+                    text(todo.deleted_at()),
+                    text(todo.archived_at()),
+                ],
+            )?;
+
+            Self::find(connection, *todo.id())
+        }
+    }
+
+    impl DatabaseConnectorTrait for TodoModel {
+        fn table_name() -> &'static str {
+            "todos"
+        }
+
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error> {
+            if !Self::is_table_initialized(connection) {
+                let sttmt = format!(
+                    "CREATE TABLE IF NOT EXISTS {}(
+                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    description TEXT,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    deleted_at DATETIME,
+                    archived_at DATETIME);",
+                    Self::table_name()
+                );
+
+                connection.execute(&sttmt)
+            } else {
+                Self::ensure_column(connection, "deleted_at", "DATETIME")?;
+                Self::ensure_column(connection, "archived_at", "DATETIME")
+            }
+        }
+    }
+
+    impl BasicDataModelTrait for TodoModel {
+        type ObjType = Todo;
+
+        fn all(connection: &Connection) -> Vec<Self::ObjType> {
+            if !Self::is_table_initialized(connection) {
+                return Vec::new();
+            }
+
+            let query_ = format!("SELECT {} FROM {};", TODO_COLUMNS, Self::table_name());
+
+            query(connection, &query_, &[])
+                .map(|rows| rows.iter().map(|row| Self::from_row(row)).collect())
+                .unwrap_or_default()
+        }
+
+        fn find(connection: &Connection, id: IdType) -> Result<Self::ObjType, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let query_ = format!(
+                "SELECT {} FROM {} WHERE id = ?;",
+                TODO_COLUMNS,
+                Self::table_name()
+            );
+
+            let rows = query(connection, &query_, &[Value::Integer(id as i64)])?;
+
+            rows.first()
+                .map(|row| Self::from_row(row))
+                .ok_or_else(|| InternalError::not_found("todo", id))
+        }
+
+        fn update(
+            connection: &Connection,
+            obj: Self::ObjType,
+        ) -> Result<Self::ObjType, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let todo = Self::find(connection, *obj.id())?;
+
+            /* Here what can be changed currently are the name, description, and the trash and archive dates. */
+
+            if obj != todo {
+                let statement = format!(
+                    "UPDATE {} SET name = ?, description = ?, deleted_at = ?, archived_at = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?;",
+                    Self::table_name()
+                );
+
+                execute(
+                    connection,
+                    &statement,
+                    &[
+                        Value::String(obj.name().clone()),
+                        text(obj.description()),
+                        text(obj.deleted_at()),
+                        text(obj.archived_at()),
+                        Value::Integer(*obj.id() as i64),
+                    ],
+                )?;
+            }
+
+            Self::find(connection, *obj.id())
+        }
+
+        fn add(connection: &Connection, obj: Self::ObjType) -> Result<Todo, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                Err(InternalError::table_not_initialized(Self::table_name()))
+            } else if Self::find(connection, *obj.id()).is_ok() {
+                Err(InternalError::already_exists("todo", *obj.id()))
+            } else {
+                let statement = format!(
+                    "INSERT INTO {}(name, description) VALUES (?, ?);",
+                    Self::table_name()
+                );
+
+                execute(
+                    connection,
+                    &statement,
+                    &[Value::String(obj.name().clone()), text(obj.description())],
+                )?;
+
+                Self::find(connection, last_insert_id(connection)?).map_err(|_| {
+                    InternalError::new("Could not get the todo after adding it to the database.")
+                })
+            }
+        }
+
+        fn delete(connection: &Connection, id: IdType) -> Result<(), InternalError> {
+            Self::find(connection, id)?;
+            let statement = format!("DELETE FROM {} WHERE id = ?;", Self::table_name());
+            execute(connection, &statement, &[Value::Integer(id as i64)])?;
+            Ok(())
+        }
+    }
+
+    impl TaskModel {
+        fn from_row(row: &[Value]) -> Task {
+            let id: IdType = row[0].as_integer().unwrap() as IdType;
+            let what = row[1].as_string().unwrap();
+            let todo_id = row[2].as_integer().unwrap() as IdType;
+            let created_at = row[3].as_string().unwrap();
+            let updated_at = row[4].as_string().unwrap();
+            let since = row[8].as_string().map(String::from);
+            // Tasks written before the status column only have the completion date.
+            let status = match row[7].as_string() {
+                Some("in_progress") => Status::InProgress(since.unwrap_or_default()),
+                Some("blocked") => Status::Blocked {
+                    reason: row[9].as_string().map(String::from).unwrap_or_default(),
+                    since: since.unwrap_or_default(),
+                },
+                Some("cancelled") => Status::Cancelled(since.unwrap_or_default()),
+                _ => row[5]
+                    .as_string()
+                    .map(|date| Status::Done(date.into()))
+                    .unwrap_or(Status::Todo),
+            };
+
+            let mut task = Task::new(id, todo_id, what, created_at, updated_at, status);
+            task.set_deleted_at(row[6].as_string());
+            task.set_estimate(row[10].as_float());
+            task.set_due(row[11].as_string());
+            task.set_priority(row[12].as_string().and_then(|p| p.parse().ok()));
+            task
+        }
+
+        /// Writes the task as given, with its id and dates.
+        fn restore(connection: &Connection, task: Task) -> Result<Task, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            TodoModel::find(connection, *task.todo_id())?;
+
+            let statement = format!(
+                "INSERT OR REPLACE INTO {}(id, what, todo_id, created_at, updated_at, deleted_at, estimate, due, priority,
+                completed_at, status, status_at, reason) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);",
+                Self::table_name()
+            );
+
+            let mut values = vec![
+                Value::Integer(*task.id() as i64),
+                Value::String(task.what().clone()),
+                Value::Integer(*task.todo_id() as i64),
+                Value::String(task.created_at().clone()),
+                Value::String(task.updated_at().clone()),
+                text(task.deleted_at()),
+                Self::estimate(&task),
+                text(task.due()),
+                Self::priority(&task),
+            ];
+            values.extend(Self::status_values(task.status()));
+
+            execute(connection, &statement, &values)?;
+
+            Self::find(connection, *task.id())
+        }
+
+        fn estimate(task: &Task) -> Value {
+            task.estimate().map(Value::Float).unwrap_or(Value::Null)
+        }
+
+        fn priority(task: &Task) -> Value {
+            task.priority()
+                .map(|priority| Value::String(String::from(priority.name())))
+                .unwrap_or(Value::Null)
+        }
+
+        /// The values of the completed_at, status, status_at and reason
+        /// columns, with the current date in place of `CURRENT_TIMESTAMP`.
+        fn status_values(status: &Status) -> [Value; 4] {
+            let status = status.clone().stamped(&now());
+            let completed_at = match &status {
+                Status::Done(date) => Some(date),
+                _ => None,
+            };
+            let since = match &status {
+                Status::Done(_) => None,
+                status => status.since(),
+            };
+            let reason = match &status {
+                Status::Blocked { reason, .. } => Some(reason),
+                _ => None,
+            };
+
+            [
+                text(completed_at),
+                Value::String(String::from(status.name())),
+                text(since),
+                text(reason),
+            ]
+        }
+
+        /// Lists the tasks matching a query.
+        fn query(connection: &Connection, task_query: &TaskQuery) -> Result<Vec<Task>, InternalError> {
+            if !TodoModel::is_table_initialized(connection)
+                || !Self::is_table_initialized(connection)
+            {
+                return Ok(Vec::new());
+            }
+
+            let mut conditions = vec![String::from("1")];
+            let mut values = Vec::new();
+
+            if let Some(todo_id) = task_query.todo_id {
+                values.push(Value::Integer(todo_id as i64));
+                conditions.push(format!("todo_id = ?{}", values.len()));
+            }
+
+            if let Some(done) = task_query.done {
+                let null = if done { "NOT NULL" } else { "NULL" };
+                conditions.push(format!("completed_at IS {}", null));
+            }
+
+            if let Some(text) = &task_query.text {
+                values.push(Value::String(format!("%{}%", text.to_lowercase())));
+                conditions.push(format!("LOWER(what) LIKE ?{}", values.len()));
+            }
+
+            if let Some(open) = task_query.open {
+                // Tasks written before the status column are closed when completed.
+                let closed = "(completed_at IS NOT NULL OR IFNULL(status, '') = 'cancelled')";
+                conditions.push(if open { format!("NOT {}", closed) } else { String::from(closed) });
+            }
+
+            if let Some(from) = &task_query.due_from {
+                values.push(Value::String(from.clone()));
+                conditions.push(format!("due >= ?{}", values.len()));
+            }
+
+            if let Some(to) = &task_query.due_to {
+                values.push(Value::String(to.clone()));
+                conditions.push(format!("due <= ?{}", values.len()));
+            }
+
+            if let Some(status) = &task_query.status {
+                values.push(Value::String(status.clone()));
+                conditions.push(format!(
+                    "COALESCE(status, CASE WHEN completed_at IS NULL THEN 'todo' ELSE 'done' END) = ?{}",
+                    values.len()
+                ));
+            }
+
+            if let Some(priority) = task_query.priority {
+                values.push(Value::String(String::from(priority.name())));
+                conditions.push(format!("priority = ?{}", values.len()));
+            }
+
+            if let Some(date) = &task_query.created_before {
+                values.push(Value::String(date.clone()));
+                conditions.push(format!("created_at < ?{}", values.len()));
+            }
+
+            let query_ = format!(
+                "SELECT {} FROM {} WHERE {};",
+                TASK_COLUMNS,
+                Self::table_name(),
+                conditions.join(" AND ")
+            );
+
+            Ok(query(connection, &query_, &values)?
+                .iter()
+                .map(|row| Self::from_row(row))
+                .collect())
+        }
+    }
+
+    impl DatabaseConnectorTrait for TaskModel {
+        fn table_name() -> &'static str {
+            "tasks"
+        }
+
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error> {
+            if !Self::is_table_initialized(connection) {
+                let sttmt = format!(
+                    "CREATE TABLE IF NOT EXISTS {}(
+                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                    what TEXT NOT NULL,
+                    todo_id INTEGER NOT NULL,
+                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    completed_at DATETIME,
+                    deleted_at DATETIME,
+                    status TEXT,
+                    status_at DATETIME,
+                    reason TEXT,
+                    estimate REAL,
+                    due DATE,
+                    priority TEXT,
+                    FOREIGN KEY (todo_id) REFERENCES Todos(todo_id) ON DELETE SET NULL);",
+                    Self::table_name()
+                );
+
+                connection.execute(&sttmt)
+            } else {
+                Self::ensure_column(connection, "deleted_at", "DATETIME")?;
+                Self::ensure_column(connection, "status", "TEXT")?;
+                Self::ensure_column(connection, "status_at", "DATETIME")?;
+                Self::ensure_column(connection, "reason", "TEXT")?;
+                Self::ensure_column(connection, "estimate", "REAL")?;
+                Self::ensure_column(connection, "due", "DATE")?;
+                Self::ensure_column(connection, "priority", "TEXT")
+            }
+        }
+    }
+
+    impl BasicDataModelTrait for TaskModel {
+        type ObjType = Task;
+
+        fn all(connection: &Connection) -> Vec<Self::ObjType> {
+            if !Self::is_table_initialized(connection) {
+                return Vec::new();
+            }
+
+            let query_ = format!("SELECT {} FROM {};", TASK_COLUMNS, Self::table_name());
+
+            query(connection, &query_, &[])
+                .map(|rows| rows.iter().map(|row| Self::from_row(row)).collect())
+                .unwrap_or_default()
+        }
+
+        fn find(connection: &Connection, id: IdType) -> Result<Self::ObjType, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let query_ = format!(
+                "SELECT {} FROM {} WHERE id = ?;",
+                TASK_COLUMNS,
+                Self::table_name()
+            );
+
+            let rows = query(connection, &query_, &[Value::Integer(id as i64)])?;
+
+            rows.first()
+                .map(|row| Self::from_row(row))
+                .ok_or_else(|| InternalError::not_found("task", id))
+        }
+
+        fn add(connection: &Connection, obj: Self::ObjType) -> Result<Self::ObjType, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                Err(InternalError::table_not_initialized(Self::table_name()))
+            } else if Self::find(connection, *obj.id()).is_ok() {
+                Err(InternalError::already_exists("task", *obj.id()))
+            } else {
+                let todo = TodoModel::find(connection, *obj.todo_id())?;
+
+                let statement = format!(
+                    "INSERT INTO {}(todo_id, what, estimate, due, priority, completed_at, status, status_at, reason)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);",
+                    Self::table_name()
+                );
+
+                let mut values = vec![
+                    Value::Integer(*todo.id() as i64),
+                    Value::String(obj.what().clone()),
+                    Self::estimate(&obj),
+                    text(obj.due()),
+                    Self::priority(&obj),
+                ];
+                values.extend(Self::status_values(obj.status()));
+
+                execute(connection, &statement, &values)?;
+
+                Self::find(connection, last_insert_id(connection)?).map_err(|_| {
+                    InternalError::new("Could not get the task after adding it to the database.")
+                })
+            }
+        }
+
+        fn update(
+            connection: &Connection,
+            obj: Self::ObjType,
+        ) -> Result<Self::ObjType, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let task = Self::find(connection, *obj.id())?;
+
+            // Here what can be changed currently are: the todo, what, status (with its date
+            // and reason), estimate, due date, priority and deleted_at.
+
+            if obj.todo_id() != task.todo_id() {
+                TodoModel::find(connection, *obj.todo_id())?;
+            }
+
+            if obj.todo_id() != task.todo_id()
+                || obj.what() != task.what()
+                || obj.status() != task.status()
+                || obj.estimate() != task.estimate()
+                || obj.due() != task.due()
+                || obj.priority() != task.priority()
+                || obj.deleted_at() != task.deleted_at()
+            {
+                let statement = format!(
+                    "UPDATE {} SET what = ?1, deleted_at = ?2, estimate = ?3, due = ?4, priority = ?5, completed_at = ?6,
+                    status = ?7, status_at = ?8, reason = ?9, todo_id = ?10, updated_at = CURRENT_TIMESTAMP WHERE id = ?11;",
+                    Self::table_name()
+                );
+
+                let mut values = vec![
+                    Value::String(obj.what().clone()),
+                    text(obj.deleted_at()),
+                    Self::estimate(&obj),
+                    text(obj.due()),
+                    Self::priority(&obj),
+                ];
+                values.extend(Self::status_values(obj.status()));
+                values.push(Value::Integer(*obj.todo_id() as i64));
+                values.push(Value::Integer(*obj.id() as i64));
+
+                execute(connection, &statement, &values)?;
+            }
+
+            Self::find(connection, *obj.id())
+        }
+
+        fn delete(connection: &Connection, id: IdType) -> Result<(), InternalError> {
+            let task = Self::find(connection, id)?;
+            let statement = format!("DELETE FROM {} WHERE id = ?;", Self::table_name());
+            execute(connection, &statement, &[Value::Integer(*task.id() as i64)])?;
+            Ok(())
+        }
+    }
+
+    struct HistoryModel;
+
+    /// Columns read from the history table, in the order used by `HistoryModel::from_row`.
+    const HISTORY_COLUMNS: &str =
+        "id, entity, entity_id, todo_id, action, field, old_value, new_value, timestamp, actor";
+
+    impl HistoryModel {
+        fn from_row(row: &[Value]) -> Result<HistoryEntry, InternalError> {
+            Ok(HistoryEntry::new(
+                row[0].as_integer().unwrap() as IdType,
+                row[1].as_string().unwrap_or_default().parse()?,
+                row[2].as_integer().unwrap() as IdType,
+                row[3].as_integer().unwrap() as IdType,
+                row[4].as_string().unwrap_or_default().parse()?,
+                row[5].as_string().unwrap(),
+                row[6].as_string().map(String::from),
+                row[7].as_string().map(String::from),
+                row[8].as_string().unwrap(),
+                row[9].as_string().unwrap(),
+            ))
+        }
+
+        fn add(connection: &Connection, entry: HistoryEntry) -> Result<HistoryEntry, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let statement = format!(
+                "INSERT INTO {}(timestamp, entity, entity_id, todo_id, action, field, old_value, new_value, actor)
+                VALUES ({}, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);",
+                Self::table_name(),
+                DATE_OR_NOW
+            );
+
+            execute(
+                connection,
+                &statement,
+                &[
+                    Value::String(entry.timestamp().clone()),
+                    Value::String(entry.entity().name().into()),
+                    Value::Integer(*entry.entity_id() as i64),
+                    Value::Integer(*entry.todo_id() as i64),
+                    Value::String(entry.action().name().into()),
+                    Value::String(entry.field().clone()),
+                    text(entry.old_value()),
+                    text(entry.new_value()),
+                    Value::String(entry.actor().clone()),
+                ],
+            )?;
+
+            let query_ = format!(
+                "SELECT {} FROM {} WHERE id = ?;",
+                HISTORY_COLUMNS,
+                Self::table_name()
+            );
+            let id = Value::Integer(last_insert_id(connection)? as i64);
+
+            match query(connection, &query_, &[id])?.first() {
+                Some(row) => Self::from_row(row),
+                None => Err(InternalError::new("Could not get the entry after adding it to the history.")),
+            }
+        }
+
+        /// Lists the entries matching a query, oldest first.
+        fn query(
+            connection: &Connection,
+            history_query: &HistoryQuery,
+        ) -> Result<Vec<HistoryEntry>, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Ok(Vec::new());
+            }
+
+            let mut conditions = vec![String::from("1")];
+            let mut values = Vec::new();
+
+            if let Some(entity) = history_query.entity {
+                values.push(Value::String(entity.name().into()));
+                conditions.push(format!("entity = ?{}", values.len()));
+            }
+
+            if let Some(entity_id) = history_query.entity_id {
+                values.push(Value::Integer(entity_id as i64));
+                conditions.push(format!("entity_id = ?{}", values.len()));
+            }
+
+            if let Some(todo_id) = history_query.todo_id {
+                values.push(Value::Integer(todo_id as i64));
+                conditions.push(format!("todo_id = ?{}", values.len()));
+            }
+
+            let query_ = format!(
+                "SELECT {} FROM {} WHERE {} ORDER BY id;",
+                HISTORY_COLUMNS,
+                Self::table_name(),
+                conditions.join(" AND ")
+            );
+
+            query(connection, &query_, &values)?
+                .iter()
+                .map(|row| Self::from_row(row))
+                .collect()
+        }
+    }
+
+    impl DatabaseConnectorTrait for HistoryModel {
+        fn table_name() -> &'static str {
+            "history"
+        }
+
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error> {
+            if !Self::is_table_initialized(connection) {
+                let sttmt = format!(
+                    "CREATE TABLE IF NOT EXISTS {}(
+                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                    entity TEXT NOT NULL,
+                    entity_id INTEGER NOT NULL,
+                    todo_id INTEGER NOT NULL,
+                    action TEXT NOT NULL,
+                    field TEXT NOT NULL,
+                    old_value TEXT,
+                    new_value TEXT,
+                    timestamp DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    actor TEXT NOT NULL);",
+                    Self::table_name()
+                );
+
+                connection.execute(&sttmt)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct JournalModel;
+
+    /// Columns read from the journal table, in the order used by `JournalModel::from_row`.
+    const JOURNAL_COLUMNS: &str = "id, operation, before, after, undone";
+
+    impl JournalModel {
+        fn snapshot(value: &Value) -> Result<Option<Snapshot>, InternalError> {
+            match value.as_string() {
+                Some(json) => serde_json::from_str(json)
+                    .map(Some)
+                    .map_err(|e| InternalError::new(&e.to_string())),
+                None => Ok(None),
+            }
+        }
+
+        fn json(snapshot: Option<&Snapshot>) -> Result<Value, InternalError> {
+            match snapshot {
+                Some(snapshot) => serde_json::to_string(snapshot)
+                    .map(Value::String)
+                    .map_err(|e| InternalError::new(&e.to_string())),
+                None => Ok(Value::Null),
+            }
+        }
+
+        fn from_row(row: &[Value]) -> Result<JournalEntry, InternalError> {
+            Ok(JournalEntry::new(
+                row[0].as_integer().unwrap() as IdType,
+                row[1].as_integer().unwrap() as IdType,
+                Self::snapshot(&row[2])?,
+                Self::snapshot(&row[3])?,
+                row[4].as_integer().unwrap_or_default() != 0,
+            ))
+        }
+
+        fn add(connection: &Connection, entry: JournalEntry) -> Result<JournalEntry, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let statement = format!(
+                "INSERT INTO {}(operation, before, after, undone) VALUES (?, ?, ?, ?);",
+                Self::table_name()
+            );
+
+            execute(
+                connection,
+                &statement,
+                &[
+                    Value::Integer(*entry.operation() as i64),
+                    Self::json(entry.before())?,
+                    Self::json(entry.after())?,
+                    Value::Integer(entry.undone() as i64),
+                ],
+            )?;
+
+            let entry = entry.with_id(last_insert_id(connection)?);
+
+            // The first change of an operation gives it its id.
+            let statement = format!("UPDATE {} SET operation = ? WHERE id = ?;", Self::table_name());
+            execute(
+                connection,
+                &statement,
+                &[
+                    Value::Integer(*entry.operation() as i64),
+                    Value::Integer(*entry.id() as i64),
+                ],
+            )?;
+
+            Ok(entry)
+        }
+
+        fn all(connection: &Connection) -> Result<Vec<JournalEntry>, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Ok(Vec::new());
+            }
+
+            let query_ = format!(
+                "SELECT {} FROM {} ORDER BY id;",
+                JOURNAL_COLUMNS,
+                Self::table_name()
+            );
+
+            query(connection, &query_, &[])?
+                .iter()
+                .map(|row| Self::from_row(row))
+                .collect()
+        }
+
+        fn set_undone(connection: &Connection, operation: IdType, undone: bool) -> Result<(), InternalError> {
+            let statement = format!("UPDATE {} SET undone = ? WHERE operation = ?;", Self::table_name());
+            execute(
+                connection,
+                &statement,
+                &[Value::Integer(undone as i64), Value::Integer(operation as i64)],
+            )?;
+            Ok(())
+        }
+
+        fn clear_undone(connection: &Connection) -> Result<(), InternalError> {
+            let statement = format!("DELETE FROM {} WHERE undone = 1;", Self::table_name());
+            execute(connection, &statement, &[])?;
+            Ok(())
+        }
+    }
+
+    impl DatabaseConnectorTrait for JournalModel {
+        fn table_name() -> &'static str {
+            "journal"
+        }
+
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error> {
+            if !Self::is_table_initialized(connection) {
+                let sttmt = format!(
+                    "CREATE TABLE IF NOT EXISTS {}(
+                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                    operation INTEGER NOT NULL,
+                    before TEXT,
+                    after TEXT,
+                    undone INTEGER NOT NULL DEFAULT 0);",
+                    Self::table_name()
+                );
+
+                connection.execute(&sttmt)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct TimeModel;
+
+    /// Columns read from the time entries table, in the order used by `TimeModel::from_row`.
+    const TIME_COLUMNS: &str = "id, task_id, started_at, ended_at";
+
+    impl TimeModel {
+        fn from_row(row: &[Value]) -> TimeEntry {
+            TimeEntry::new(
+                row[0].as_integer().unwrap() as IdType,
+                row[1].as_integer().unwrap() as IdType,
+                row[2].as_string().unwrap(),
+                row[3].as_string(),
+            )
+        }
+
+        fn find(connection: &Connection, id: IdType) -> Result<TimeEntry, InternalError> {
+            let query_ = format!("SELECT {} FROM {} WHERE id = ?;", TIME_COLUMNS, Self::table_name());
+
+            query(connection, &query_, &[Value::Integer(id as i64)])?
+                .first()
+                .map(|row| Self::from_row(row))
+                .ok_or_else(|| InternalError::not_found("time entry", id))
+        }
+
+        fn add(connection: &Connection, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let statement = format!(
+                "INSERT INTO {}(task_id, started_at, ended_at) VALUES (?, ?, ?);",
+                Self::table_name()
+            );
+
+            execute(
+                connection,
+                &statement,
+                &[
+                    Value::Integer(*entry.task_id() as i64),
+                    Value::String(entry.started_at().clone()),
+                    text(entry.ended_at()),
+                ],
+            )?;
+
+            Self::find(connection, last_insert_id(connection)?)
+        }
+
+        fn update(connection: &Connection, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            Self::find(connection, *entry.id())?;
+
+            let statement = format!("UPDATE {} SET ended_at = ? WHERE id = ?;", Self::table_name());
+            execute(
+                connection,
+                &statement,
+                &[text(entry.ended_at()), Value::Integer(*entry.id() as i64)],
+            )?;
+
+            Self::find(connection, *entry.id())
+        }
+
+        /// Lists the entries matching a query, oldest first.
+        fn query(connection: &Connection, time_query: &TimeQuery) -> Result<Vec<TimeEntry>, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Ok(Vec::new());
+            }
+
+            let mut conditions = vec![String::from("1")];
+            let mut values = Vec::new();
+
+            if let Some(task_id) = time_query.task_id {
+                values.push(Value::Integer(task_id as i64));
+                conditions.push(format!("task_id = ?{}", values.len()));
+            }
+
+            if let Some(running) = time_query.running {
+                let null = if running { "NULL" } else { "NOT NULL" };
+                conditions.push(format!("ended_at IS {}", null));
+            }
+
+            if let Some(from) = &time_query.from {
+                values.push(Value::String(from.clone()));
+                conditions.push(format!("started_at >= ?{}", values.len()));
+            }
+
+            if let Some(to) = &time_query.to {
+                values.push(Value::String(to.clone()));
+                conditions.push(format!("SUBSTR(started_at, 1, LENGTH(?{0})) <= ?{0}", values.len()));
+            }
+
+            let query_ = format!(
+                "SELECT {} FROM {} WHERE {} ORDER BY started_at, id;",
+                TIME_COLUMNS,
+                Self::table_name(),
+                conditions.join(" AND ")
+            );
+
+            Ok(query(connection, &query_, &values)?
+                .iter()
+                .map(|row| Self::from_row(row))
+                .collect())
+        }
+    }
+
+    impl DatabaseConnectorTrait for TimeModel {
+        fn table_name() -> &'static str {
+            "time_entries"
+        }
+
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error> {
+            if !Self::is_table_initialized(connection) {
+                let sttmt = format!(
+                    "CREATE TABLE IF NOT EXISTS {}(
+                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                    task_id INTEGER NOT NULL,
+                    started_at DATETIME NOT NULL,
+                    ended_at DATETIME,
+                    FOREIGN KEY (task_id) REFERENCES tasks(id));",
+                    Self::table_name()
+                );
+
+                connection.execute(&sttmt)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct ViewModel;
+
+    impl ViewModel {
+        fn from_row(row: &[Value]) -> View {
+            View::new(row[0].as_string().unwrap(), row[1].as_string().unwrap())
+        }
+
+        fn save(connection: &Connection, view: View) -> Result<View, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Err(InternalError::table_not_initialized(Self::table_name()));
+            }
+
+            let statement = format!("INSERT OR REPLACE INTO {}(name, args) VALUES (?, ?);", Self::table_name());
+            execute(
+                connection,
+                &statement,
+                &[Value::String(view.name().clone()), Value::String(view.args().clone())],
+            )?;
+
+            Ok(view)
+        }
+
+        fn all(connection: &Connection) -> Result<Vec<View>, InternalError> {
+            if !Self::is_table_initialized(connection) {
+                return Ok(Vec::new());
+            }
+
+            let query_ = format!("SELECT name, args FROM {} ORDER BY name;", Self::table_name());
+
+            Ok(query(connection, &query_, &[])?
+                .iter()
+                .map(|row| Self::from_row(row))
+                .collect())
+        }
+
+        fn delete(connection: &Connection, name: &str) -> Result<(), InternalError> {
+            let statement = format!("DELETE FROM {} WHERE name = ?;", Self::table_name());
+            execute(connection, &statement, &[Value::String(String::from(name))])?;
+            Ok(())
+        }
+    }
+
+    impl DatabaseConnectorTrait for ViewModel {
+        fn table_name() -> &'static str {
+            "views"
+        }
+
+        fn init_table(connection: &Connection) -> Result<(), sqlite::Error> {
+            if !Self::is_table_initialized(connection) {
+                let sttmt = format!(
+                    "CREATE TABLE IF NOT EXISTS {}(
+                    name TEXT NOT NULL PRIMARY KEY,
+                    args TEXT NOT NULL);",
+                    Self::table_name()
+                );
+
+                connection.execute(&sttmt)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Storage over a single connection, used by `SqliteStorage`
+    /// for the operations run inside of a transaction.
+    struct ConnectionStorage<'a> {
+        connection: &'a Connection,
+    }
+
+    impl Storage for ConnectionStorage<'_> {
+        fn init(&self) -> Result<(), InternalError> {
+            TodoModel::init_table(self.connection)?;
+            TaskModel::init_table(self.connection)?;
+            HistoryModel::init_table(self.connection)?;
+            JournalModel::init_table(self.connection)?;
+            TimeModel::init_table(self.connection)?;
+            ViewModel::init_table(self.connection)?;
+            Ok(())
+        }
+
+        fn todos(&self) -> Result<Vec<Todo>, InternalError> {
+            Ok(TodoModel::all(self.connection))
+        }
+
+        fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            TodoModel::find(self.connection, id)
+        }
+
+        fn insert_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            TodoModel::add(self.connection, todo)
+        }
+
+        fn update_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            TodoModel::update(self.connection, todo)
+        }
+
+        fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            TodoModel::delete(self.connection, id)
+        }
+
+        fn tasks(&self) -> Result<Vec<Task>, InternalError> {
+            Ok(TaskModel::all(self.connection))
+        }
+
+        fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            TaskModel::find(self.connection, id)
+        }
+
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError> {
+            TaskModel::add(self.connection, task)
+        }
+
+        fn update_task(&self, task: Task) -> Result<Task, InternalError> {
+            TaskModel::update(self.connection, task)
+        }
+
+        fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            TaskModel::delete(self.connection, id)
+        }
+
+        fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>, InternalError> {
+            TaskModel::query(self.connection, query)
+        }
+
+        fn insert_history(&self, entry: HistoryEntry) -> Result<HistoryEntry, InternalError> {
+            HistoryModel::add(self.connection, entry)
+        }
+
+        fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, InternalError> {
+            HistoryModel::query(self.connection, query)
+        }
+
+        fn restore_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            TodoModel::restore(self.connection, todo)
+        }
+
+        fn restore_task(&self, task: Task) -> Result<Task, InternalError> {
+            TaskModel::restore(self.connection, task)
+        }
+
+        fn insert_journal(&self, entry: JournalEntry) -> Result<JournalEntry, InternalError> {
+            JournalModel::add(self.connection, entry)
+        }
+
+        fn journal(&self) -> Result<Vec<JournalEntry>, InternalError> {
+            JournalModel::all(self.connection)
+        }
+
+        fn set_undone(&self, operation: IdType, undone: bool) -> Result<(), InternalError> {
+            JournalModel::set_undone(self.connection, operation, undone)
+        }
+
+        fn clear_undone(&self) -> Result<(), InternalError> {
+            JournalModel::clear_undone(self.connection)
+        }
+
+        fn insert_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            TimeModel::add(self.connection, entry)
+        }
+
+        fn update_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            TimeModel::update(self.connection, entry)
+        }
+
+        fn time_entries(&self, query: &TimeQuery) -> Result<Vec<TimeEntry>, InternalError> {
+            TimeModel::query(self.connection, query)
+        }
+
+        fn save_view(&self, view: View) -> Result<View, InternalError> {
+            ViewModel::save(self.connection, view)
+        }
+
+        fn views(&self) -> Result<Vec<View>, InternalError> {
+            ViewModel::all(self.connection)
+        }
+
+        fn delete_view(&self, name: &str) -> Result<(), InternalError> {
+            ViewModel::delete(self.connection, name)
+        }
+
+        fn transaction(
+            &self,
+            operation: &mut dyn FnMut(&dyn Storage) -> Result<(), InternalError>,
+        ) -> Result<(), InternalError> {
+            // Already inside of a transaction, a savepoint allows
+            // undoing only the changes of the nested one.
+            self.connection.execute("SAVEPOINT nested;")?;
+
+            let res = operation(self);
+
+            if res.is_err() {
+                self.connection.execute("ROLLBACK TO nested;")?;
+            }
+
+            self.connection.execute("RELEASE nested;")?;
+            res
+        }
+    }
+
+    /// The default storage, keeping the todos and tasks on a sqlite database.
+    pub struct SqliteStorage {
+        database: Database,
+    }
+
+    impl SqliteStorage {
+        pub fn new(database: Database) -> Self {
+            Self { database }
+        }
+
+        /// References the database of the storage.
+        pub fn database(&self) -> &Database {
+            &self.database
+        }
+
+        fn read<T>(
+            &self,
+            operation: impl FnOnce(&ConnectionStorage) -> Result<T, InternalError>,
+        ) -> Result<T, InternalError> {
+            self.database
+                .read(|connection| operation(&ConnectionStorage { connection }))
+        }
+
+        fn write<T>(
+            &self,
+            operation: impl FnOnce(&ConnectionStorage) -> Result<T, InternalError>,
+        ) -> Result<T, InternalError> {
+            self.database
+                .write(|connection| operation(&ConnectionStorage { connection }))
+        }
+    }
+
+    impl Storage for SqliteStorage {
+        fn init(&self) -> Result<(), InternalError> {
+            self.read(|storage| storage.init())
+        }
+
+        fn todos(&self) -> Result<Vec<Todo>, InternalError> {
+            self.read(|storage| storage.todos())
+        }
+
+        fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            self.read(|storage| storage.find_todo(id))
+        }
+
+        fn insert_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.write(|storage| storage.insert_todo(todo))
+        }
+
+        fn update_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.write(|storage| storage.update_todo(todo))
+        }
+
+        fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            self.write(|storage| storage.delete_todo(id))
+        }
+
+        fn tasks(&self) -> Result<Vec<Task>, InternalError> {
+            self.read(|storage| storage.tasks())
+        }
+
+        fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            self.read(|storage| storage.find_task(id))
+        }
+
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.write(|storage| storage.insert_task(task))
+        }
+
+        fn update_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.write(|storage| storage.update_task(task))
+        }
+
+        fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            self.write(|storage| storage.delete_task(id))
+        }
+
+        fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>, InternalError> {
+            self.read(|storage| storage.query_tasks(query))
+        }
+
+        fn insert_history(&self, entry: HistoryEntry) -> Result<HistoryEntry, InternalError> {
+            self.write(|storage| storage.insert_history(entry))
+        }
+
+        fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, InternalError> {
+            self.read(|storage| storage.history(query))
+        }
+
+        fn restore_todo(&self, todo: Todo) -> Result<Todo, InternalError> {
+            self.write(|storage| storage.restore_todo(todo))
+        }
+
+        fn restore_task(&self, task: Task) -> Result<Task, InternalError> {
+            self.write(|storage| storage.restore_task(task))
+        }
+
+        fn insert_journal(&self, entry: JournalEntry) -> Result<JournalEntry, InternalError> {
+            self.write(|storage| storage.insert_journal(entry))
+        }
+
+        fn journal(&self) -> Result<Vec<JournalEntry>, InternalError> {
+            self.read(|storage| storage.journal())
+        }
+
+        fn set_undone(&self, operation: IdType, undone: bool) -> Result<(), InternalError> {
+            self.write(|storage| storage.set_undone(operation, undone))
+        }
+
+        fn clear_undone(&self) -> Result<(), InternalError> {
+            self.write(|storage| storage.clear_undone())
+        }
+
+        fn insert_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            self.write(|storage| storage.insert_time_entry(entry))
+        }
+
+        fn update_time_entry(&self, entry: TimeEntry) -> Result<TimeEntry, InternalError> {
+            self.write(|storage| storage.update_time_entry(entry))
+        }
+
+        fn time_entries(&self, query: &TimeQuery) -> Result<Vec<TimeEntry>, InternalError> {
+            self.read(|storage| storage.time_entries(query))
+        }
+
+        fn save_view(&self, view: View) -> Result<View, InternalError> {
+            self.write(|storage| storage.save_view(view))
+        }
+
+        fn views(&self) -> Result<Vec<View>, InternalError> {
+            self.read(|storage| storage.views())
+        }
+
+        fn delete_view(&self, name: &str) -> Result<(), InternalError> {
+            self.write(|storage| storage.delete_view(name))
+        }
+
+        fn transaction(
+            &self,
+            operation: &mut dyn FnMut(&dyn Storage) -> Result<(), InternalError>,
+        ) -> Result<(), InternalError> {
+            self.write(|storage| operation(storage))
+        }
+    }
+
+    /// Given as the operation of the first change of an operation.
+    pub(crate) const NEW_OPERATION: IdType = 0;
+
+    /// Finds a todo, unless it is in the trash.
+    fn live_todo(storage: &dyn Storage, id: IdType) -> Result<Todo, InternalError> {
+        match storage.find_todo(id)? {
+            todo if todo.deleted_at().is_some() => Err(InternalError::not_found("todo", id)),
+            todo => Ok(todo),
+        }
+    }
+
+    /// Stops the running timer of a task, if there is one.
+    fn end_timer(storage: &dyn Storage, task_id: IdType) -> Result<Option<TimeEntry>, InternalError> {
+        let query = TimeQuery {
+            task_id: Some(task_id),
+            running: Some(true),
+            ..TimeQuery::default()
+        };
+
+        match storage.time_entries(&query)?.pop() {
+            Some(mut entry) => {
+                entry.set_ended_at(&now());
+                storage.update_time_entry(entry).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The hook event of an update of a task, completing it or not.
+    fn task_event(old: &Task, new: &Task) -> HookEvent {
+        match (old.status(), new.status()) {
+            (Status::Done(_), _) => HookEvent::Modify,
+            (_, Status::Done(_)) => HookEvent::Complete,
+            _ => HookEvent::Modify,
+        }
+    }
+
+    /// Finds a task, unless it is in the trash, by itself or with its todo.
+    fn live_task(storage: &dyn Storage, id: IdType) -> Result<Task, InternalError> {
+        let task = storage.find_task(id)?;
+
+        if task.deleted_at().is_some() || trashed_todos(storage)?.contains(task.todo_id()) {
+            Err(InternalError::not_found("task", id))
+        } else {
+            Ok(task)
+        }
+    }
+
+    /// The ids of the todos in the trash.
+    fn trashed_todos(storage: &dyn Storage) -> Result<Vec<IdType>, InternalError> {
+        Ok(storage
+            .todos()?
+            .iter()
+            .filter(|todo| todo.deleted_at().is_some())
+            .map(|todo| *todo.id())
+            .collect())
+    }
+
+    /// Leaves out the tasks in the trash, by themselves or with their todo.
+    fn live(storage: &dyn Storage, tasks: Vec<Task>) -> Vec<Task> {
+        let trashed = trashed_todos(storage).unwrap_or_default();
+
+        tasks
+            .into_iter()
+            .filter(|task| task.deleted_at().is_none() && !trashed.contains(task.todo_id()))
+            .collect()
+    }
+
+    /// Fails when an object is no longer the one a change was made from,
+    /// e.g. when it was changed while the hook of the change was running.
+    fn unchanged<T: PartialEq>(current: T, old: &T, entity: &str, id: IdType) -> Result<(), InternalError> {
+        if current != *old {
+            return Err(InternalError::invalid_input(&format!(
+                "{} {} was changed meanwhile, try again",
+                entity, id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Owns a storage and exposes the operations on its
+    /// todos and tasks. The static methods of `Todo` and
+    /// `Task` use the default store of the process.
+    pub struct Store {
+        backend: Box<dyn Storage + Send + Sync>,
+        actor: String,
+        hooks: Hooks,
+    }
+
+    lazy_static! {
+        static ref DEFAULT_STORE: RwLock<Arc<Store>> =
+            RwLock::new(Arc::new(Store::new(Database::new(":memory:"))));
+    }
+
+    impl Store {
+        /// Wraps a sqlite database, without creating its tables.
+        pub fn new(database: Database) -> Self {
+            Self::with_backend(Box::new(SqliteStorage::new(database)))
+        }
+
+        /// Wraps any storage, without initializing it.
+        pub fn with_backend(backend: Box<dyn Storage + Send + Sync>) -> Self {
+            Self {
+                backend,
+                actor: default_actor(),
+                hooks: Hooks::default(),
+            }
+        }
+
+        /// Sets who is recorded in the history as making the changes.
+        pub fn with_actor(mut self, actor: &str) -> Self {
+            self.actor = String::from(actor);
+            self
+        }
+
+        /// Who is recorded in the history as making the changes.
+        pub fn actor(&self) -> &String {
+            &self.actor
+        }
+
+        /// Sets the scripts run when the todos and tasks are added,
+        /// updated or deleted, see `Hooks::run`.
+        pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+            self.hooks = hooks;
+            self
+        }
+
+        /// The scripts run when the todos and tasks change.
+        pub fn hooks(&self) -> &Hooks {
+            &self.hooks
+        }
+
+        /// Runs the hook of the event on a change of a todo before it is
+        /// written, out of any transaction, so that the hook can run `todo`
+        /// itself. Returns the todo to write, the one changed by the hook
+        /// if it changed it.
+        fn todo_hook(&self, event: HookEvent, old: Option<&Todo>, todo: Todo) -> Result<Todo, InternalError> {
+            Ok(self.hooks.run(event, "todo", old, &todo)?.unwrap_or(todo))
+        }
+
+        /// Like `todo_hook`, for a task. The task changed by the hook is
+        /// checked as the change asked for is: its status must be one the
+        /// task had before the change can become, see `Status::can_become`,
+        /// a new task starting as to do, and its estimate and due day must
+        /// be valid. The hook can't change the ids or dates of the task.
+        fn task_hook(&self, event: HookEvent, old: Option<&Task>, task: Task) -> Result<Task, InternalError> {
+            let name = event.name();
+            let changed = match self.hooks.run(event, "task", old, &task)? {
+                Some(changed) if changed != task => changed,
+                _ => return Ok(task),
+            };
+            let refuse = |details: String| {
+                Err(InternalError::invalid_input(&format!("the {} hook {}", name, details)))
+            };
+
+            let before = old.map(|old| old.status().clone()).unwrap_or(Status::Todo);
+
+            if changed.status() != &before && !before.can_become(changed.status()) {
+                return refuse(format!(
+                    "can't set task {} from {} to {}",
+                    task.id(),
+                    before.name(),
+                    changed.status().name()
+                ));
+            }
+
+            if changed.id() != task.id()
+                || changed.todo_id() != task.todo_id()
+                || changed.created_at() != task.created_at()
+                || changed.deleted_at() != task.deleted_at()
+            {
+                return refuse(format!("can't change the ids or dates of task {}", task.id()));
+            }
+
+            if changed.estimate().is_some_and(|estimate| !estimate.is_finite() || estimate < 0.0) {
+                return refuse(String::from("set an estimate that isn't a positive number"));
+            }
+
+            if let Some(due) = changed.due().filter(|due| chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d").is_err()) {
+                return refuse(format!("set '{}' as the due day, which isn't a day", due));
+            }
+
+            Ok(changed)
+        }
+
+        /// Writes a change of a todo read as `old`: runs its hook first, then
+        /// writes the todo the hook leaves in a transaction, unless the todo
+        /// was changed meanwhile.
+        fn change_todo(&self, event: HookEvent, old: Todo, todo: Todo) -> Result<Todo, InternalError> {
+            let todo = self.todo_hook(event, Some(&old), todo)?;
+
+            self.transaction(|storage| {
+                unchanged(storage.find_todo(*old.id())?, &old, "todo", *old.id())?;
+                let todo = storage.update_todo(todo)?;
+                self.record_todo(storage, NEW_OPERATION, Some(&old), Some(&todo))?;
+                Ok(todo)
+            })
+        }
+
+        /// Writes a change of a task read as `old`, as `change_todo`.
+        fn change_task(&self, event: HookEvent, old: Task, task: Task) -> Result<Task, InternalError> {
+            let task = self.task_hook(event, Some(&old), task)?;
+            self.transaction(|storage| Ok(self.write_task(storage, NEW_OPERATION, &old, task)?.1))
+        }
+
+        /// Writes a task changed from `old` whose hook already ran, inside
+        /// of a transaction. Fails if the task was changed meanwhile or moved
+        /// to an unknown todo. Returns the operation of the change, see
+        /// `record_task`, and the task written.
+        fn write_task(
+            &self,
+            storage: &dyn Storage,
+            operation: IdType,
+            old: &Task,
+            task: Task,
+        ) -> Result<(IdType, Task), InternalError> {
+            unchanged(storage.find_task(*old.id())?, old, "task", *old.id())?;
+
+            if task.todo_id() != old.todo_id() {
+                live_todo(storage, *task.todo_id())?;
+            }
+
+            // The time spent on a task ends with it.
+            if task.status().is_closed() {
+                end_timer(storage, *task.id())?;
+            }
+
+            let task = storage.update_task(task)?;
+            let operation = self.record_task(storage, operation, Some(old), Some(&task))?;
+            Ok((operation, task))
+        }
+
+        /// Adds a task once its hook ran, see `todo_hook`.
+        fn insert_task(&self, task: Task) -> Result<Task, InternalError> {
+            live_todo(self.backend(), *task.todo_id())?;
+            let task = self.task_hook(HookEvent::Add, None, task)?;
+
+            self.transaction(|storage| {
+                // Every backend must refuse tasks of unknown todos.
+                live_todo(storage, *task.todo_id())?;
+                let task = storage.insert_task(task)?;
+                self.record_task(storage, NEW_OPERATION, None, Some(&task))?;
+                Ok(task)
+            })
+        }
+
+        /// Opens the sqlite database on the given path and creates its tables.
+        pub fn open(path: &str) -> Result<Self, InternalError> {
+            let database = Database::open(path, Database::default_pool_size(path))?;
+            let store = Self::new(database);
+            store.init_tables()?;
+            Ok(store)
+        }
+
+        /// Creates a store on a new in-memory database, private to it.
+        pub fn in_memory() -> Result<Self, InternalError> {
+            Self::open(":memory:")
+        }
+
+        /// The store used by the static methods of `Todo` and `Task`.
+        pub fn default_store() -> Arc<Store> {
+            Arc::clone(&DEFAULT_STORE.read().unwrap())
+        }
+
+        /// Replaces the store used by the static methods of `Todo` and `Task`.
+        pub fn set_default_store(store: Store) {
+            *DEFAULT_STORE.write().unwrap() = Arc::new(store);
+        }
+
+        /// References the storage of the store.
+        pub fn backend(&self) -> &(dyn Storage + Send + Sync) {
+            self.backend.as_ref()
+        }
+
+        /// Creates the tables of the todos and tasks if they don't exist yet.
+        pub fn init_tables(&self) -> Result<(), InternalError> {
+            self.backend.init()
+        }
+
+        /// Runs the operation inside of a transaction of the storage.
+        pub fn transaction<T>(
+            &self,
+            operation: impl FnOnce(&dyn Storage) -> Result<T, InternalError>,
+        ) -> Result<T, InternalError> {
+            let mut operation = Some(operation);
+            let mut value = None;
+
+            self.backend.transaction(&mut |storage| {
+                let operation = operation
+                    .take()
+                    .ok_or_else(|| InternalError::new("the transaction was run twice"))?;
+                value = Some(operation(storage)?);
+                Ok(())
+            })?;
+
+            value.ok_or_else(|| InternalError::new("the transaction was not run"))
+        }
+
+        /// Lists the todos, leaving out the ones archived or in the trash.
+        pub fn todos(&self) -> Vec<Todo> {
+            self.backend
+                .todos()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|todo| todo.deleted_at().is_none() && todo.archived_at().is_none())
+                .collect()
+        }
+
+        /// Finds a todo, unless it is in the trash.
+        pub fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            live_todo(self.backend(), id)
+        }
+
+        pub fn add_todo(
+            &self,
+            name: String,
+            description: Option<String>,
+        ) -> Result<Todo, InternalError> {
+            let id: IdType = 0;
+            let created_at = "CURRENT_TIMESTAMP";
+            let updated_at = "CURRENT_TIMESTAMP";
+
+            let todo = Todo::new(id, name, description, created_at.into(), updated_at.into());
+            let todo = self.todo_hook(HookEvent::Add, None, todo)?;
+
+            self.transaction(|storage| {
+                let todo = storage.insert_todo(todo)?;
+                self.record_todo(storage, NEW_OPERATION, None, Some(&todo))?;
+                Ok(todo)
+            })
+        }
+
+        pub fn update_todo(
+            &self,
+            id: IdType,
+            new_name: Option<String>,
+            new_description: Option<String>,
+        ) -> Result<Todo, InternalError> {
+            self.patch_todo(id, new_name, new_description.map(Some))
+        }
+
+        /// Changes the name and the description of a todo as a single
+        /// change. A description of `Some(None)` removes it.
+        pub fn patch_todo(
+            &self,
+            id: IdType,
+            new_name: Option<String>,
+            new_description: Option<Option<String>>,
+        ) -> Result<Todo, InternalError> {
+            let old = self.find_todo(id)?;
+            let mut todo = old.clone();
+
+            if let Some(name) = new_name {
+                todo.set_name(&name);
+            }
+
+            match new_description {
+                Some(Some(description)) => todo.set_description(&description),
+                Some(None) => todo.clear_description(),
+                None => (),
+            }
+
+            self.change_todo(HookEvent::Modify, old, todo)
+        }
+
+        /// Removes the description of a todo.
+        pub fn clear_todo_description(&self, id: IdType) -> Result<Todo, InternalError> {
+            self.patch_todo(id, None, Some(None))
+        }
+
+        /// Moves a todo to the trash, its tasks are hidden along with it.
+        pub fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            let old = self.find_todo(id)?;
+            let mut todo = old.clone();
+            todo.set_deleted_at(Some(&now()));
+            self.change_todo(HookEvent::Delete, old, todo).map(|_| ())
+        }
+
+        /// Lists the archived todos, which `todos` leaves out.
+        pub fn archived_todos(&self) -> Vec<Todo> {
+            self.backend
+                .todos()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|todo| todo.deleted_at().is_none() && todo.archived_at().is_some())
+                .collect()
+        }
+
+        /// Archives a todo, meant for the completed ones: it
+        /// is kept, but left out of the listings of todos.
+        pub fn archive_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            let old = self.find_todo(id)?;
+
+            if old.archived_at().is_some() {
+                return Ok(old);
+            }
+
+            let mut todo = old.clone();
+            todo.set_archived_at(Some(&now()));
+            self.change_todo(HookEvent::Modify, old, todo)
+        }
+
+        /// Brings an archived todo back to the listings.
+        pub fn unarchive_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            let old = self.find_todo(id)?;
+            let mut todo = old.clone();
+            todo.set_archived_at(None);
+            self.change_todo(HookEvent::Modify, old, todo)
+        }
+
+        /// Lists the todos in the trash.
+        pub fn trashed_todos(&self) -> Vec<Todo> {
+            self.backend
+                .todos()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|todo| todo.deleted_at().is_some())
+                .collect()
+        }
+
+        /// Lists the tasks in the trash, with the ones of the todos there.
+        pub fn trashed_tasks(&self) -> Vec<Task> {
+            let trashed = trashed_todos(self.backend()).unwrap_or_default();
+
+            self.backend
+                .tasks()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|task| task.deleted_at().is_some() || trashed.contains(task.todo_id()))
+                .collect()
+        }
+
+        /// Brings a todo back from the trash, with its tasks.
+        pub fn restore_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            let old = self.backend.find_todo(id)?;
+
+            if old.deleted_at().is_none() {
+                let details = format!("todo {} is not in the trash", id);
+                return Err(InternalError::invalid_input(&details));
+            }
+
+            let mut todo = old.clone();
+            todo.set_deleted_at(None);
+            self.change_todo(HookEvent::Modify, old, todo)
+        }
+
+        /// Brings a task back from the trash, its todo must not be there.
+        pub fn restore_task(&self, id: IdType) -> Result<Task, InternalError> {
+            let old = self.backend.find_task(id)?;
+
+            if live_todo(self.backend(), *old.todo_id()).is_err() {
+                let details = format!("the todo {} of the task must be restored first", old.todo_id());
+                return Err(InternalError::invalid_input(&details));
+            }
+
+            if old.deleted_at().is_none() {
+                let details = format!("task {} is not in the trash", id);
+                return Err(InternalError::invalid_input(&details));
+            }
+
+            let mut task = old.clone();
+            task.set_deleted_at(None);
+            self.change_task(HookEvent::Modify, old, task)
+        }
+
+        /// Deletes for good what is in the trash for longer than
+        /// `older_than`, returning how many todos and tasks were deleted.
+        /// It can still be undone, as any other change. No hook is run,
+        /// the delete hooks ran when the todos and tasks were trashed.
+        pub fn purge(&self, older_than: Duration) -> Result<(usize, usize), InternalError> {
+            let cutoff = chrono::Duration::from_std(older_than)
+                .ok()
+                .and_then(|age| chrono::Utc::now().checked_sub_signed(age))
+                .map(|date| date.format(DATE_FORMAT).to_string());
+
+            let cutoff = match cutoff {
+                Some(cutoff) => cutoff,
+                None => return Ok((0, 0)),
+            };
+
+            let expired = |deleted_at: Option<&String>| deleted_at.is_some_and(|date| *date <= cutoff);
+
+            self.transaction(|storage| {
+                let todos: Vec<Todo> = storage
+                    .todos()?
+                    .into_iter()
+                    .filter(|todo| expired(todo.deleted_at()))
+                    .collect();
+
+                // The tasks of the todos deleted can't be kept, whatever their date.
+                let tasks: Vec<Task> = storage
+                    .tasks()?
+                    .into_iter()
+                    .filter(|task| {
+                        expired(task.deleted_at()) || todos.iter().any(|todo| todo.id() == task.todo_id())
+                    })
+                    .collect();
+
+                let mut operation = NEW_OPERATION;
+
+                for task in &tasks {
+                    storage.delete_task(*task.id())?;
+                    operation = self.record_task(storage, operation, Some(task), None)?;
+                }
+
+                for todo in &todos {
+                    storage.delete_todo(*todo.id())?;
+                    operation = self.record_todo(storage, operation, Some(todo), None)?;
+                }
+
+                Ok((todos.len(), tasks.len()))
+            })
+        }
+
+        /// How many of the tasks of a todo are done.
+        pub fn todo_progress(&self, todo_id: IdType) -> Progress {
+            Progress::of(&self.todo_tasks(todo_id))
+        }
+
+        /// Sets every task of a todo as done, in a single operation, leaving
+        /// the cancelled ones as they are. Returns the tasks that were changed.
+        pub fn complete_todo(&self, id: IdType) -> Result<Vec<Task>, InternalError> {
+            self.find_todo(id)?;
+
+            let mut changes = Vec::new();
+
+            for old in self.todo_tasks(id) {
+                if old.status().is_closed() {
+                    continue;
+                }
+
+                let mut task = old.clone();
+                task.transition(Status::Done(String::from("CURRENT_TIMESTAMP")))?;
+                let task = self.task_hook(HookEvent::Complete, Some(&old), task)?;
+                changes.push((old, task));
+            }
+
+            self.write_tasks(changes)
+        }
+
+        /// Writes the changes of several tasks whose hooks already ran, in
+        /// a single operation. Returns the tasks written.
+        fn write_tasks(&self, changes: Vec<(Task, Task)>) -> Result<Vec<Task>, InternalError> {
+            self.transaction(|storage| {
+                let mut operation = NEW_OPERATION;
+                let mut written = Vec::new();
+
+                for (old, task) in changes {
+                    let (next, task) = self.write_task(storage, operation, &old, task)?;
+                    operation = next;
+                    written.push(task);
+                }
+
+                Ok(written)
+            })
+        }
+
+        /// Lists the tasks of a todo, leaving out the ones in the trash.
+        pub fn todo_tasks(&self, todo_id: IdType) -> Vec<Task> {
+            live(self.backend(), self.backend.todo_tasks(todo_id).unwrap_or_default())
+        }
+
+        /// Lists the tasks, leaving out the ones in the trash.
+        pub fn tasks(&self) -> Vec<Task> {
+            live(self.backend(), self.backend.tasks().unwrap_or_default())
+        }
+
+        /// Lists the tasks matching the query, leaving out the ones in the trash.
+        pub fn query_tasks(&self, query: &TaskQuery) -> Vec<Task> {
+            live(self.backend(), self.backend.query_tasks(query).unwrap_or_default())
+        }
+
+        /// Finds a task, unless it is in the trash.
+        pub fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            live_task(self.backend(), id)
+        }
+
+        pub fn add_task(&self, what: String, todo_id: IdType) -> Result<Task, InternalError> {
+            self.add_task_with(what, todo_id, None, None)
+        }
+
+        /// Adds a task with its status and estimate, as a single change:
+        /// nothing is written if either of them is invalid.
+        pub fn add_task_with(
+            &self,
+            what: String,
+            todo_id: IdType,
+            status: Option<Status>,
+            estimate: Option<f64>,
+        ) -> Result<Task, InternalError> {
+            if estimate.is_some_and(|estimate| !estimate.is_finite() || estimate < 0.0) {
+                return Err(InternalError::invalid_input("the estimate must be a positive number"));
+            }
+
+            let id: IdType = 0; // Only a placeholder
+            let created_at = "CURRENT_TIMESTAMP";
+            let updated_at = "CURRENT_TIMESTAMP";
+
+            let mut task = Task::new(id, todo_id, &what, created_at, updated_at, Status::Todo);
+
+            // A new task is to do, unless it is given another status.
+            if let Some(status) = status.filter(|status| *status != Status::Todo) {
+                task.transition(status)?;
+            }
+
+            task.set_estimate(estimate);
+            self.insert_task(task)
+        }
+
+        pub fn update_task(
+            &self,
+            id: IdType,
+            what_new: Option<String>,
+            new_status: Option<Status>,
+            new_estimate: Option<f64>,
+        ) -> Result<Task, InternalError> {
+            if new_estimate.is_some_and(|estimate| !estimate.is_finite() || estimate < 0.0) {
+                return Err(InternalError::invalid_input("the estimate must be a positive number"));
+            }
+
+            let old = self.find_task(id)?;
+            let mut task = old.clone();
+
+            if let Some(what) = what_new {
+                task.set_what(&what);
+            }
+
+            if let Some(status) = new_status {
+                task.transition(status)?;
+            }
+
+            if new_estimate.is_some() {
+                task.set_estimate(new_estimate);
+            }
+
+            self.change_task(task_event(&old, &task), old, task)
+        }
+
+        /// Sets the day a task is due, as YYYY-MM-DD, or clears it.
+        pub fn set_task_due(&self, id: IdType, due: Option<&str>) -> Result<Task, InternalError> {
+            if let Some(due) = due {
+                if chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d").is_err() {
+                    return Err(InternalError::invalid_input(&format!(
+                        "'{}' is not a day, use YYYY-MM-DD",
+                        due
+                    )));
+                }
+            }
+
+            self.edit_task(id, |task| task.set_due(due))
+        }
+
+        /// Sets the effort a task is expected to take, or clears it.
+        pub fn set_task_estimate(&self, id: IdType, estimate: Option<f64>) -> Result<Task, InternalError> {
+            if estimate.is_some_and(|estimate| !estimate.is_finite() || estimate < 0.0) {
+                return Err(InternalError::invalid_input("the estimate must be a positive number"));
+            }
+
+            self.edit_task(id, |task| task.set_estimate(estimate))
+        }
+
+        /// Sets the priority of a task, or clears it.
+        pub fn set_task_priority(&self, id: IdType, priority: Option<Priority>) -> Result<Task, InternalError> {
+            self.edit_task(id, |task| task.set_priority(priority))
+        }
+
+        fn edit_task(&self, id: IdType, change: impl Fn(&mut Task)) -> Result<Task, InternalError> {
+            let old = self.find_task(id)?;
+            let mut task = old.clone();
+            change(&mut task);
+            self.change_task(HookEvent::Modify, old, task)
+        }
+
+        /// Moves a task to another todo, keeping everything else, e.g. the
+        /// time spent on it.
+        pub fn move_task(&self, id: IdType, todo_id: IdType) -> Result<Task, InternalError> {
+            self.find_todo(todo_id)?;
+            self.edit_task(id, |task| task.set_todo_id(todo_id))
+        }
+
+        /// Moves every task matching the query to another todo, in a single
+        /// operation. Returns the tasks that were moved.
+        pub fn move_tasks(&self, query: &TaskQuery, todo_id: IdType) -> Result<Vec<Task>, InternalError> {
+            self.find_todo(todo_id)?;
+
+            let mut changes = Vec::new();
+
+            for old in self.query_tasks(query) {
+                if *old.todo_id() == todo_id {
+                    continue;
+                }
+
+                let mut task = old.clone();
+                task.set_todo_id(todo_id);
+                let task = self.task_hook(HookEvent::Modify, Some(&old), task)?;
+                changes.push((old, task));
+            }
+
+            self.write_tasks(changes)
+        }
+
+        /// Adds a copy of a task to a todo, which may be its own. The copy
+        /// is a new task to do, with the same text, estimate, due day and
+        /// priority.
+        pub fn copy_task(&self, id: IdType, todo_id: IdType) -> Result<Task, InternalError> {
+            let old = self.find_task(id)?;
+            let mut task = Task::new(0, todo_id, old.what(), "CURRENT_TIMESTAMP", "CURRENT_TIMESTAMP", Status::Todo);
+            task.set_estimate(old.estimate());
+            task.set_due(old.due().map(|due| due.as_str()));
+            task.set_priority(old.priority());
+            self.insert_task(task)
+        }
+
+        /// The tasks still to do, not started yet, due up to the given day,
+        /// and from the other one when given, grouped by day. Each day lists
+        /// the tasks with the highest priority first, the ones without a
+        /// priority last.
+        pub fn agenda(&self, from: Option<&str>, to: &str) -> Vec<AgendaGroup> {
+            let query = TaskQuery {
+                status: Some(String::from(Status::Todo.name())),
+                due_from: from.map(String::from),
+                due_to: Some(String::from(to)),
+                ..TaskQuery::default()
+            };
+
+            let mut tasks = self.query_tasks(&query);
+            tasks.sort_by(|a, b| {
+                a.due()
+                    .cmp(&b.due())
+                    .then_with(|| b.priority().cmp(&a.priority()))
+                    .then_with(|| a.id().cmp(b.id()))
+            });
+
+            let mut groups: Vec<AgendaGroup> = Vec::new();
+
+            for task in tasks {
+                let due = task.due().cloned().unwrap_or_default();
+
+                match groups.last_mut() {
+                    Some(group) if group.due == due => group.tasks.push(task),
+                    _ => groups.push(AgendaGroup { due, tasks: vec![task] }),
+                }
+            }
+
+            groups
+        }
+
+        /// Moves a task to the trash.
+        pub fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            let old = self.find_task(id)?;
+            let mut task = old.clone();
+            task.set_deleted_at(Some(&now()));
+            self.change_task(HookEvent::Delete, old, task).map(|_| ())
+        }
+
+        /// Lists the changes matching the query, oldest first.
+        pub fn history(&self, query: &HistoryQuery) -> Vec<HistoryEntry> {
+            self.backend.history(query).unwrap_or_default()
+        }
+
+        /// Starts timing a task, setting it as in progress if it isn't yet.
+        pub fn start_timer(&self, task_id: IdType) -> Result<TimeEntry, InternalError> {
+            let running = TimeQuery {
+                task_id: Some(task_id),
+                running: Some(true),
+                ..TimeQuery::default()
+            };
+            let not_running = |storage: &dyn Storage| {
+                if storage.time_entries(&running)?.is_empty() {
+                    return Ok(());
+                }
+
+                Err(InternalError::invalid_input(&format!("task {} is already being timed", task_id)))
+            };
+
+            let old = self.find_task(task_id)?;
+            not_running(self.backend())?;
+
+            let started = if matches!(old.status(), Status::InProgress(_)) {
+                None
+            } else {
+                let mut task = old.clone();
+                task.transition(Status::InProgress(String::from("CURRENT_TIMESTAMP")))?;
+                Some(self.task_hook(HookEvent::Modify, Some(&old), task)?)
+            };
+
+            self.transaction(|storage| {
+                not_running(storage)?;
+
+                match started {
+                    Some(task) => {
+                        self.write_task(storage, NEW_OPERATION, &old, task)?;
+                    }
+                    None => unchanged(storage.find_task(task_id)?, &old, "task", task_id)?,
+                }
+
+                storage.insert_time_entry(TimeEntry::new(0, task_id, &now(), None))
+            })
+        }
+
+        /// Stops timing a task, returning the time entry ended.
+        pub fn stop_timer(&self, task_id: IdType) -> Result<TimeEntry, InternalError> {
+            self.transaction(|storage| {
+                live_task(storage, task_id)?;
+                end_timer(storage, task_id)?.ok_or_else(|| {
+                    InternalError::invalid_input(&format!("task {} is not being timed", task_id))
+                })
+            })
+        }
+
+        /// Records time spent on a task, as ending now.
+        pub fn log_time(&self, task_id: IdType, duration: Duration) -> Result<TimeEntry, InternalError> {
+            let end = chrono::Utc::now();
+            let start = chrono::Duration::from_std(duration)
+                .ok()
+                .and_then(|duration| end.checked_sub_signed(duration))
+                .ok_or_else(|| InternalError::invalid_input("the time logged is too long"))?;
+
+            let entry = TimeEntry::new(
+                0,
+                task_id,
+                &start.format(DATE_FORMAT).to_string(),
+                Some(&end.format(DATE_FORMAT).to_string()),
+            );
+
+            self.transaction(|storage| {
+                live_task(storage, task_id)?;
+                storage.insert_time_entry(entry.clone())
+            })
+        }
+
+        /// Lists the time entries matching the query, oldest first.
+        pub fn time_entries(&self, query: &TimeQuery) -> Vec<TimeEntry> {
+            self.backend.time_entries(query).unwrap_or_default()
+        }
+
+        /// Saves the filters under a name, replacing the view
+        /// that had it, see `View::check_name`.
+        pub fn save_view(&self, name: &str, args: &str) -> Result<View, InternalError> {
+            View::check_name(name)?;
+            self.backend.save_view(View::new(name, args))
+        }
+
+        /// Finds the view with the given name.
+        pub fn find_view(&self, name: &str) -> Result<View, InternalError> {
+            self.views().into_iter().find(|view| view.name() == name).ok_or_else(|| {
+                InternalError::with_kind(ErrorKind::NotFound, &format!("there is no view named '{}'", name))
+            })
+        }
+
+        /// Lists the saved views, by name.
+        pub fn views(&self) -> Vec<View> {
+            self.backend.views().unwrap_or_default()
+        }
+
+        /// Deletes the view with the given name.
+        pub fn delete_view(&self, name: &str) -> Result<(), InternalError> {
+            self.transaction(|storage| {
+                if !storage.views()?.iter().any(|view| view.name() == name) {
+                    return Err(InternalError::with_kind(
+                        ErrorKind::NotFound,
+                        &format!("there is no view named '{}'", name),
+                    ));
+                }
+
+                storage.delete_view(name)
+            })
+        }
+
+        /// The tasks completed each week since the given date, with their
+        /// estimated effort, oldest week first. Tasks in the trash are left out.
+        pub fn velocity(&self, since: &str) -> Vec<VelocityRow> {
+            let mut rows: Vec<VelocityRow> = Vec::new();
+
+            for task in self.tasks() {
+                let week = match task.status() {
+                    Status::Done(date) if date.as_str() >= since => {
+                        match chrono::NaiveDateTime::parse_from_str(date, DATE_FORMAT) {
+                            Ok(date) => date.format("%G-W%V").to_string(),
+                            Err(_) => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+
+                match rows.iter_mut().find(|row| row.week == week) {
+                    Some(row) => {
+                        row.tasks += 1;
+                        row.effort += task.estimate().unwrap_or_default();
+                    }
+                    None => rows.push(VelocityRow {
+                        week,
+                        tasks: 1,
+                        effort: task.estimate().unwrap_or_default(),
+                    }),
+                }
+            }
+
+            rows.sort_by(|a, b| a.week.cmp(&b.week));
+            rows
+        }
+
+        /// Computes the statistics of the todos and tasks
+        /// not in the trash, see `Stats::compute`.
+        pub fn stats(&self, period: Period, oldest: usize) -> Stats {
+            Stats::compute(&self.todos(), &self.tasks(), period, oldest)
+        }
+
+        /// The tasks of a todo left at the end of each period, up to today.
+        pub fn burndown(&self, todo_id: IdType, period: Period) -> Vec<BurndownPoint> {
+            let today = chrono::Utc::now().date_naive();
+            burndown(&self.todo_tasks(todo_id), period, today)
+        }
+
+        /// The time spent on a task, counting a running timer up to now.
+        pub fn task_time(&self, task_id: IdType) -> Duration {
+            let query = TimeQuery {
+                task_id: Some(task_id),
+                ..TimeQuery::default()
+            };
+
+            self.time_entries(&query).iter().map(|entry| entry.duration()).sum()
+        }
+
+        /// The time spent on the tasks of a todo, leaving out the ones in the trash.
+        pub fn todo_time(&self, todo_id: IdType) -> Duration {
+            self.todo_tasks(todo_id)
+                .iter()
+                .map(|task| self.task_time(*task.id()))
+                .sum()
+        }
+
+        /// The time spent on each task per day, from and to the given
+        /// days (e.g. `2022-06-20`), ordered by day and task.
+        pub fn timesheet(&self, from: &str, to: &str) -> Vec<TimesheetRow> {
+            let query = TimeQuery {
+                from: Some(String::from(from)),
+                to: Some(String::from(to)),
+                ..TimeQuery::default()
+            };
+
+            let mut rows: Vec<TimesheetRow> = Vec::new();
+
+            for entry in self.time_entries(&query) {
+                let day = entry.started_at().chars().take(10).collect::<String>();
+                let row = rows
+                    .iter_mut()
+                    .find(|row| row.day == day && row.task.id() == entry.task_id());
+
+                match row {
+                    Some(row) => row.duration += entry.duration(),
+                    // The time spent on tasks in the trash still counts.
+                    None => match self.backend.find_task(*entry.task_id()) {
+                        Ok(task) => rows.push(TimesheetRow {
+                            day,
+                            task,
+                            duration: entry.duration(),
+                        }),
+                        Err(_) => continue,
+                    },
+                }
+            }
+
+            rows.sort_by(|a, b| a.day.cmp(&b.day).then(a.task.id().cmp(b.task.id())));
+            rows
+        }
+
+        /// Undoes the last `count` operations, newest first. Returns
+        /// the changes undone, nothing when there is nothing to undo.
+        pub fn undo(&self, count: usize) -> Result<Vec<JournalEntry>, InternalError> {
+            self.transaction(|storage| {
+                let journal = storage.journal()?;
+                let mut operations: Vec<IdType> = journal
+                    .iter()
+                    .rev()
+                    .filter(|entry| !entry.undone())
+                    .map(|entry| *entry.operation())
+                    .collect();
+                operations.dedup();
+                operations.truncate(count);
+
+                let mut undone = Vec::new();
+
+                for operation in operations {
+                    let entries = journal.iter().rev().filter(|e| *e.operation() == operation);
+
+                    for entry in entries {
+                        self.apply(storage, entry.after(), entry.before())?;
+                        undone.push(entry.clone());
+                    }
+
+                    storage.set_undone(operation, true)?;
+                }
+
+                Ok(undone)
+            })
+        }
+
+        /// Redoes the last `count` operations undone, oldest first.
+        pub fn redo(&self, count: usize) -> Result<Vec<JournalEntry>, InternalError> {
+            self.transaction(|storage| {
+                let journal = storage.journal()?;
+                // The undone operations redone first are the last ones undone.
+                let mut operations: Vec<IdType> = journal
+                    .iter()
+                    .filter(|entry| entry.undone())
+                    .map(|entry| *entry.operation())
+                    .collect();
+                operations.dedup();
+                operations.truncate(count);
+
+                let mut redone = Vec::new();
+
+                for operation in operations {
+                    let entries = journal.iter().filter(|e| *e.operation() == operation);
+
+                    for entry in entries {
+                        self.apply(storage, entry.before(), entry.after())?;
+                        redone.push(entry.clone());
+                    }
+
+                    storage.set_undone(operation, false)?;
+                }
+
+                Ok(redone)
+            })
+        }
+
+        /// Changes an object from its state `from` to `to`, as recorded
+        /// in the journal, keeping the history but not the journal. The
+        /// timer of a task is ended once it isn't in progress anymore,
+        /// e.g. when `task start` is undone.
+        fn apply(
+            &self,
+            storage: &dyn Storage,
+            from: Option<&Snapshot>,
+            to: Option<&Snapshot>,
+        ) -> Result<(), InternalError> {
+            match (from, to) {
+                (_, Some(Snapshot::Todo(todo))) => {
+                    let current = storage.find_todo(*todo.id()).ok();
+                    let todo = storage.restore_todo(todo.clone())?;
+                    let entries = HistoryEntry::todo_changes(current.as_ref(), Some(&todo), &self.actor);
+                    self.record_history(storage, entries)
+                }
+                (_, Some(Snapshot::Task(task))) => {
+                    let current = storage.find_task(*task.id()).ok();
+                    let task = storage.restore_task(task.clone())?;
+
+                    if !matches!(task.status(), Status::InProgress(_)) {
+                        end_timer(storage, *task.id())?;
+                    }
+
+                    let entries = HistoryEntry::task_changes(current.as_ref(), Some(&task), &self.actor);
+                    self.record_history(storage, entries)
+                }
+                (Some(Snapshot::Todo(todo)), None) => {
+                    let current = storage.find_todo(*todo.id())?;
+                    storage.delete_todo(*todo.id())?;
+                    let entries = HistoryEntry::todo_changes(Some(&current), None, &self.actor);
+                    self.record_history(storage, entries)
+                }
+                (Some(Snapshot::Task(task)), None) => {
+                    let current = storage.find_task(*task.id())?;
+                    end_timer(storage, *task.id())?;
+                    storage.delete_task(*task.id())?;
+                    let entries = HistoryEntry::task_changes(Some(&current), None, &self.actor);
+                    self.record_history(storage, entries)
+                }
+                (None, None) => Ok(()),
+            }
+        }
+
+        /// Records a change of a todo in the history and the journal,
+        /// inside of the transaction of the change. Returns the operation
+        /// of the change, to be given to the next changes of the operation.
+        pub(crate) fn record_todo(
+            &self,
+            storage: &dyn Storage,
+            operation: IdType,
+            old: Option<&Todo>,
+            new: Option<&Todo>,
+        ) -> Result<IdType, InternalError> {
+            self.record_history(storage, HistoryEntry::todo_changes(old, new, &self.actor))?;
+            self.record_journal(
+                storage,
+                operation,
+                old.cloned().map(Snapshot::Todo),
+                new.cloned().map(Snapshot::Todo),
+            )
+        }
+
+        /// Records a change of a task, as `record_todo`.
+        pub(crate) fn record_task(
+            &self,
+            storage: &dyn Storage,
+            operation: IdType,
+            old: Option<&Task>,
+            new: Option<&Task>,
+        ) -> Result<IdType, InternalError> {
+            self.record_history(storage, HistoryEntry::task_changes(old, new, &self.actor))?;
+            self.record_journal(
+                storage,
+                operation,
+                old.cloned().map(Snapshot::Task),
+                new.cloned().map(Snapshot::Task),
+            )
+        }
+
+        fn record_history(
+            &self,
+            storage: &dyn Storage,
+            entries: Vec<HistoryEntry>,
+        ) -> Result<(), InternalError> {
+            for entry in entries {
+                storage.insert_history(entry)?;
+            }
+
+            Ok(())
+        }
+
+        fn record_journal(
+            &self,
+            storage: &dyn Storage,
+            operation: IdType,
+            before: Option<Snapshot>,
+            after: Option<Snapshot>,
+        ) -> Result<IdType, InternalError> {
+            if before == after {
+                return Ok(operation);
+            }
+
+            // A new change can't be followed by the ones undone before it.
+            storage.clear_undone()?;
+            let entry = JournalEntry::new(0, operation, before, after, false);
+            Ok(*storage.insert_journal(entry)?.operation())
+        }
+    }
+
+    impl Todo {
+        pub fn all() -> Vec<Todo> {
+            Store::default_store().todos()
+        }
+
+        pub fn add(name: String, description: Option<String>) -> Result<Todo, InternalError> {
+            Store::default_store().add_todo(name, description)
+        }
+
+        pub fn update(
+            id: IdType,
+            new_name: Option<String>,
+            new_description: Option<String>,
+        ) -> Result<Todo, InternalError> {
+            Store::default_store().update_todo(id, new_name, new_description)
+        }
+
+        pub fn find(id: IdType) -> Result<Todo, InternalError> {
+            Store::default_store().find_todo(id)
+        }
+
+        /// Moves the todo to the trash, along with its tasks.
+        pub fn delete(id: IdType) -> Result<(), InternalError> {
+            Store::default_store().delete_todo(id)
+        }
+
+        /// Brings the todo back from the trash.
+        pub fn restore(id: IdType) -> Result<Todo, InternalError> {
+            Store::default_store().restore_todo(id)
+        }
+
+        /// Keeps the todo out of `Todo::all`, without deleting it.
+        pub fn archive(id: IdType) -> Result<Todo, InternalError> {
+            Store::default_store().archive_todo(id)
+        }
+
+        pub fn unarchive(id: IdType) -> Result<Todo, InternalError> {
+            Store::default_store().unarchive_todo(id)
+        }
+
+        pub fn tasks(&self) -> Vec<Task> {
+            Store::default_store().todo_tasks(*self.id())
+        }
+
+        /// How many of the tasks of the todo are done.
+        pub fn progress(&self) -> Progress {
+            Store::default_store().todo_progress(*self.id())
+        }
+
+        /// Sets every task of the todo as done, returning the ones changed.
+        pub fn complete(id: IdType) -> Result<Vec<Task>, InternalError> {
+            Store::default_store().complete_todo(id)
+        }
+
+        /// Lists the changes made to the todo and to its tasks.
+        pub fn history(&self) -> Vec<HistoryEntry> {
+            HistoryEntry::query(&HistoryQuery {
+                todo_id: Some(*self.id()),
+                ..HistoryQuery::default()
+            })
+        }
+
+        /// The time spent on the tasks of the todo.
+        pub fn time_spent(&self) -> Duration {
+            Store::default_store().todo_time(*self.id())
+        }
+
+        pub fn init_table() -> Result<(), sqlite::Error> {
+            Store::default_store()
+                .init_tables()
+                .map_err(|e| sqlite::Error {
+                    code: None,
+                    message: Some(e.to_string()),
+                })
+        }
+    }
+
+    impl Task {
+        pub fn all() -> Vec<Task> {
+            Store::default_store().tasks()
+        }
+
+        pub fn find(id: IdType) -> Result<Task, InternalError> {
+            Store::default_store().find_task(id)
+        }
+
+        /// Moves the task to the trash.
+        pub fn delete(id: IdType) -> Result<(), InternalError> {
+            Store::default_store().delete_task(id)
+        }
+
+        /// Brings the task back from the trash.
+        pub fn restore(id: IdType) -> Result<Task, InternalError> {
+            Store::default_store().restore_task(id)
+        }
+
+        pub fn add(what: String, todo_id: IdType) -> Result<Task, InternalError> {
+            Store::default_store().add_task(what, todo_id)
+        }
+
+        pub fn update(
+            id: IdType,
+            what_new: Option<String>,
+            new_status: Option<Status>,
+        ) -> Result<Task, InternalError> {
+            Store::default_store().update_task(id, what_new, new_status, None)
+        }
+
+        /// Sets the effort the task is expected to take, or clears it.
+        pub fn update_estimate(id: IdType, estimate: Option<f64>) -> Result<Task, InternalError> {
+            Store::default_store().set_task_estimate(id, estimate)
+        }
+
+        /// Lists the tasks matching the query.
+        pub fn query(query: &TaskQuery) -> Vec<Task> {
+            Store::default_store().query_tasks(query)
+        }
+
+        pub fn todo(&self) -> Result<Todo, InternalError> {
+            Todo::find(*self.todo_id())
+        }
+
+        /// Lists the changes made to the task.
+        pub fn history(&self) -> Vec<HistoryEntry> {
+            HistoryEntry::query(&HistoryQuery {
+                entity: Some(Entity::Task),
+                entity_id: Some(*self.id()),
+                ..HistoryQuery::default()
+            })
+        }
+
+        /// The time spent on the task.
+        pub fn time_spent(&self) -> Duration {
+            Store::default_store().task_time(*self.id())
+        }
+
+        pub fn init_table() -> Result<(), sqlite::Error> {
+            Store::default_store()
+                .init_tables()
+                .map_err(|e| sqlite::Error {
+                    code: None,
+                    message: Some(e.to_string()),
+                })
+        }
+    }
+
+    impl HistoryEntry {
+        /// Lists every change, oldest first.
+        pub fn all() -> Vec<HistoryEntry> {
+            Self::query(&HistoryQuery::default())
+        }
+
+        /// Lists the changes matching the query, oldest first.
+        pub fn query(query: &HistoryQuery) -> Vec<HistoryEntry> {
+            Store::default_store().history(query)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_api {
+    use super::core::*;
+    use super::data_access_layer::Store;
+    use super::history::*;
+    use super::journal::*;
+    use super::storage::*;
+    use lazy_static::lazy_static;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::thread;
+    use tokio::sync::{mpsc, oneshot};
+
+    /// Number of operations that may wait for the worker
+    /// before the callers have to wait to send more.
+    pub const QUEUE_SIZE: usize = 64;
+
+    type Job = Box<dyn FnOnce(&Store) + Send>;
+
+    lazy_static! {
+        static ref DEFAULT_ASYNC_STORE: AsyncStore = AsyncStore::spawn(None, QUEUE_SIZE);
+    }
+
+    fn stopped() -> InternalError {
+        InternalError::new("the storage worker has stopped")
+    }
+
+    #[derive(Clone)]
+    /// Async version of `Store`. The operations are sent through a bounded
+    /// queue to a dedicated thread, which runs them on the blocking store,
+    /// so they never block the executor.
+    pub struct AsyncStore {
+        sender: mpsc::Sender<Job>,
+    }
+
+    impl AsyncStore {
+        /// Runs the operations of the given store on a new worker thread.
+        pub fn new(store: Arc<Store>) -> Self {
+            Self::with_queue_size(store, QUEUE_SIZE)
+        }
+
+        /// Same as `new`, but waiting for at most `size` queued operations.
+        pub fn with_queue_size(store: Arc<Store>, size: usize) -> Self {
+            Self::spawn(Some(store), size)
+        }
+
+        /// The async store running on the default store of the process,
+        /// the same used by the static methods of `Todo` and `Task`.
+        pub fn default_store() -> Self {
+            DEFAULT_ASYNC_STORE.clone()
+        }
+
+        fn spawn(store: Option<Arc<Store>>, size: usize) -> Self {
+            let (sender, mut receiver) = mpsc::channel::<Job>(size.max(1));
+
+            thread::Builder::new()
+                .name(String::from("todo-storage"))
+                .spawn(move || {
+                    while let Some(job) = receiver.blocking_recv() {
+                        let store = store.clone().unwrap_or_else(Store::default_store);
+                        // A panicking operation only loses its own response.
+                        let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&store)));
+                    }
+                })
+                .expect("failed to spawn the storage worker");
+
+            Self { sender }
+        }
+
+        /// Sends the operation to the worker and waits for its result.
+        async fn run<T: Send + 'static>(
+            &self,
+            operation: impl FnOnce(&Store) -> T + Send + 'static,
+        ) -> Result<T, InternalError> {
+            let (reply, response) = oneshot::channel();
+
+            let job: Job = Box::new(move |store| {
+                let _ = reply.send(operation(store));
+            });
+
+            self.sender.send(job).await.map_err(|_| stopped())?;
+            response.await.map_err(|_| stopped())
+        }
+
+        async fn try_run<T: Send + 'static>(
+            &self,
+            operation: impl FnOnce(&Store) -> Result<T, InternalError> + Send + 'static,
+        ) -> Result<T, InternalError> {
+            self.run(operation).await?
+        }
+
+        /// Runs the operation inside of a transaction of the storage.
+        pub async fn transaction<T: Send + 'static>(
+            &self,
+            operation: impl FnOnce(&dyn Storage) -> Result<T, InternalError> + Send + 'static,
+        ) -> Result<T, InternalError> {
+            self.try_run(move |store| store.transaction(operation)).await
+        }
+
+        pub async fn todos(&self) -> Vec<Todo> {
+            self.run(|store| store.todos()).await.unwrap_or_default()
+        }
+
+        pub async fn find_todo(&self, id: IdType) -> Result<Todo, InternalError> {
+            self.try_run(move |store| store.find_todo(id)).await
+        }
+
+        pub async fn add_todo(
+            &self,
+            name: String,
+            description: Option<String>,
+        ) -> Result<Todo, InternalError> {
+            self.try_run(move |store| store.add_todo(name, description)).await
+        }
+
+        pub async fn update_todo(
+            &self,
+            id: IdType,
+            new_name: Option<String>,
+            new_description: Option<String>,
+        ) -> Result<Todo, InternalError> {
+            self.try_run(move |store| store.update_todo(id, new_name, new_description))
+                .await
+        }
+
+        pub async fn delete_todo(&self, id: IdType) -> Result<(), InternalError> {
+            self.try_run(move |store| store.delete_todo(id)).await
+        }
+
+        /// Lists the tasks of a todo.
+        pub async fn todo_tasks(&self, todo_id: IdType) -> Vec<Task> {
+            self.run(move |store| store.todo_tasks(todo_id))
+                .await
+                .unwrap_or_default()
+        }
+
+        pub async fn tasks(&self) -> Vec<Task> {
+            self.run(|store| store.tasks()).await.unwrap_or_default()
+        }
+
+        /// Lists the tasks matching the query.
+        pub async fn query_tasks(&self, query: TaskQuery) -> Vec<Task> {
+            self.run(move |store| store.query_tasks(&query))
+                .await
+                .unwrap_or_default()
+        }
+
+        pub async fn find_task(&self, id: IdType) -> Result<Task, InternalError> {
+            self.try_run(move |store| store.find_task(id)).await
+        }
+
+        pub async fn add_task(&self, what: String, todo_id: IdType) -> Result<Task, InternalError> {
+            self.try_run(move |store| store.add_task(what, todo_id)).await
+        }
+
+        pub async fn update_task(
+            &self,
+            id: IdType,
+            what_new: Option<String>,
+            new_status: Option<Status>,
+            new_estimate: Option<f64>,
+        ) -> Result<Task, InternalError> {
+            self.try_run(move |store| store.update_task(id, what_new, new_status, new_estimate))
+                .await
+        }
+
+        pub async fn delete_task(&self, id: IdType) -> Result<(), InternalError> {
+            self.try_run(move |store| store.delete_task(id)).await
+        }
+
+        /// Undoes the last `count` operations, as `Store::undo`.
+        pub async fn undo(&self, count: usize) -> Result<Vec<JournalEntry>, InternalError> {
+            self.try_run(move |store| store.undo(count)).await
+        }
+
+        /// Redoes the last `count` operations undone, as `Store::redo`.
+        pub async fn redo(&self, count: usize) -> Result<Vec<JournalEntry>, InternalError> {
+            self.try_run(move |store| store.redo(count)).await
+        }
+
+        /// Lists the changes matching the query, oldest first.
+        pub async fn history(&self, query: HistoryQuery) -> Vec<HistoryEntry> {
+            self.run(move |store| store.history(&query))
+                .await
+                .unwrap_or_default()
+        }
+    }
+}
+
+mod csv_io {
+    use super::core::*;
+    use super::data_access_layer::{Store, NEW_OPERATION};
+    use std::collections::HashMap;
+    use std::fmt::{self, Display};
+    use std::io;
+    use std::str::FromStr;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    /// A column that can be written to or read from
+    /// a csv file. Each row of the file is a task
+    /// joined with the todo it belongs to.
+    pub enum CsvColumn {
+        TodoId,
+        TodoName,
+        TodoDescription,
+        TaskId,
+        What,
+        Status,
+        CreatedAt,
+        UpdatedAt,
+        CompletedAt,
+        Estimate,
+    }
+
+    impl CsvColumn {
+        /// Every column supported, in the default order.
+        pub const ALL: [CsvColumn; 10] = [
+            CsvColumn::TodoId,
+            CsvColumn::TodoName,
+            CsvColumn::TodoDescription,
+            CsvColumn::TaskId,
+            CsvColumn::What,
+            CsvColumn::Status,
+            CsvColumn::CreatedAt,
+            CsvColumn::UpdatedAt,
+            CsvColumn::CompletedAt,
+            CsvColumn::Estimate,
+        ];
+
+        /// The name of the column, as used in the header of the file.
+        pub fn name(&self) -> &'static str {
+            match self {
+                CsvColumn::TodoId => "todo_id",
+                CsvColumn::TodoName => "todo_name",
+                CsvColumn::TodoDescription => "todo_description",
+                CsvColumn::TaskId => "task_id",
+                CsvColumn::What => "what",
+                CsvColumn::Status => "status",
+                CsvColumn::CreatedAt => "created_at",
+                CsvColumn::UpdatedAt => "updated_at",
+                CsvColumn::CompletedAt => "completed_at",
+                CsvColumn::Estimate => "estimate",
+            }
+        }
+
+        /// Extracts the value of this column from a todo and one of its tasks.
+        fn value(&self, todo: &Todo, task: Option<&Task>) -> String {
+            match (self, task) {
+                (CsvColumn::TodoId, _) => todo.id().to_string(),
+                (CsvColumn::TodoName, _) => todo.name().clone(),
+                (CsvColumn::TodoDescription, _) => todo.description().cloned().unwrap_or_default(),
+                (CsvColumn::TaskId, Some(task)) => task.id().to_string(),
+                (CsvColumn::What, Some(task)) => task.what().clone(),
+                (CsvColumn::Status, Some(task)) => String::from(task.status().name()),
+                (CsvColumn::CreatedAt, Some(task)) => task.created_at().clone(),
+                (CsvColumn::UpdatedAt, Some(task)) => task.updated_at().clone(),
+                (CsvColumn::Estimate, Some(task)) => {
+                    task.estimate().map(|e| e.to_string()).unwrap_or_default()
+                }
+                (CsvColumn::CompletedAt, Some(task)) => match task.status() {
+                    Status::Done(date) => date.clone(),
+                    _ => String::new(),
+                },
+                (_, None) => String::new(),
+            }
+        }
+    }
+
+    impl Display for CsvColumn {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+
+    impl FromStr for CsvColumn {
+        type Err = InternalError;
+
+        fn from_str(name: &str) -> Result<Self, Self::Err> {
+            Self::ALL
+                .iter()
+                .find(|column| column.name() == name.trim())
+                .copied()
+                .ok_or_else(|| InternalError::new(&format!("unknown csv column '{}'", name)))
+        }
+    }
+
+    fn csv_error(e: csv::Error) -> InternalError {
+        InternalError::new(&e.to_string())
+    }
+
+    /// Writes every task of the store, joined with its todo, as a row of
+    /// a csv file with the given columns. Todos without tasks are written
+    /// as a single row with the task columns left empty.
+    ///
+    /// Returns the number of rows written, not counting the header.
+    pub fn export_csv<W: io::Write>(
+        store: &Store,
+        writer: W,
+        columns: &[CsvColumn],
+    ) -> Result<usize, InternalError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        let mut todos: HashMap<IdType, Todo> = HashMap::new();
+        let mut rows = 0;
+
+        writer
+            .write_record(columns.iter().map(|c| c.name()))
+            .map_err(csv_error)?;
+
+        for task in store.tasks() {
+            if !todos.contains_key(task.todo_id()) {
+                match store.find_todo(*task.todo_id()) {
+                    Ok(todo) => todos.insert(*task.todo_id(), todo),
+                    // Tasks left behind by a deleted todo are skipped.
+                    Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                };
+            }
+
+            let todo = &todos[task.todo_id()];
+
+            writer
+                .write_record(columns.iter().map(|c| c.value(todo, Some(&task))))
+                .map_err(csv_error)?;
+            rows += 1;
+        }
+
+        for todo in store.todos() {
+            if !todos.contains_key(todo.id()) {
+                writer
+                    .write_record(columns.iter().map(|c| c.value(&todo, None)))
+                    .map_err(csv_error)?;
+                rows += 1;
+            }
+        }
+
+        writer.flush().map_err(|e| InternalError::new(&e.to_string()))?;
+        Ok(rows)
+    }
+
+    /// Reads a csv file with a header row and adds its todos and tasks
+    /// to the store. Rows are grouped into todos by the `todo_id`
+    /// column, or by `todo_name` if there isn't one; new ids are
+    /// always given to the imported objects.
+    ///
+    /// The whole file is read before anything is written, then added in
+    /// a single transaction: an invalid row leaves the store untouched.
+    /// The import is one operation of the journal and runs no hooks.
+    ///
+    /// Returns the number of todos and tasks added.
+    pub fn import_csv<R: io::Read>(store: &Store, reader: R) -> Result<(usize, usize), InternalError> {
+        let mut reader = csv::Reader::from_reader(reader);
+
+        let header: Vec<Option<CsvColumn>> = reader
+            .headers()
+            .map_err(csv_error)?
+            .iter()
+            .map(|name| name.parse().ok())
+            .collect();
+
+        let position = |column: CsvColumn| header.iter().position(|c| *c == Some(column));
+
+        let todo_name = position(CsvColumn::TodoName)
+            .ok_or_else(|| InternalError::new("the csv file has no 'todo_name' column"))?;
+        let todo_id = position(CsvColumn::TodoId);
+        let todo_description = position(CsvColumn::TodoDescription);
+        let what = position(CsvColumn::What);
+        let status = position(CsvColumn::Status);
+        let completed_at = position(CsvColumn::CompletedAt);
+        let estimate = position(CsvColumn::Estimate);
+
+        let now = "CURRENT_TIMESTAMP";
+        let mut todos: Vec<(Todo, Vec<Task>)> = Vec::new();
+        let mut keys: HashMap<String, usize> = HashMap::new();
+
+        for record in reader.records() {
+            let record = record.map_err(csv_error)?;
+            let field = |i: Option<usize>| {
+                i.and_then(|i| record.get(i))
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            };
+
+            let name = field(Some(todo_name))
+                .ok_or_else(|| InternalError::new("found a row with an empty 'todo_name'"))?;
+            let key = field(todo_id).unwrap_or_else(|| name.clone());
+
+            let index = match keys.get(&key) {
+                Some(index) => *index,
+                None => {
+                    let todo = Todo::new(0, name, field(todo_description), now.into(), now.into());
+                    todos.push((todo, Vec::new()));
+                    keys.insert(key, todos.len() - 1);
+                    todos.len() - 1
+                }
+            };
+
+            if let Some(what) = field(what) {
+                let done = field(status).map(|s| s.eq_ignore_ascii_case("done"));
+                let date = field(completed_at);
+                let estimate = field(estimate)
+                    .map(|e| match e.parse::<f64>() {
+                        Ok(estimate) if estimate.is_finite() && estimate >= 0.0 => Ok(estimate),
+                        _ => Err(InternalError::invalid_input(&format!("invalid estimate '{}'", e))),
+                    })
+                    .transpose()?;
+
+                let status = if done.unwrap_or(date.is_some()) {
+                    Status::Done(date.unwrap_or_else(|| String::from(now)))
+                } else {
+                    Status::Todo
+                };
+
+                let mut task = Task::new(0, 0, &what, now, now, status);
+                task.set_estimate(estimate);
+                todos[index].1.push(task);
+            }
+        }
+
+        let todos_added = todos.len();
+
+        let tasks_added = store.transaction(|storage| {
+            let mut operation = NEW_OPERATION;
+            let mut tasks_added = 0;
+
+            for (todo, tasks) in todos {
+                let todo = storage.insert_todo(todo)?;
+                operation = store.record_todo(storage, operation, None, Some(&todo))?;
+
+                for mut task in tasks {
+                    task.set_todo_id(*todo.id());
+                    let task = storage.insert_task(task)?;
+                    operation = store.record_task(storage, operation, None, Some(&task))?;
+                    tasks_added += 1;
+                }
+            }
+
+            Ok(tasks_added)
+        })?;
+
+        Ok((todos_added, tasks_added))
+    }
+}
+
+mod server {
+    use super::core::*;
+    use super::data_access_layer::Store;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use std::thread;
+    use tiny_http::{Header, Request, Response, Server};
+
+    /// Number of threads answering requests.
+    const WORKERS: usize = 4;
+
+    #[derive(Debug, PartialEq)]
+    /// The outcome of a request made to the API: an
+    /// http status code and an optional json body.
+    pub struct ApiResponse {
+        pub status: u16,
+        pub body: Option<Value>,
+    }
+
+    impl ApiResponse {
+        fn new(status: u16, body: Value) -> Self {
+            Self {
+                status,
+                body: Some(body),
+            }
+        }
+
+        fn no_content() -> Self {
+            Self {
+                status: 204,
+                body: None,
+            }
+        }
+
+        fn error(status: u16, details: &str) -> Self {
+            Self::new(status, json!({ "error": details }))
+        }
+    }
+
+    impl From<InternalError> for ApiResponse {
+        fn from(e: InternalError) -> Self {
+            let status = match e.kind() {
+                ErrorKind::NotFound => 404,
+                ErrorKind::AlreadyExists => 409,
+                ErrorKind::InvalidInput => 422,
+                ErrorKind::Other => 500,
+            };
+
+            Self::error(status, &e.to_string())
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct TodoPayload {
+        name: Option<String>,
+        /// `None` keeps the description, `Some(None)` clears it with `null`.
+        #[serde(default, deserialize_with = "nullable")]
+        description: Option<Option<String>>,
+    }
+
+    /// Tells an explicit `null` apart from a missing field.
+    fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct TaskPayload {
+        what: Option<String>,
+        status: Option<String>,
+        started_at: Option<String>,
+        blocked_at: Option<String>,
+        reason: Option<String>,
+        completed_at: Option<String>,
+        cancelled_at: Option<String>,
+        estimate: Option<f64>,
+    }
+
+    impl TaskPayload {
+        /// The status asked for, its date is the current one if not given.
+        fn status(&self) -> Result<Option<Status>, InternalError> {
+            let status = match &self.status {
+                Some(status) => status.clone(),
+                None => return Ok(None),
+            };
+            let date = |date: &Option<String>| {
+                Some(date.clone().unwrap_or_else(|| String::from("CURRENT_TIMESTAMP")))
+            };
+
+            let fields = StatusFields {
+                status,
+                started_at: date(&self.started_at),
+                blocked_at: date(&self.blocked_at),
+                reason: self.reason.clone(),
+                completed_at: date(&self.completed_at),
+                cancelled_at: date(&self.cancelled_at),
+            };
+
+            Status::try_from(fields).map(Some)
+        }
+    }
+
+    fn parse<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, ApiResponse> {
+        serde_json::from_str(body).map_err(|e| ApiResponse::error(400, &e.to_string()))
+    }
+
+    fn parse_id(segment: &str) -> Result<IdType, ApiResponse> {
+        segment
+            .parse()
+            .map_err(|_| ApiResponse::error(400, &format!("invalid id '{}'", segment)))
+    }
+
+    fn to_json<T: serde::Serialize>(value: &T) -> Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    /// Answers a single request to the API. It is independent
+    /// of the http server, so it can be used by other front-ends.
+    pub fn handle_request(method: &str, url: &str, body: &str) -> ApiResponse {
+        route(method, url, body).unwrap_or_else(|response| response)
+    }
+
+    fn route(method: &str, url: &str, body: &str) -> Result<ApiResponse, ApiResponse> {
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match (method, segments.as_slice()) {
+            ("GET", ["todos"]) => Ok(ApiResponse::new(200, to_json(&Todo::all()))),
+            ("POST", ["todos"]) => {
+                let payload: TodoPayload = parse(body)?;
+                let name = payload
+                    .name
+                    .ok_or_else(|| ApiResponse::error(422, "missing field 'name'"))?;
+                let todo = Todo::add(name, payload.description.flatten())?;
+                Ok(ApiResponse::new(201, to_json(&todo)))
+            }
+            ("GET", ["todos", id]) => {
+                let todo = Todo::find(parse_id(id)?)?;
+                Ok(ApiResponse::new(200, to_json(&todo)))
+            }
+            ("PATCH", ["todos", id]) => {
+                let id = parse_id(id)?;
+                let payload: TodoPayload = parse(body)?;
+                let todo = Store::default_store().patch_todo(id, payload.name, payload.description)?;
+                Ok(ApiResponse::new(200, to_json(&todo)))
+            }
+            ("DELETE", ["todos", id]) => {
+                Todo::delete(parse_id(id)?)?;
+                Ok(ApiResponse::no_content())
+            }
+            ("GET", ["todos", id, "tasks"]) => {
+                let todo = Todo::find(parse_id(id)?)?;
+                Ok(ApiResponse::new(200, to_json(&todo.tasks())))
+            }
+            ("POST", ["todos", id, "tasks"]) => {
+                let id = parse_id(id)?;
+                let payload: TaskPayload = parse(body)?;
+                let status = payload.status()?;
+                let what = payload
+                    .what
+                    .ok_or_else(|| ApiResponse::error(422, "missing field 'what'"))?;
+                let task = Store::default_store().add_task_with(what, id, status, payload.estimate)?;
+                Ok(ApiResponse::new(201, to_json(&task)))
+            }
+            ("GET", ["tasks"]) => Ok(ApiResponse::new(200, to_json(&Task::all()))),
+            ("GET", ["tasks", id]) => {
+                let task = Task::find(parse_id(id)?)?;
+                Ok(ApiResponse::new(200, to_json(&task)))
+            }
+            ("PATCH", ["tasks", id]) => {
+                let id = parse_id(id)?;
+                let payload: TaskPayload = parse(body)?;
+                let task = Store::default_store().update_task(id, payload.what.clone(), payload.status()?, payload.estimate)?;
+                Ok(ApiResponse::new(200, to_json(&task)))
+            }
+            ("DELETE", ["tasks", id]) => {
+                Task::delete(parse_id(id)?)?;
+                Ok(ApiResponse::no_content())
+            }
+            (_, ["todos"]) | (_, ["todos", _]) | (_, ["todos", _, "tasks"]) | (_, ["tasks"])
+            | (_, ["tasks", _]) => Err(ApiResponse::error(405, "method not allowed")),
+            _ => Err(ApiResponse::error(404, "no such resource")),
+        }
+    }
+
+    fn respond(mut request: Request) {
+        let mut body = String::new();
+
+        let response = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle_request(request.method().as_str(), request.url(), &body),
+            Err(e) => ApiResponse::error(400, &e.to_string()),
+        };
+
+        let content = response.body.map(|b| b.to_string()).unwrap_or_default();
+        let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let res = Response::from_string(content)
+            .with_status_code(response.status)
+            .with_header(header);
+
+        if let Err(e) = request.respond(res) {
+            eprintln!("Could not answer the request: {}", e);
+        }
+    }
+
+    /// Serves the todos and tasks as a json REST API on the
+    /// given address. Blocks until the server is shut down.
+    pub fn serve(address: &str) -> Result<(), InternalError> {
+        serve_on(bind(address)?)
+    }
+
+    /// Listens on the given address, for `serve_on`.
+    pub fn bind(address: &str) -> Result<Server, InternalError> {
+        Server::http(address).map_err(|e| InternalError::new(&e.to_string()))
+    }
+
+    /// Serves the API with a server already listening, see `serve`.
+    pub fn serve_on(server: Server) -> Result<(), InternalError> {
+        let server = Arc::new(server);
+
+        let workers: Vec<_> = (0..WORKERS)
+            .map(|_| {
+                let server = Arc::clone(&server);
+                thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        respond(request);
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker
+                .join()
+                .map_err(|_| InternalError::new("a server worker has panicked"))?;
+        }
+
+        Ok(())
+    }
+}
+
+mod tui {
+    use super::core::*;
+    use super::data_access_layer::Store;
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+    use ratatui::layout::{Constraint, Direction, Layout};
+    use ratatui::style::{Modifier, Style};
+    use ratatui::text::Line;
+    use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+    use ratatui::{DefaultTerminal, Frame};
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    /// The list that currently has the focus.
+    pub enum Pane {
+        Todos,
+        Tasks,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    /// A field that can be edited inline.
+    pub enum Field {
+        Name,
+        Description,
+        What,
+        NewTodo,
+        NewTask,
+    }
+
+    impl Field {
+        fn label(&self) -> &'static str {
+            match self {
+                Field::Name => "name",
+                Field::Description => "description",
+                Field::What => "what",
+                Field::NewTodo => "new todo",
+                Field::NewTask => "new task",
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    /// What the key presses are currently used for.
+    pub enum Mode {
+        Normal,
+        Editing { field: Field, buffer: String },
+        Filtering,
+        ConfirmDelete(Pane, IdType),
+    }
+
+    /// The state of the terminal interface, kept apart
+    /// from the drawing so it can be driven without a terminal.
+    pub struct TuiState {
+        store: Arc<Store>,
+        todos: Vec<Todo>,
+        /// The progress of each todo, in the same order.
+        progress: Vec<Progress>,
+        tasks: Vec<Task>,
+        todo_state: ListState,
+        task_state: ListState,
+        focus: Pane,
+        mode: Mode,
+        filter: String,
+        message: Option<String>,
+    }
+
+    impl Default for TuiState {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl TuiState {
+        pub fn new() -> Self {
+            Self::with_store(Store::default_store())
+        }
+
+        /// A state showing and changing the todos of the given store.
+        pub fn with_store(store: Arc<Store>) -> Self {
+            let mut state = Self {
+                store,
+                todos: Vec::new(),
+                progress: Vec::new(),
+                tasks: Vec::new(),
+                todo_state: ListState::default(),
+                task_state: ListState::default(),
+                focus: Pane::Todos,
+                mode: Mode::Normal,
+                filter: String::new(),
+                message: None,
+            };
+
+            state.reload();
+            state
+        }
+
+        /// The todos shown on the left pane.
+        pub fn todos(&self) -> &Vec<Todo> {
+            &self.todos
+        }
+
+        /// The tasks of the selected todo, shown on the right pane.
+        pub fn tasks(&self) -> &Vec<Task> {
+            &self.tasks
+        }
+
+        pub fn focus(&self) -> Pane {
+            self.focus
+        }
+
+        pub fn mode(&self) -> &Mode {
+            &self.mode
+        }
+
+        pub fn filter(&self) -> &String {
+            &self.filter
+        }
+
+        pub fn selected_todo(&self) -> Option<&Todo> {
+            self.todo_state.selected().and_then(|i| self.todos.get(i))
+        }
+
+        pub fn selected_task(&self) -> Option<&Task> {
+            self.task_state.selected().and_then(|i| self.tasks.get(i))
+        }
+
+        fn matches(&self, text: &str) -> bool {
+            text.to_lowercase().contains(&self.filter.to_lowercase())
+        }
+
+        fn todo_matches(&self, todo: &Todo) -> bool {
+            self.matches(todo.name()) || todo.description().is_some_and(|d| self.matches(d))
+        }
+
+        /// Reads the todos and tasks again from the database,
+        /// keeping the selection where possible.
+        pub fn reload(&mut self) {
+            let selected = self.selected_todo().map(|t| *t.id());
+            let all_tasks = self.store.tasks();
+
+            self.todos = self
+                .store
+                .todos()
+                .into_iter()
+                .filter(|todo| {
+                    self.todo_matches(todo)
+                        || all_tasks
+                            .iter()
+                            .any(|t| t.todo_id() == todo.id() && self.matches(t.what()))
+                })
+                .collect();
+
+            let index = selected
+                .and_then(|id| self.todos.iter().position(|t| *t.id() == id))
+                .or(if self.todos.is_empty() { None } else { Some(0) })
+                .map(|i| i.min(self.todos.len().saturating_sub(1)));
+
+            self.todo_state.select(index);
+            self.reload_tasks();
+        }
+
+        fn reload_tasks(&mut self) {
+            let selected = self.selected_task().map(|t| *t.id());
+            let all_tasks = self.store.tasks();
+
+            self.progress = self
+                .todos
+                .iter()
+                .map(|todo| {
+                    let tasks: Vec<Task> = all_tasks
+                        .iter()
+                        .filter(|t| t.todo_id() == todo.id())
+                        .cloned()
+                        .collect();
+                    Progress::of(&tasks)
+                })
+                .collect();
+
+            self.tasks = match self.selected_todo() {
+                Some(todo) => {
+                    let show_all = self.todo_matches(todo);
+                    self.store
+                        .todo_tasks(*todo.id())
+                        .into_iter()
+                        .filter(|t| show_all || self.matches(t.what()))
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+
+            let index = selected
+                .and_then(|id| self.tasks.iter().position(|t| *t.id() == id))
+                .or(if self.tasks.is_empty() { None } else { Some(0) });
+
+            self.task_state.select(index);
+        }
+
+        fn move_selection(&mut self, delta: isize) {
+            let (state, len) = match self.focus {
+                Pane::Todos => (&mut self.todo_state, self.todos.len()),
+                Pane::Tasks => (&mut self.task_state, self.tasks.len()),
+            };
+
+            if len > 0 {
+                let current = state.selected().unwrap_or(0) as isize;
+                let next = (current + delta).clamp(0, len as isize - 1);
+                state.select(Some(next as usize));
+            }
+
+            if self.focus == Pane::Todos {
+                self.task_state.select(None);
+                self.reload_tasks();
+            }
+        }
+
+        fn report<T>(&mut self, res: Result<T, InternalError>) {
+            match res {
+                Ok(_) => self.reload(),
+                Err(e) => self.message = Some(e.to_string()),
+            }
+        }
+
+        fn toggle_status(&mut self) {
+            if let Some(task) = self.selected_task() {
+                let status = if task.status().is_closed() {
+                    Status::Todo
+                } else {
+                    Status::Done(String::from("CURRENT_TIMESTAMP"))
+                };
+                let res = self.store.update_task(*task.id(), None, Some(status), None);
+                self.report(res);
+            }
+        }
+
+        fn start_editing(&mut self, field: Field) {
+            let buffer = match field {
+                Field::Name => self.selected_todo().map(|t| t.name().clone()),
+                Field::Description => self
+                    .selected_todo()
+                    .map(|t| t.description().cloned().unwrap_or_default()),
+                Field::What => self.selected_task().map(|t| t.what().clone()),
+                Field::NewTodo => Some(String::new()),
+                Field::NewTask => self.selected_todo().map(|_| String::new()),
+            };
+
+            if let Some(buffer) = buffer {
+                self.mode = Mode::Editing { field, buffer };
+            }
+        }
+
+        fn commit_edit(&mut self, field: Field, value: String) {
+            let todo = self.selected_todo().map(|t| *t.id());
+            let task = self.selected_task().map(|t| *t.id());
+
+            let store = &self.store;
+            let res = match (field, todo, task) {
+                (Field::Name, Some(id), _) => store.update_todo(id, Some(value), None).map(|_| ()),
+                // An emptied description is removed rather than kept empty.
+                (Field::Description, Some(id), _) if value.is_empty() => {
+                    store.clear_todo_description(id).map(|_| ())
+                }
+                (Field::Description, Some(id), _) => {
+                    store.update_todo(id, None, Some(value)).map(|_| ())
+                }
+                (Field::What, _, Some(id)) => store.update_task(id, Some(value), None, None).map(|_| ()),
+                (Field::NewTodo, _, _) => store.add_todo(value, None).map(|_| ()),
+                (Field::NewTask, Some(id), _) => store.add_task(value, id).map(|_| ()),
+                _ => Ok(()),
+            };
+
+            self.report(res);
+        }
 
-        fn all() -> Vec<Self::ObjType> {
-            let mut todos: Vec<Todo> = Vec::new();
+        fn delete(&mut self, pane: Pane, id: IdType) {
+            let res = match pane {
+                Pane::Todos => self.store.delete_todo(id),
+                Pane::Tasks => self.store.delete_task(id),
+            };
 
-            if Self::is_table_initialized() {
-                let query = format!(
-                    "SELECT id, name, description, created_at, updated_at FROM {}",
-                    Self::table_name()
-                );
+            self.report(res);
+        }
 
-                if let Ok(mut cursor) = DB.lock().unwrap().select_query(&query) {
-                    while let Some(result) = cursor.next().unwrap() {
-                        let id: IdType = result[0].as_integer().unwrap() as IdType;
-                        let name = result[1].as_string().unwrap();
-                        let description = result[2].as_string().map(|desc| String::from(desc));
-                        let created_at = result[3].as_string().unwrap();
-                        let updated_at = result[4].as_string().unwrap();
-                        todos.push(Todo::new(
-                            id,
-                            name.into(),
-                            description,
-                            created_at.into(),
-                            updated_at.into(),
-                        ))
+        /// Handles a key press, returns false when the
+        /// interface should be closed.
+        pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+            self.message = None;
+
+            match std::mem::replace(&mut self.mode, Mode::Normal) {
+                Mode::Normal => return self.handle_normal_key(key),
+                Mode::Editing { field, mut buffer } => match key.code {
+                    KeyCode::Enter => self.commit_edit(field, buffer),
+                    KeyCode::Esc => (),
+                    KeyCode::Backspace => {
+                        buffer.pop();
+                        self.mode = Mode::Editing { field, buffer };
+                    }
+                    KeyCode::Char(c) => {
+                        buffer.push(c);
+                        self.mode = Mode::Editing { field, buffer };
+                    }
+                    _ => self.mode = Mode::Editing { field, buffer },
+                },
+                Mode::Filtering => match key.code {
+                    KeyCode::Enter => (),
+                    KeyCode::Esc => {
+                        self.filter.clear();
+                        self.reload();
+                    }
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                        self.mode = Mode::Filtering;
+                        self.reload();
+                    }
+                    KeyCode::Char(c) => {
+                        self.filter.push(c);
+                        self.mode = Mode::Filtering;
+                        self.reload();
+                    }
+                    _ => self.mode = Mode::Filtering,
+                },
+                Mode::ConfirmDelete(pane, id) => {
+                    if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+                        self.delete(pane, id);
                     }
                 }
             }
 
-            return todos;
+            true
         }
 
-        fn find(id: IdType) -> Result<Self::ObjType, InternalError> {
-            if !Self::is_table_initialized() {
-                return Err(InternalError::table_not_initialized(&Self::table_name()));
+        fn handle_normal_key(&mut self, key: KeyEvent) -> bool {
+            match key.code {
+                KeyCode::Char('q') => return false,
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Left | KeyCode::Char('h') => self.focus = Pane::Todos,
+                KeyCode::Right | KeyCode::Char('l') if !self.tasks.is_empty() => {
+                    self.focus = Pane::Tasks
+                }
+                KeyCode::Tab if !self.tasks.is_empty() || self.focus == Pane::Tasks => {
+                    self.focus = match self.focus {
+                        Pane::Todos => Pane::Tasks,
+                        Pane::Tasks => Pane::Todos,
+                    }
+                }
+                KeyCode::Char(' ') if self.focus == Pane::Tasks => self.toggle_status(),
+                KeyCode::Char('e') => match self.focus {
+                    Pane::Todos => self.start_editing(Field::Name),
+                    Pane::Tasks => self.start_editing(Field::What),
+                },
+                KeyCode::Char('E') if self.focus == Pane::Todos => {
+                    self.start_editing(Field::Description)
+                }
+                KeyCode::Char('a') => match self.focus {
+                    Pane::Todos => self.start_editing(Field::NewTodo),
+                    Pane::Tasks => self.start_editing(Field::NewTask),
+                },
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    let id = match self.focus {
+                        Pane::Todos => self.selected_todo().map(|t| *t.id()),
+                        Pane::Tasks => self.selected_task().map(|t| *t.id()),
+                    };
+
+                    if let Some(id) = id {
+                        self.mode = Mode::ConfirmDelete(self.focus, id);
+                    }
+                }
+                KeyCode::Char('/') => self.mode = Mode::Filtering,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.reload();
+                }
+                _ => (),
             }
 
-            let query = format!(
-                "
-                SELECT id, name, description, created_at, updated_at FROM {} WHERE id = {}",
-                Self::table_name(),
-                id
-            );
-
-            let todo: Option<Todo> = if let Ok(mut cursor) = DB.lock().unwrap().select_query(&query)
-            {
-                cursor.next().unwrap().map(|t: &[sqlite::Value]| {
-                    let id: IdType = t[0].as_integer().unwrap() as IdType;
-                    let name = t[1].as_string().unwrap();
-                    let description = t[2].as_string().map(|desc| String::from(desc));
-                    let created_at = t[3].as_string().unwrap();
-                    let updated_at = t[4].as_string().unwrap();
-                    Todo::new(
-                        id,
-                        name.into(),
-                        description,
-                        created_at.into(),
-                        updated_at.into(),
-                    )
-                })
-            } else {
-                None
-            };
+            if self.tasks.is_empty() {
+                self.focus = Pane::Todos;
+            }
 
-            let details = format!("todo with id = {} was not found in the database.", id);
-            todo.ok_or(InternalError::new(&details))
+            true
         }
 
-        fn update(obj: Self::ObjType) -> Result<Self::ObjType, InternalError> {
-            if !Self::is_table_initialized() {
-                return Err(InternalError::table_not_initialized(&Self::table_name()));
+        fn status_line(&self) -> String {
+            if let Some(message) = &self.message {
+                return message.clone();
             }
 
-            let todo = Self::find(*obj.id()) ? ;
-
-            /* Here what can be changed currently are the name and description. */
-
-            if obj.name() != todo.name() {
-                let statement = format!(
-                    "UPDATE {} SET name = '{}', updated_at = CURRENT_TIMESTAMP WHERE id = {};",
-                    Self::table_name(),
-                    obj.name(),
-                    obj.id()
-                );
-
-                let res = DB.lock().unwrap().exec_sttmt(&statement);
-
-                if let Err(e) = res {
-                    return Err(InternalError::new(&e.to_string()));
+            match &self.mode {
+                Mode::Normal if self.filter.is_empty() => String::from(
+                    "q quit | j/k move | tab switch | space toggle | e edit | E description | a add | d delete | / filter",
+                ),
+                Mode::Normal => format!("filter: {} (esc to clear)", self.filter),
+                Mode::Editing { field, buffer } => format!("{}: {}_", field.label(), buffer),
+                Mode::Filtering => format!("/{}_", self.filter),
+                Mode::ConfirmDelete(pane, id) => {
+                    let what = match pane {
+                        Pane::Todos => "todo",
+                        Pane::Tasks => "task",
+                    };
+                    format!("Delete {} {}? (y/n)", what, id)
                 }
             }
+        }
+
+        fn draw(&mut self, frame: &mut Frame) {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(1)])
+                .split(frame.area());
+
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(rows[0]);
+
+            let highlight = Style::default().add_modifier(Modifier::REVERSED);
+            let border = |pane: Pane, title: String| {
+                let block = Block::default().borders(Borders::ALL).title(title);
+                if self.focus == pane {
+                    block.border_style(Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    block
+                }
+            };
 
-            if obj.description() != todo.description() {
-                let description = obj
-                    .description()
-                    .map(|desc| format!("'{}'", desc))
-                    .unwrap_or(String::from("NULL"));
+            let todos: Vec<ListItem> = self
+                .todos
+                .iter()
+                .zip(&self.progress)
+                .map(|(t, p)| ListItem::new(format!("{:>4} {} {}", t.id(), p.bar(5), t.name())))
+                .collect();
+
+            let title = self
+                .selected_todo()
+                .and_then(|t| t.description().cloned())
+                .map(|d| format!("Tasks - {}", d))
+                .unwrap_or_else(|| String::from("Tasks"));
+
+            let tasks: Vec<ListItem> = self
+                .tasks
+                .iter()
+                .map(|t| ListItem::new(format!("{} {:>4} {}", t.status().mark(), t.id(), t.what())))
+                .collect();
+
+            let todos = List::new(todos)
+                .block(border(Pane::Todos, String::from("Todos")))
+                .highlight_style(highlight);
+            let tasks = List::new(tasks)
+                .block(border(Pane::Tasks, title))
+                .highlight_style(highlight);
+
+            frame.render_stateful_widget(todos, columns[0], &mut self.todo_state);
+            frame.render_stateful_widget(tasks, columns[1], &mut self.task_state);
+            frame.render_widget(Paragraph::new(Line::from(self.status_line())), rows[1]);
+        }
+    }
 
-                let statement =
-                    format!(
-                    "UPDATE {} SET description = {}, updated_at = CURRENT_TIMESTAMP WHERE id = {};",
-                    Self::table_name(), description, obj.id()
-                );
+    fn event_loop(terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        let mut state = TuiState::new();
 
-                let res = DB.lock().unwrap().exec_sttmt(&statement);
+        loop {
+            terminal.draw(|frame| state.draw(frame))?;
 
-                if let Err(e) = res {
-                    return Err(InternalError::new(&e.to_string()));
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !state.handle_key(key) {
+                    return Ok(());
                 }
             }
-
-            Self::find(*obj.id())
         }
+    }
 
-        fn add(obj: Self::ObjType) -> Result<Todo, InternalError> {
-            if !Self::is_table_initialized() {
-                return Err(InternalError::table_not_initialized(&Self::table_name()));
-            } else if let Ok(todo) = Self::find(*obj.id()) {
-                let details = format!(
-                    "todo with id = {}, is already in use in the table",
-                    obj.id()
-                );
-                return Err(InternalError::new(&details));
-            } else {
-                let name = obj.name();
-                let description = obj
-                    .description()
-                    .map(|desc| format!("'{}'", desc))
-                    .unwrap_or(String::from("NULL"));
+    /// Opens the full-screen terminal interface and
+    /// blocks until the user closes it.
+    pub fn run_tui() -> Result<(), InternalError> {
+        let mut terminal = ratatui::try_init().map_err(|e| InternalError::new(&e.to_string()))?;
+        let res = event_loop(&mut terminal);
+        ratatui::restore();
+        res.map_err(|e| InternalError::new(&e.to_string()))
+    }
+}
 
-                let statement = format!(
-                    "INSERT INTO {}(name, description) VALUES ('{}', {});",
-                    Self::table_name(),
-                    name,
-                    description
-                );
+mod config {
+    use super::core::*;
+    use super::hooks::Hooks;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+
+    /// Environment variable with the path of the config file.
+    pub const CONFIG_ENV: &str = "TODO_CONFIG";
+
+    /// Prefix of the environment variables overriding the settings,
+    /// e.g. `TODO_DATE_FORMAT` overrides `date-format`.
+    pub const ENV_PREFIX: &str = "TODO_";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// Where the todos are stored.
+    pub enum Backend {
+        Sqlite,
+        Files,
+    }
 
-                // Using it this way will make it to only be unlocked
-                // when the variable below is out of scope.
-                let mut db = DB.lock().unwrap();
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// How the listings are printed.
+    pub enum OutputFormat {
+        Text,
+        Json,
+    }
 
-                let res = db.exec_sttmt(&statement);
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// When the output is coloured, `Auto` colours it only
+    /// on terminals and when `NO_COLOR` isn't set.
+    pub enum ColorChoice {
+        Auto,
+        Always,
+        Never,
+    }
 
-                if let Err(e) = res {
-                    return Err(InternalError::new(&e.to_string()));
-                }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    /// The order the tasks of a todo are listed in.
+    pub enum SortOrder {
+        Id,
+        /// The soonest due first, the ones without a due day last.
+        Due,
+        /// The highest priority first, the ones without a priority last.
+        Priority,
+        Created,
+        /// The open tasks first, in the order of `Status::name`.
+        Status,
+    }
 
-                let query = format!("
-                    SELECT id, name, description, created_at, updated_at FROM {} WHERE id = (SELECT MAX(id) FROM {});",
-                    Self::table_name(), Self::table_name()
-                );
+    impl Backend {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Self::Sqlite => "sqlite",
+                Self::Files => "files",
+            }
+        }
+    }
 
-                let todo: Option<Todo> = if let Ok(mut cursor) = db.select_query(&query) {
-                    cursor.next().unwrap().map(|t: &[sqlite::Value]| {
-                        let id: IdType = t[0].as_integer().unwrap() as IdType;
-                        let name = t[1].as_string().unwrap();
-                        let description = t[2].as_string().map(|desc| String::from(desc));
-                        let created_at = t[3].as_string().unwrap();
-                        let updated_at = t[4].as_string().unwrap();
-                        Todo::new(
-                            id,
-                            name.into(),
-                            description,
-                            created_at.into(),
-                            updated_at.into(),
-                        )
-                    })
-                } else {
-                    None
-                };
+    impl FromStr for Backend {
+        type Err = InternalError;
 
-                todo.ok_or(InternalError::new(
-                    "Could not get the todo after adding it to the database.",
-                ))
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value {
+                "sqlite" => Ok(Self::Sqlite),
+                "files" => Ok(Self::Files),
+                _ => Err(invalid("backend", value, "sqlite or files")),
             }
         }
+    }
+
+    impl FromStr for OutputFormat {
+        type Err = InternalError;
 
-        fn delete(id: IdType) -> Result<(), InternalError> {
-            let res = Self::find(id) ? ;
-            let statement = format!("DELETE FROM {} WHERE id = {};", Self::table_name(), id);
-            let res = DB.lock().unwrap().exec_sttmt(&statement);
-            res.map_err(|e| InternalError::new(&e.to_string()))
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value {
+                "text" => Ok(Self::Text),
+                "json" => Ok(Self::Json),
+                _ => Err(invalid("format", value, "text or json")),
+            }
         }
     }
 
-    impl Todo {
-        pub fn all() -> Vec<Todo> {
-            TodoModel::all()
+    impl FromStr for ColorChoice {
+        type Err = InternalError;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value {
+                "auto" => Ok(Self::Auto),
+                "always" => Ok(Self::Always),
+                "never" => Ok(Self::Never),
+                _ => Err(invalid("color", value, "auto, always or never")),
+            }
         }
+    }
 
-        pub fn add(name: String, description: Option<String>) -> Result<Todo, InternalError> {
-            let id: IdType = 0;
-            let created_at = "CURRENT_TIMESTAMP";
-            let updated_at = "CURRENT_TIMESTAMP";
+    impl FromStr for SortOrder {
+        type Err = InternalError;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value {
+                "id" => Ok(Self::Id),
+                "due" => Ok(Self::Due),
+                "priority" => Ok(Self::Priority),
+                "created" => Ok(Self::Created),
+                "status" => Ok(Self::Status),
+                _ => Err(invalid("sort", value, "id, due, priority, created or status")),
+            }
+        }
+    }
 
-            let todo = Todo::new(id, name, description, created_at.into(), updated_at.into());
+    fn invalid(key: &str, value: &str, expected: &str) -> InternalError {
+        InternalError::invalid_input(&format!("'{}' is not a valid {}, use {}", value, key, expected))
+    }
+
+    impl SortOrder {
+        /// Sorts the tasks, the ties are broken by id.
+        pub fn sort(&self, tasks: &mut [Task]) {
+            let rank = |task: &Task| {
+                ["todo", "in_progress", "blocked", "done", "cancelled"]
+                    .iter()
+                    .position(|name| *name == task.status().name())
+            };
+
+            tasks.sort_by(|a, b| {
+                let order = match self {
+                    Self::Id => std::cmp::Ordering::Equal,
+                    Self::Due => (a.due().is_none(), a.due()).cmp(&(b.due().is_none(), b.due())),
+                    Self::Priority => b.priority().cmp(&a.priority()),
+                    Self::Created => a.created_at().cmp(b.created_at()),
+                    Self::Status => rank(a).cmp(&rank(b)),
+                };
 
-            TodoModel::add(todo)
+                order.then_with(|| a.id().cmp(b.id()))
+            });
         }
+    }
 
-        pub fn update(
-            id: IdType,
-            new_name: Option<String>,
-            new_description: Option<String>,
-        ) -> Result<Todo, InternalError> {
-            let mut todo = TodoModel::find(id) ? ;
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    /// A separate set of todos, kept in its own database or directory,
+    /// by default in the `workspaces` directory of `Config::data_dir`.
+    pub struct Workspace {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub database: Option<PathBuf>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dir: Option<PathBuf>,
+    }
 
-            if let Some(name) = new_name {
-                todo.set_name(&name);
-            }
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+    /// The settings of the application, read from a toml file
+    /// like:
+    ///
+    /// ```toml
+    /// database = "/home/me/todo.db"
+    /// date-format = "%d/%m/%Y %H:%M"
+    /// sort = "priority"
+    /// workspace = "work"
+    ///
+    /// [aliases]
+    /// t = "today"
+    ///
+    /// [hooks]
+    /// on-complete = "/home/me/.config/todo/notify.sh"
+    ///
+    /// [workspaces.work]
+    /// [workspaces.client]
+    /// database = "/home/me/client.db"
+    /// ```
+    ///
+    /// The settings left out keep their default. The environment
+    /// variables, e.g. `TODO_SORT`, override the file, see `Config::apply_env`.
+    pub struct Config {
+        pub backend: Backend,
+        /// The sqlite database, `:memory:` keeps nothing.
+        pub database: PathBuf,
+        /// The directory of the files backend.
+        pub dir: PathBuf,
+        pub format: OutputFormat,
+        /// The format of the dates shown, as in `strftime`.
+        pub date_format: String,
+        pub color: ColorChoice,
+        pub sort: SortOrder,
+        /// Words expanded to other arguments of the command line.
+        pub aliases: BTreeMap<String, String>,
+        pub hooks: Hooks,
+        /// The workspace in use, whose database and directory are used
+        /// instead of the ones above. The todos outside of any are the
+        /// default ones.
+        pub workspace: Option<String>,
+        pub workspaces: BTreeMap<String, Workspace>,
+    }
 
-            if let Some(description) = new_description {
-                todo.set_description(&description);
+    impl Default for Config {
+        fn default() -> Self {
+            let data = Config::data_dir();
+
+            Self {
+                backend: Backend::Sqlite,
+                database: data.join("todo.db"),
+                dir: data,
+                format: OutputFormat::Text,
+                date_format: String::from(DATE_FORMAT),
+                color: ColorChoice::Auto,
+                sort: SortOrder::Id,
+                aliases: BTreeMap::new(),
+                hooks: Hooks::default(),
+                workspace: None,
+                workspaces: BTreeMap::new(),
             }
+        }
+    }
 
-            TodoModel::update(todo)
+    impl Config {
+        /// The settings that can be given by `Config::set`, also
+        /// through the environment and the command line.
+        pub const KEYS: [&'static str; 8] =
+            ["workspace", "backend", "database", "dir", "format", "date-format", "color", "sort"];
+
+        /// The name of the todos outside of any workspace.
+        pub const DEFAULT_WORKSPACE: &'static str = "default";
+
+        /// The directory of the data of the application, following XDG.
+        pub fn data_dir() -> PathBuf {
+            match (env::var_os("XDG_DATA_HOME"), env::var_os("HOME")) {
+                (Some(data), _) => PathBuf::from(data).join("todo"),
+                (None, Some(home)) => PathBuf::from(home).join(".local/share/todo"),
+                (None, None) => PathBuf::from("todos"),
+            }
         }
 
-        pub fn find(id: IdType) -> Result<Todo, InternalError> {
-            TodoModel::find(id)
+        /// Where the config file is looked for when no other is given,
+        /// following XDG.
+        pub fn default_path() -> Option<PathBuf> {
+            match (env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME")) {
+                (Some(config), _) => Some(PathBuf::from(config).join("todo/config.toml")),
+                (None, Some(home)) => Some(PathBuf::from(home).join(".config/todo/config.toml")),
+                (None, None) => None,
+            }
         }
 
-        pub fn delete(id: IdType) -> Result<(), InternalError> {
-            TodoModel::delete(id)
+        /// Parses the contents of a config file.
+        pub fn parse(text: &str) -> Result<Self, InternalError> {
+            let mut config: Self = toml::from_str(text)
+                .map_err(|e| InternalError::invalid_input(&format!("invalid config: {}", e.message())))?;
+            check_date_format(&config.date_format)?;
+
+            for name in config.workspaces.keys() {
+                check_workspace_name(name)?;
+            }
+
+            if let Some(name) = config.workspace.take() {
+                config.use_workspace(&name)?;
+            }
+
+            Ok(config)
         }
 
-        pub fn tasks(&self) -> Vec<Task> {
-            let mut tasks: Vec<Task> = Vec::new();
+        /// Reads the config file at the given path, or the one at the
+        /// default path. Only the default file may be missing, in which
+        /// case the defaults are used.
+        pub fn load(path: Option<&Path>) -> Result<Self, InternalError> {
+            let (path, required) = match path {
+                Some(path) => (path.to_path_buf(), true),
+                None => match Self::default_path() {
+                    Some(path) => (path, false),
+                    None => return Ok(Self::default()),
+                },
+            };
 
-            if TodoModel::is_table_initialized() && TaskModel::is_table_initialized() {
-                let query = format!(
-                    "SELECT id, what, todo_id, created_at, updated_at, completed_at FROM {} WHERE todo_id = {}",
-                    TaskModel::table_name(), self.id()
-                );
+            match fs::read_to_string(&path) {
+                Ok(text) => Self::parse(&text),
+                Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+                Err(e) => Err(InternalError::new(&format!("could not read {}: {}", path.display(), e))),
+            }
+        }
 
-                if let Ok(mut cursor) = DB.lock().unwrap().select_query(&query) {
-                    while let Some(result) = cursor.next().unwrap() {
-                        let id: IdType = result[0].as_integer().unwrap() as IdType;
-                        let what = result[1].as_string().unwrap();
-                        let todo_id = result[2].as_integer().unwrap() as IdType;
-                        let created_at = result[3].as_string().unwrap();
-                        let updated_at = result[4].as_string().unwrap();
-                        let status = result[5]
-                            .as_string()
-                            .map(|date| Status::Done(date.into()))
-                            .unwrap_or(Status::Todo);
-
-                        let task = Task::new(id, todo_id, what, created_at, updated_at, status);
-
-                        tasks.push(task);
-                    }
+        /// Changes one of the `Config::KEYS`, checking the value.
+        pub fn set(&mut self, key: &str, value: &str) -> Result<(), InternalError> {
+            match key {
+                "workspace" => self.use_workspace(value)?,
+                "backend" => self.backend = value.parse()?,
+                "database" => self.database = PathBuf::from(value),
+                "dir" => self.dir = PathBuf::from(value),
+                "format" => self.format = value.parse()?,
+                "date-format" => {
+                    check_date_format(value)?;
+                    self.date_format = String::from(value);
                 }
+                "color" => self.color = value.parse()?,
+                "sort" => self.sort = value.parse()?,
+                _ => return Err(InternalError::invalid_input(&format!("unknown setting '{}'", key))),
             }
 
-            return tasks;
+            Ok(())
         }
 
-        pub fn init_table() -> Result<(), sqlite::Error> {
-            TodoModel::init_table() ? ;
+        /// Overrides the settings given by the variables, named after the
+        /// `Config::KEYS` in upper case with `ENV_PREFIX`, e.g. `TODO_DATE_FORMAT`.
+        /// The other variables are ignored.
+        pub fn apply_env<I>(&mut self, vars: I) -> Result<(), InternalError>
+        where
+            I: IntoIterator<Item = (String, String)>,
+        {
+            let vars: BTreeMap<String, String> = vars
+                .into_iter()
+                .filter_map(|(name, value)| {
+                    let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase().replace('_', "-");
+                    Some((key, value))
+                })
+                .collect();
+
+            for key in Self::KEYS {
+                if let Some(value) = vars.get(key) {
+                    self.set(key, value)?;
+                }
+            }
+
             Ok(())
         }
-    }
 
-    impl DatabaseConnectorTrait for TaskModel {
-        fn table_name() -> &'static str {
-            "tasks"
+        /// The database of a workspace.
+        pub fn workspace_database(&self, name: &str) -> PathBuf {
+            match self.workspaces.get(name).and_then(|workspace| workspace.database.clone()) {
+                Some(database) => database,
+                None => Self::data_dir().join("workspaces").join(format!("{}.db", name)),
+            }
         }
 
-        fn init_table() -> Result<(), sqlite::Error> {
-            if !Self::is_table_initialized() {
-                let sttmt = format!(
-                    "{}(
-                    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
-                    what TEXT NOT NULL,
-                    todo_id INTEGER NOT NULL,
-                    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                    completed_at DATETIME,
-                    FOREIGN KEY (todo_id) REFERENCES Todos(todo_id) ON DELETE SET NULL);",
-                    Self::table_name()
-                );
+        /// The directory of the files backend of a workspace.
+        pub fn workspace_dir(&self, name: &str) -> PathBuf {
+            match self.workspaces.get(name).and_then(|workspace| workspace.dir.clone()) {
+                Some(dir) => dir,
+                None => Self::data_dir().join("workspaces").join(name),
+            }
+        }
 
-                DB.lock().unwrap().create_table(&sttmt)
-            } else {
-                Ok(())
+        /// The files of a workspace made by the application for the
+        /// backend in use: its database, with the other files sqlite
+        /// writes next to it, or its directory. A database or directory
+        /// given in the config isn't one of them.
+        pub fn workspace_files(&self, name: &str) -> Vec<PathBuf> {
+            let workspace = self.workspaces.get(name).cloned().unwrap_or_default();
+
+            match self.backend {
+                Backend::Sqlite if workspace.database.is_none() => {
+                    let database = self.workspace_database(name);
+                    ["", "-wal", "-shm"]
+                        .iter()
+                        .map(|suffix| {
+                            let mut path = database.clone().into_os_string();
+                            path.push(suffix);
+                            PathBuf::from(path)
+                        })
+                        .collect()
+                }
+                Backend::Files if workspace.dir.is_none() => vec![self.workspace_dir(name)],
+                _ => Vec::new(),
             }
         }
-    }
 
-    impl BasicDataModelTrait for TaskModel {
-        type ObjType = Task;
+        /// The database used, the one of the workspace in use if any.
+        pub fn active_database(&self) -> PathBuf {
+            match &self.workspace {
+                Some(name) => self.workspace_database(name),
+                None => self.database.clone(),
+            }
+        }
 
-        fn all() -> Vec<Self::ObjType> {
-            let mut tasks: Vec<Task> = Vec::new();
+        /// The directory of the files backend used, the one of the workspace in use if any.
+        pub fn active_dir(&self) -> PathBuf {
+            match &self.workspace {
+                Some(name) => self.workspace_dir(name),
+                None => self.dir.clone(),
+            }
+        }
 
-            if Self::is_table_initialized() {
-                let query = format!(
-                    "SELECT id, what, todo_id, created_at, updated_at, completed_at FROM {};",
-                    Self::table_name()
-                );
+        /// Uses the todos of a workspace, or the default ones with
+        /// `Config::DEFAULT_WORKSPACE`. Only the workspaces of the
+        /// config can be used.
+        pub fn use_workspace(&mut self, name: &str) -> Result<(), InternalError> {
+            if name == Self::DEFAULT_WORKSPACE {
+                self.workspace = None;
+                return Ok(());
+            }
 
-                if let Ok(mut cursor) = DB.lock().unwrap().select_query(&query) {
-                    while let Some(mut result) = cursor.next().unwrap() {
-                        let mut result: &[sqlite::Value] = result;
+            if !self.workspaces.contains_key(name) {
+                return Err(InternalError::with_kind(
+                    ErrorKind::NotFound,
+                    &format!("there is no workspace named '{}'", name),
+                ));
+            }
 
-                        let id: IdType = result[0].as_integer().unwrap() as IdType;
-                        let what = result[1].as_string().unwrap();
-                        let todo_id = result[2].as_integer().unwrap() as IdType;
-                        let created_at = result[3].as_string().unwrap();
-                        let updated_at = result[4].as_string().unwrap();
-                        let status = result[5]
-                            .as_string()
-                            .map(|date| Status::Done(date.into()))
-                            .unwrap_or(Status::Todo);
+            self.workspace = Some(String::from(name));
+            Ok(())
+        }
 
-                        let task = Task::new(id, todo_id, what, created_at, updated_at, status);
+        /// Shows a date of the storage, see `DATE_FORMAT`, in the
+        /// configured format. Other texts are kept as they are.
+        pub fn format_date(&self, date: &str) -> String {
+            match chrono::NaiveDateTime::parse_from_str(date, DATE_FORMAT) {
+                Ok(date) => date.format(&self.date_format).to_string(),
+                Err(_) => String::from(date),
+            }
+        }
 
-                        tasks.push(task);
-                    }
-                }
+        /// Whether to colour an output, given if it is a terminal.
+        pub fn use_color(&self, terminal: bool) -> bool {
+            match self.color {
+                ColorChoice::Always => true,
+                ColorChoice::Never => false,
+                ColorChoice::Auto => terminal && env::var_os("NO_COLOR").is_none(),
             }
+        }
+    }
+
+    /// The names of the workspaces are used for their files.
+    fn check_workspace_name(name: &str) -> Result<(), InternalError> {
+        let valid = !name.is_empty()
+            && name != Config::DEFAULT_WORKSPACE
+            && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
 
-            return tasks;
+        if !valid {
+            return Err(InternalError::invalid_input(&format!("'{}' is not a valid name for a workspace", name)));
         }
 
-        fn find(id: IdType) -> Result<Self::ObjType, InternalError> {
-            if !Self::is_table_initialized() {
-                return Err(InternalError::table_not_initialized(&Self::table_name()));
-            }
+        Ok(())
+    }
 
-            let query = format!(
-                "SELECT id, what, todo_id, created_at, updated_at, completed_at FROM {} WHERE id = {};",
-                Self::table_name(), id
-            );
+    /// A config file changed by the application, e.g. to keep the
+    /// workspace in use. Its comments and layout are kept.
+    pub struct ConfigFile {
+        path: PathBuf,
+        document: toml_edit::DocumentMut,
+    }
 
-            let task: Option<Task> = if let Ok(mut cursor) = DB.lock().unwrap().select_query(&query)
-            {
-                cursor.next().unwrap().map(|t: &[sqlite::Value]| {
-                    let id: IdType = t[0].as_integer().unwrap() as IdType;
-                    let what = t[1].as_string().unwrap();
-                    let todo_id = t[2].as_integer().unwrap() as IdType;
-                    let created_at = t[3].as_string().unwrap();
-                    let updated_at = t[4].as_string().unwrap();
-                    let status = t[5]
-                        .as_string()
-                        .map(|date| Status::Done(date.into()))
-                        .unwrap_or(Status::Todo);
-                    Task::new(id, todo_id, what, created_at, updated_at, status)
-                })
-            } else {
-                None
+    impl ConfigFile {
+        /// Reads the file to be changed, which may not exist yet.
+        pub fn open(path: &Path) -> Result<Self, InternalError> {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(InternalError::new(&format!("could not read {}: {}", path.display(), e))),
             };
 
-            let details = format!("task with id = {} was not found in the database.", id);
-            task.ok_or(InternalError::new(&details))
+            let document = text
+                .parse()
+                .map_err(|e: toml_edit::TomlError| InternalError::invalid_input(&format!("invalid config: {}", e.message())))?;
+
+            Ok(Self {
+                path: path.to_path_buf(),
+                document,
+            })
         }
 
-        fn add(obj: Self::ObjType) -> Result<Self::ObjType, InternalError> {
-            if !Self::is_table_initialized() {
-                return Err(InternalError::table_not_initialized(&Self::table_name()));
-            } else if let Ok(task) = Self::find(*obj.id()) {
-                let details = format!(
-                    "task with id = {}, is already in use in the table",
-                    obj.id()
-                );
-                return Err(InternalError::new(&details));
-            } else {
-                let todo = TodoModel::find(*obj.todo_id()) ? ;
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
 
-                let todo_id = todo.id();
-                let what = obj.what();
-                let completed_at = match obj.status() {
-                    Status::Done(date) => date,
-                    Status::Todo => "NULL",
-                };
+        /// The settings of the file as it is now.
+        pub fn config(&self) -> Result<Config, InternalError> {
+            Config::parse(&self.document.to_string())
+        }
 
-                let statement = format!(
-                    "INSERT INTO {}(todo_id, what, completed_at) VALUES ({}, '{}', {});",
-                    Self::table_name(),
-                    todo_id,
-                    what,
-                    completed_at
-                );
+        /// Adds a workspace, with its own database if one is given.
+        pub fn create_workspace(&mut self, name: &str, database: Option<&Path>) -> Result<(), InternalError> {
+            check_workspace_name(name)?;
 
-                // Using it this way will make it to only be unlocked
-                // when the variable below is out of scope.
-                let mut db = DB.lock().unwrap();
+            if self.config()?.workspaces.contains_key(name) {
+                return Err(InternalError::with_kind(
+                    ErrorKind::AlreadyExists,
+                    &format!("the workspace '{}' already exists", name),
+                ));
+            }
 
-                let res = db.exec_sttmt(&statement);
+            let workspaces = self.document.entry("workspaces").or_insert_with(|| {
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                toml_edit::Item::Table(table)
+            });
 
-                if let Err(e) = res {
-                    return Err(InternalError::new(&e.to_string()));
-                }
+            let mut workspace = toml_edit::Table::new();
 
-                let query = format!("
-                    SELECT id, what, todo_id, created_at, updated_at, completed_at FROM {} WHERE id = (SELECT MAX(id) FROM {});",
-                    Self::table_name(), Self::table_name()
-                );
+            if let Some(database) = database {
+                workspace["database"] = toml_edit::value(database.to_string_lossy().as_ref());
+            }
 
-                let task: Option<Task> = if let Ok(mut cursor) = db.select_query(&query) {
-                    cursor.next().unwrap().map(|t: &[sqlite::Value]| {
-                        let id: IdType = t[0].as_integer().unwrap() as IdType;
-                        let what = t[1].as_string().unwrap();
-                        let todo_id = t[2].as_integer().unwrap() as IdType;
-                        let created_at = t[3].as_string().unwrap();
-                        let updated_at = t[4].as_string().unwrap();
-                        let status = t[5]
-                            .as_string()
-                            .map(|date| Status::Done(date.into()))
-                            .unwrap_or(Status::Todo);
-                        Task::new(id, todo_id, what, created_at, updated_at, status)
-                    })
-                } else {
-                    None
-                };
+            match workspaces.as_table_mut() {
+                Some(workspaces) => workspaces.insert(name, toml_edit::Item::Table(workspace)),
+                None => return Err(InternalError::invalid_input("invalid config: workspaces must be a table")),
+            };
 
-                task.ok_or(InternalError::new(
-                    "Could not get the task after adding it to the database.",
-                ))
-            }
+            Ok(())
         }
 
-        fn update(obj: Self::ObjType) -> Result<Self::ObjType, InternalError> {
-            if !Self::is_table_initialized() {
-                return Err(InternalError::table_not_initialized(&Self::table_name()));
-            }
+        /// Uses the todos of a workspace from now on, see `Config::use_workspace`.
+        pub fn use_workspace(&mut self, name: &str) -> Result<(), InternalError> {
+            self.config()?.use_workspace(name)?;
 
-            let task = Self::find(*obj.id()) ? ;
+            if name == Config::DEFAULT_WORKSPACE {
+                self.document.remove("workspace");
+            } else {
+                self.document["workspace"] = toml_edit::value(name);
+            }
 
-            // Here what can be changed currently are: what, and status (actually completed_at date).
+            Ok(())
+        }
 
-            if obj.what() != task.what() {
-                let statement = format!(
-                    "UPDATE {} SET what = '{}', updated_at = CURRENT_TIMESTAMP WHERE id = {};",
-                    Self::table_name(),
-                    obj.what(),
-                    obj.id()
-                );
+        /// Removes a workspace from the file, its todos are kept. The
+        /// workspace in use can't be removed.
+        pub fn delete_workspace(&mut self, name: &str) -> Result<(), InternalError> {
+            let config = self.config()?;
 
-                let res = DB.lock().unwrap().exec_sttmt(&statement);
+            if !config.workspaces.contains_key(name) {
+                return Err(InternalError::with_kind(
+                    ErrorKind::NotFound,
+                    &format!("there is no workspace named '{}'", name),
+                ));
+            }
 
-                if let Err(e) = res {
-                    return Err(InternalError::new(&e.to_string()));
-                }
+            if config.workspace.as_deref() == Some(name) {
+                return Err(InternalError::invalid_input(&format!(
+                    "the workspace '{}' is in use, use another one first",
+                    name
+                )));
             }
 
-            if obj.status() != task.status() {
-                let completed_at = match obj.status() {
-                    Status::Done(date) => format!("'{}'", date), // Note the single collon
-                    Status::Todo => String::from("NULL"),
-                };
+            if let Some(workspaces) = self.document.get_mut("workspaces").and_then(|item| item.as_table_mut()) {
+                workspaces.remove(name);
+            }
 
-                let statement = format!(
-                    "UPDATE {} SET completed_at = {}, updated_at = CURRENT_TIMESTAMP WHERE id = {};",
-                    Self::table_name(), completed_at, obj.id()
-                );
+            Ok(())
+        }
 
-                let res = DB.lock().unwrap().exec_sttmt(&statement);
+        /// Writes the changes to the file, creating its directory if needed.
+        pub fn save(&self) -> Result<(), InternalError> {
+            let failed = |e: std::io::Error| InternalError::new(&format!("could not write {}: {}", self.path.display(), e));
 
-                if let Err(e) = res {
-                    return Err(InternalError::new(&e.to_string()));
-                }
+            if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                fs::create_dir_all(parent).map_err(failed)?;
             }
 
-            Self::find(*obj.id())
+            fs::write(&self.path, self.document.to_string()).map_err(failed)
         }
+    }
 
-        fn delete(id: IdType) -> Result<(), InternalError> {
-            let task = Self::find(id) ? ;
-            let statement = format!(
-                "DELETE FROM {} WHERE id = {};",
-                Self::table_name(),
-                task.id()
-            );
-            let res = DB.lock().unwrap().exec_sttmt(&statement);
-            res.map_err(|e| InternalError::new(&e.to_string()))
+    fn check_date_format(format: &str) -> Result<(), InternalError> {
+        use chrono::format::{Item, StrftimeItems};
+
+        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(InternalError::invalid_input(&format!("'{}' is not a valid date format", format)));
         }
+
+        Ok(())
     }
+}
 
-    impl Task {
-        pub fn all() -> Vec<Task> {
-            TaskModel::all()
-        }
+mod hooks {
+    use super::core::*;
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use std::fmt::Display;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+    use std::thread;
 
-        pub fn find(id: IdType) -> Result<Task, InternalError> {
-            TaskModel::find(id)
-        }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    /// The changes of the todos and tasks the hooks are run on.
+    pub enum HookEvent {
+        Add,
+        Modify,
+        /// A task set as done, instead of `Modify`.
+        Complete,
+        /// A todo or task moved to the trash.
+        Delete,
+    }
 
-        pub fn delete(id: IdType) -> Result<(), InternalError> {
-            TaskModel::delete(id)
+    impl HookEvent {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Self::Add => "on-add",
+                Self::Modify => "on-modify",
+                Self::Complete => "on-complete",
+                Self::Delete => "on-delete",
+            }
         }
+    }
 
-        pub fn add(what: String, todo_id: IdType) -> Result<Task, InternalError> {
-            let id: IdType = 0; // Only a placeholder
-            let created_at = "CURRENT_TIMESTAMP";
-            let updated_at = "CURRENT_TIMESTAMP";
-            let status = Status::Todo;
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+    /// The scripts run when the todos and tasks change, see `Hooks::run`.
+    pub struct Hooks {
+        pub on_add: Option<PathBuf>,
+        pub on_modify: Option<PathBuf>,
+        pub on_complete: Option<PathBuf>,
+        pub on_delete: Option<PathBuf>,
+    }
 
-            let task = Task::new(id, todo_id, &what, created_at, updated_at, status);
+    #[derive(Serialize)]
+    /// What a hook reads from its standard input.
+    struct HookInput<'a, T> {
+        event: &'static str,
+        entity: &'a str,
+        old: Option<&'a T>,
+        new: &'a T,
+    }
 
-            TaskModel::add(task)
+    impl Hooks {
+        /// The script of an event, if any.
+        pub fn script(&self, event: HookEvent) -> Option<&PathBuf> {
+            match event {
+                HookEvent::Add => self.on_add.as_ref(),
+                HookEvent::Modify => self.on_modify.as_ref(),
+                HookEvent::Complete => self.on_complete.as_ref(),
+                HookEvent::Delete => self.on_delete.as_ref(),
+            }
         }
 
-        pub fn update(
-            id: IdType,
-            what_new: Option<String>,
-            new_status: Option<Status>,
-        ) -> Result<Task, InternalError> {
-            let mut task = TaskModel::find(id) ? ;
+        /// Runs the script of the event on a change of a todo or task,
+        /// the `entity`. The script reads the event, the entity and the
+        /// objects before and after the change as a json object from its
+        /// standard input, like
+        /// `{"event": "on-modify", "entity": "task", "old": {..}, "new": {..}}`.
+        ///
+        /// The change is refused when the script exits with an error, the
+        /// first line of its output telling why. Otherwise, the script may
+        /// print the new object changed, which is returned, the id can't be
+        /// changed though. The output of the delete hooks is ignored.
+        ///
+        /// Hooks run before the change is written, so the objects added
+        /// have no id yet, it is 0. Archiving a todo and restoring todos
+        /// and tasks from the trash are modifications, while purging the
+        /// trash, undoing and redoing run no hook.
+        pub fn run<T>(&self, event: HookEvent, entity: &str, old: Option<&T>, new: &T) -> Result<Option<T>, InternalError>
+        where
+            T: Serialize + DeserializeOwned,
+        {
+            let script = match self.script(event) {
+                Some(script) => script,
+                None => return Ok(None),
+            };
+            let failed = |e: &dyn Display| {
+                InternalError::new(&format!("the {} hook {} failed: {}", event.name(), script.display(), e))
+            };
 
-            if let Some(what) = what_new {
-                task.set_what(&what);
+            let input = serde_json::to_vec(&HookInput { event: event.name(), entity, old, new })
+                .map_err(|e| failed(&e))?;
+
+            let mut child = Command::new(script)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::inherit())
+                .spawn()
+                .map_err(|e| failed(&e))?;
+
+            // Written while the output is read, so that neither of them fills
+            // its pipe waiting for the other. The script may exit without
+            // reading it all.
+            let writer = child.stdin.take().map(|mut stdin| {
+                thread::spawn(move || {
+                    let _ = stdin.write_all(&input);
+                })
+            });
+
+            let output = child.wait_with_output().map_err(|e| failed(&e))?;
+
+            if let Some(writer) = writer {
+                let _ = writer.join();
             }
+            let stdout = String::from_utf8_lossy(&output.stdout);
 
-            if let Some(status) = new_status {
-                task.set_status(status);
+            if !output.status.success() {
+                let reason = stdout.lines().next().unwrap_or_default().trim();
+                let reason = if reason.is_empty() { output.status.to_string() } else { String::from(reason) };
+
+                return Err(InternalError::invalid_input(&format!(
+                    "the {} hook refused the change: {}",
+                    event.name(),
+                    reason
+                )));
             }
 
-            TaskModel::update(task)
-        }
+            if event == HookEvent::Delete || stdout.trim().is_empty() {
+                return Ok(None);
+            }
 
-        pub fn todo(&self) -> Result<Todo, InternalError> {
-            Todo::find(*self.todo_id())
-        }
+            let changed: serde_json::Value = serde_json::from_str(&stdout).map_err(|e| failed(&e))?;
+            let original = serde_json::to_value(new).map_err(|e| failed(&e))?;
 
-        pub fn init_table() -> Result<(), sqlite::Error> {
-            TodoModel::init_table() ? ;
-            TaskModel::init_table() ? ;
-            Ok(())
+            if changed.get("id") != original.get("id") {
+                return Err(failed(&"the id can't be changed"));
+            }
+
+            serde_json::from_value(changed).map(Some).map_err(|e| failed(&e))
         }
     }
 }
@@ -1,5 +1,18 @@
 use todo::prelude::*;
 
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap_complete::Shell;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Context, Editor, Helper, Highlighter, Hinter, Validator};
+
 //TODO: Update main.rs
 
 use clap::{
@@ -11,15 +24,90 @@ use clap::{
 /// Represents the todo application
 struct App {
     name: String,
-    version: String
+    version: String,
+    config: Config,
+    /// Where the config is written to, e.g. to keep the workspace in use.
+    config_path: Option<PathBuf>,
+    /// Set inside of the shell, which goes on after the errors of a command.
+    in_shell: bool,
 }
 
-fn parse_args(app_name: &str, app_version: &str) -> ArgMatches {
+/// Builds the grammar of the command line, shared by
+/// the arguments of the process and the interactive shell.
+fn build_command<'a>(app_name: &str, app_version: &'a str) -> Command<'a> {
     Command::new(app_name)
             .version(app_version)
             .author("Anaxímeno Brito")
             .about("Command-Line Todo Application.")
-            .subcommand(
+            .after_help("Other commands run the plugins found on PATH, e.g. `todo foo` runs todo-foo.")
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .help("The config file, instead of config.toml in the XDG config directory")
+                    .takes_value(true)
+                    .global(true)
+                    .value_name("FILE")
+            ).arg(
+                Arg::new("workspace")
+                    .long("workspace")
+                    .help("The workspace whose todos are used, instead of the one in the config")
+                    .takes_value(true)
+                    .global(true)
+                    .value_name("NAME")
+            ).arg(
+                Arg::new("backend")
+                    .long("backend")
+                    .help("Where the todos are stored, sqlite by default")
+                    .takes_value(true)
+                    .global(true)
+                    .possible_values(["sqlite", "files"])
+            ).arg(
+                Arg::new("database")
+                    .long("database")
+                    .help("The database of the sqlite backend")
+                    .takes_value(true)
+                    .global(true)
+                    .value_name("FILE")
+            ).arg(
+                Arg::new("dir")
+                    .long("dir")
+                    .help("The directory of the files backend")
+                    .takes_value(true)
+                    .global(true)
+            ).arg(
+                Arg::new("output-format")
+                    .long("output-format")
+                    .help("How the listings are printed")
+                    .takes_value(true)
+                    .global(true)
+                    .possible_values(["text", "json"])
+            ).arg(
+                Arg::new("date-format")
+                    .long("date-format")
+                    .help("The format of the dates shown, e.g. %d/%m/%Y")
+                    .takes_value(true)
+                    .global(true)
+            ).arg(
+                Arg::new("color")
+                    .long("color")
+                    .help("When to colour the output")
+                    .takes_value(true)
+                    .global(true)
+                    .possible_values(["auto", "always", "never"])
+            ).arg(
+                Arg::new("sort")
+                    .long("sort")
+                    .help("The order the tasks of a todo are listed in")
+                    .takes_value(true)
+                    .global(true)
+                    .possible_values(["id", "due", "priority", "created", "status"])
+            ).arg(
+                Arg::new("id")
+                    .help("The todo the command acts on")
+                    .takes_value(true)
+                    .value_name(TODO_ID)
+                    .value_parser(clap::value_parser!(IdType))
+            ).subcommand(
                 Command::new("add")
                     .arg(
                         Arg::new("name")
@@ -45,13 +133,676 @@ fn parse_args(app_name: &str, app_version: &str) -> ArgMatches {
                     )
             ).subcommand(
                 Command::new("done")
-            ).get_matches()
+            ).subcommand(
+                Command::new("export")
+                    .about("Exports the tasks, joined with their todos")
+                    .arg(
+                        Arg::new("format")
+                            .short('f')
+                            .long("format")
+                            .help("The format of the exported file")
+                            .takes_value(true)
+                            .possible_values(["csv"])
+                            .default_value("csv")
+                    )
+                    .arg(
+                        Arg::new("columns")
+                            .short('c')
+                            .long("columns")
+                            .help("Comma separated list of the columns to export")
+                            .takes_value(true)
+                            .use_value_delimiter(true)
+                            .possible_values(CsvColumn::ALL.map(|c| c.name()))
+                    )
+                    .arg(
+                        Arg::new("output")
+                            .short('o')
+                            .long("output")
+                            .help("The file to write to, defaults to the standard output")
+                            .takes_value(true)
+                    )
+            ).subcommand(
+                Command::new("import")
+                    .about("Imports todos and tasks from a file")
+                    .arg(
+                        Arg::new("file")
+                            .help("The file to read from, use '-' for the standard input")
+                            .takes_value(true)
+                            .required(true)
+                    )
+                    .arg(
+                        Arg::new("format")
+                            .short('f')
+                            .long("format")
+                            .help("The format of the imported file")
+                            .takes_value(true)
+                            .possible_values(["csv"])
+                            .default_value("csv")
+                    )
+            ).subcommand(
+                Command::new("serve")
+                    .about("Serves the todos and tasks as a JSON REST API")
+                    .arg(
+                        Arg::new("bind")
+                            .short('b')
+                            .long("bind")
+                            .help("The address to listen on")
+                            .takes_value(true)
+                            .default_value("127.0.0.1:8080")
+                    )
+            ).subcommand(
+                Command::new("tui")
+                    .about("Opens the interactive terminal interface")
+            ).subcommand(
+                Command::new("shell")
+                    .about("Opens an interactive shell accepting the same commands")
+            ).subcommand(
+                Command::new("list")
+                    .about("Lists the todos with their progress, or the tasks of the todo given before the command")
+                    .long_about("Lists the todos with their progress, or the tasks of the todo given before the command. \
+                                 With filters, lists the tasks matching them, of every todo if none is given.")
+                    .args(filter_args())
+            ).subcommand(
+                Command::new("move")
+                    .about("Moves the tasks of the todo given before the command, or the ones matching the filters, to another todo")
+                    .arg(to_arg("The todo the tasks are moved to"))
+                    .args(filter_args())
+            ).subcommand(
+                Command::new("view")
+                    .about("Runs a saved list of filters, or manages them")
+                    .long_about("Runs a saved list of filters, or manages them. The views can't be named add, list \
+                                 or remove, which are the commands managing them.")
+                    .args_conflicts_with_subcommands(true)
+                    .arg(
+                        Arg::new("name")
+                            .help("The view to run, with the todo given before the command if any")
+                            .takes_value(true)
+                    ).subcommand(
+                        Command::new("add")
+                            .about("Saves filters of the list command under a name")
+                            .long_about("Saves filters of the list command under a name. The filters are the ones \
+                                         of the list command: --status, --text, --priority and --older-than. The \
+                                         tasks have no tags, so there is no filter on them.")
+                            .allow_hyphen_values(true)
+                            .arg(
+                                Arg::new("name")
+                                    .help("The name of the view, other than add, list and remove")
+                                    .takes_value(true)
+                                    .required(true)
+                            ).arg(
+                                Arg::new("filters")
+                                    .help("The filters, e.g. \"--status todo --older-than 30d\"")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .allow_hyphen_values(true)
+                            )
+                    ).subcommand(
+                        Command::new("list")
+                            .about("Lists the saved views")
+                    ).subcommand(
+                        Command::new("remove")
+                            .about("Deletes a saved view")
+                            .arg(
+                                Arg::new("name")
+                                    .help("The name of the view")
+                                    .takes_value(true)
+                                    .required(true)
+                            )
+                    )
+            ).subcommand(
+                Command::new("set")
+                    .about("Changes the todo given before the command")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("complete")
+                            .about("Sets all the tasks of the todo as done")
+                    )
+            ).subcommand(
+                Command::new("task")
+                    .about("Changes the status of a task, or tracks the time spent on it")
+                    .arg(
+                        Arg::new("task_id")
+                            .help("The task to change")
+                            .takes_value(true)
+                            .required(true)
+                            .value_name(TASK_ID)
+                            .value_parser(clap::value_parser!(IdType))
+                    )
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("start")
+                            .about("Sets the task as in progress and starts timing it")
+                    ).subcommand(
+                        Command::new("stop")
+                            .about("Stops timing the task")
+                    ).subcommand(
+                        Command::new("log")
+                            .about("Records time spent on the task, e.g. 1h30m")
+                            .arg(
+                                Arg::new("duration")
+                                    .help("The time spent, e.g. 45m or 1h30m")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .value_parser(parse_age)
+                            )
+                    ).subcommand(
+                        Command::new("estimate")
+                            .about("Sets the effort the task is expected to take, in points or hours")
+                            .arg(
+                                Arg::new("effort")
+                                    .help("The estimated effort, e.g. 3 or 0.5")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .value_parser(clap::value_parser!(f64))
+                            )
+                    ).subcommand(
+                        Command::new("due")
+                            .about("Sets the day the task is due, or clears it with none")
+                            .arg(
+                                Arg::new("day")
+                                    .help("The day the task is due, or none")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .value_name("YYYY-MM-DD")
+                            )
+                    ).subcommand(
+                        Command::new("priority")
+                            .about("Sets the priority of the task, or clears it with none")
+                            .arg(
+                                Arg::new("priority")
+                                    .help("The priority of the task")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .possible_values(["low", "medium", "high", "none"])
+                            )
+                    ).subcommand(
+                        Command::new("block")
+                            .about("Sets the task as blocked")
+                            .arg(
+                                Arg::new("reason")
+                                    .help("Why the task is blocked")
+                                    .takes_value(true)
+                                    .required(true)
+                            )
+                    ).subcommand(
+                        Command::new("done")
+                            .about("Sets the task as done")
+                    ).subcommand(
+                        Command::new("cancel")
+                            .about("Sets the task as cancelled")
+                    ).subcommand(
+                        Command::new("reopen")
+                            .about("Sets the task back to todo")
+                    ).subcommand(
+                        Command::new("move")
+                            .about("Moves the task to another todo")
+                            .arg(to_arg("The todo the task is moved to"))
+                    ).subcommand(
+                        Command::new("copy")
+                            .about("Adds a copy of the task, as a new task to do, to a todo")
+                            .arg(to_arg("The todo the copy is added to"))
+                    )
+            ).subcommand(
+                Command::new("today")
+                    .about("Lists the tasks to do due today")
+            ).subcommand(
+                Command::new("week")
+                    .about("Lists the tasks to do due in the next seven days")
+            ).subcommand(
+                Command::new("overdue")
+                    .about("Lists the tasks to do due before today")
+            ).subcommand(
+                Command::new("workspace")
+                    .about("Manages the workspaces, separate sets of todos")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("create")
+                            .about("Adds a workspace to the config")
+                            .arg(
+                                Arg::new("name")
+                                    .help("The name of the workspace")
+                                    .takes_value(true)
+                                    .required(true)
+                            ).arg(
+                                Arg::new("database")
+                                    .long("database")
+                                    .help("The database of the workspace, one in the data directory by default")
+                                    .takes_value(true)
+                                    .value_name("FILE")
+                            )
+                    ).subcommand(
+                        Command::new("list")
+                            .about("Lists the workspaces, marking the one in use")
+                    ).subcommand(
+                        Command::new("use")
+                            .about("Uses the todos of a workspace from now on, or the default ones")
+                            .arg(
+                                Arg::new("name")
+                                    .help("The name of the workspace, or default")
+                                    .takes_value(true)
+                                    .required(true)
+                            )
+                    ).subcommand(
+                        Command::new("delete")
+                            .about("Removes a workspace from the config, keeping its todos")
+                            .arg(
+                                Arg::new("name")
+                                    .help("The name of the workspace")
+                                    .takes_value(true)
+                                    .required(true)
+                            ).arg(
+                                Arg::new("purge")
+                                    .long("purge")
+                                    .help("Deletes the todos of the workspace too, unless they are in a database or directory given in the config")
+                            )
+                    )
+            ).subcommand(
+                Command::new("report")
+                    .about("Shows reports about the work done")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("timesheet")
+                            .about("Shows the time spent on each task per day")
+                            .arg(
+                                Arg::new("from")
+                                    .long("from")
+                                    .help("The first day of the report, a week ago by default")
+                                    .takes_value(true)
+                                    .value_name("YYYY-MM-DD")
+                            ).arg(
+                                Arg::new("to")
+                                    .long("to")
+                                    .help("The last day of the report, today by default")
+                                    .takes_value(true)
+                                    .value_name("YYYY-MM-DD")
+                            )
+                    ).subcommand(
+                        Command::new("velocity")
+                            .about("Shows the tasks done and their estimated effort per week")
+                            .arg(
+                                Arg::new("weeks")
+                                    .long("weeks")
+                                    .help("How many weeks to show")
+                                    .takes_value(true)
+                                    .default_value("8")
+                                    .value_parser(clap::value_parser!(u64).range(1..))
+                            )
+                    )
+            ).subcommand(
+                Command::new("stats")
+                    .about("Shows statistics about the tasks created and completed")
+                    .arg(
+                        Arg::new("by")
+                            .long("by")
+                            .help("The period the tasks are counted by")
+                            .takes_value(true)
+                            .possible_values(["day", "week"])
+                            .default_value("day")
+                    ).arg(
+                        Arg::new("oldest")
+                            .long("oldest")
+                            .help("How many of the oldest open tasks to show")
+                            .takes_value(true)
+                            .default_value("5")
+                            .value_parser(clap::value_parser!(usize))
+                    ).arg(
+                        Arg::new("burndown")
+                            .long("burndown")
+                            .help("Draws the burndown of each todo, or of the one given before the command")
+                    ).arg(
+                        Arg::new("json")
+                            .long("json")
+                            .help("Prints the statistics as json")
+                    )
+            ).subcommand(
+                Command::new("drop")
+                    .about("Deletes the todo given before the command")
+            ).subcommand(
+                Command::new("archive")
+                    .about("Archives the todo given before the command, or lists the archived ones")
+            ).subcommand(
+                Command::new("unarchive")
+                    .about("Brings back the archived todo given before the command")
+            ).subcommand(
+                Command::new("trash")
+                    .about("Manages the deleted todos and tasks")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("list")
+                            .about("Lists what is in the trash")
+                    ).subcommand(
+                        Command::new("restore")
+                            .about("Brings a todo, or a task, back from the trash")
+                            .arg(
+                                Arg::new("id")
+                                    .help("The id of the todo, or of the task with --task")
+                                    .takes_value(true)
+                                    .required(true)
+                                    .value_parser(clap::value_parser!(IdType))
+                            )
+                            .arg(
+                                Arg::new("task")
+                                    .long("task")
+                                    .help("Restores a task instead of a todo")
+                            )
+                    ).subcommand(
+                        Command::new("purge")
+                            .about("Deletes for good what is in the trash")
+                            .arg(
+                                Arg::new("older-than")
+                                    .long("older-than")
+                                    .help("Only what was deleted before this long ago, e.g. 30d, 12h or 2w")
+                                    .takes_value(true)
+                                    .value_parser(parse_age)
+                            )
+                    )
+            ).subcommand(
+                Command::new("undo")
+                    .about("Undoes the last changes")
+                    .arg(
+                        Arg::new("count")
+                            .help("How many changes to undo")
+                            .takes_value(true)
+                            .default_value("1")
+                            .value_parser(clap::value_parser!(usize))
+                    )
+            ).subcommand(
+                Command::new("redo")
+                    .about("Redoes the last changes undone")
+                    .arg(
+                        Arg::new("count")
+                            .help("How many changes to redo")
+                            .takes_value(true)
+                            .default_value("1")
+                            .value_parser(clap::value_parser!(usize))
+                    )
+            ).subcommand(
+                Command::new("log")
+                    .about("Shows the history of changes")
+                    .arg(
+                        Arg::new("id")
+                            .help("Only the changes of this todo and of its tasks")
+                            .takes_value(true)
+                            .value_name(TODO_ID)
+                            .value_parser(clap::value_parser!(IdType))
+                    )
+            ).subcommand(
+                Command::new("completions")
+                    .about("Generates the completion script for a shell")
+                    .arg(
+                        Arg::new("shell")
+                            .help("The shell to generate the script for")
+                            .takes_value(true)
+                            .required(true)
+                            .possible_values(["bash", "zsh", "fish"])
+                    )
+            )
+}
+
+/// The hidden command used by the completion scripts to ask for ids.
+/// It is kept out of `build_command`, so it isn't part of the scripts.
+fn complete_command<'a>() -> Command<'a> {
+    Command::new("__complete")
+        .hide(true)
+        .arg(
+            Arg::new("words")
+                .takes_value(true)
+                .multiple_values(true)
+                .allow_hyphen_values(true)
+                .last(true)
+        )
+}
+
+/// Parses an age such as `30d`, `12h`, `2w` or `1d12h`.
+fn parse_age(value: &str) -> Result<Duration, String> {
+    let mut seconds = 0;
+    let mut number = String::new();
+
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(format!("unknown unit '{}', use s, m, h, d or w", c)),
+        };
+
+        if number.is_empty() {
+            return Err(format!("missing number before '{}'", c));
+        }
+
+        seconds = number
+            .parse::<u64>()
+            .ok()
+            .and_then(|amount| amount.checked_mul(unit))
+            .and_then(|amount| amount.checked_add(seconds))
+            .ok_or_else(|| format!("the age '{}' is too long", value.trim()))?;
+        number.clear();
+    }
+
+    if !number.is_empty() || value.trim().is_empty() {
+        return Err(String::from("expected an amount followed by its unit, e.g. 30d"));
+    }
+
+    Ok(Duration::from_secs(seconds))
+}
+
+/// The filters of the tasks, given to the list and move commands.
+fn filter_args<'a>() -> [Arg<'a>; 4] {
+    [
+        Arg::new("status")
+            .long("status")
+            .help("Only the tasks with this status")
+            .takes_value(true)
+            .possible_values(["todo", "in_progress", "blocked", "done", "cancelled", "open", "closed"]),
+        Arg::new("text")
+            .long("text")
+            .help("Only the tasks containing this text")
+            .takes_value(true),
+        Arg::new("priority")
+            .long("priority")
+            .help("Only the tasks with this priority")
+            .takes_value(true)
+            .possible_values(["low", "medium", "high"]),
+        Arg::new("older-than")
+            .long("older-than")
+            .help("Only the tasks created before this long ago, e.g. 30d, 12h or 2w")
+            .takes_value(true)
+            .value_parser(parse_age),
+    ]
+}
+
+/// The todo the tasks are moved or copied to.
+fn to_arg(help: &str) -> Arg<'_> {
+    Arg::new("to")
+        .long("to")
+        .help(help)
+        .takes_value(true)
+        .required(true)
+        .value_name(TODO_ID)
+        .value_parser(clap::value_parser!(IdType))
+}
+
+/// The query of the filters given to the list command.
+fn task_query(todo_id: Option<IdType>, args: &ArgMatches) -> TaskQuery {
+    let mut query = TaskQuery {
+        todo_id,
+        text: args.get_one::<String>("text").cloned(),
+        priority: args.get_one::<String>("priority").and_then(|priority| priority.parse().ok()),
+        ..TaskQuery::default()
+    };
+
+    match args.get_one::<String>("status").map(String::as_str) {
+        Some("open") => query.open = Some(true),
+        Some("closed") => query.open = Some(false),
+        status => query.status = status.map(String::from),
+    }
+
+    if let Some(age) = args.get_one::<Duration>("older-than") {
+        let date = chrono::Duration::from_std(*age).ok()
+                                                   .and_then(|age| chrono::Utc::now().checked_sub_signed(age));
+        // Nothing was created before a date too far in the past.
+        query.created_before = Some(date.map_or_else(String::new, |date| date.format(DATE_FORMAT).to_string()));
+    }
+
+    query
+}
+
+/// Finds the config file given in the arguments. It is looked for before
+/// parsing them, since the config changes how they are parsed.
+fn config_arg(argv: &[String]) -> Option<PathBuf> {
+    argv.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix("--config") {
+        Some("") => argv.get(i + 1).map(PathBuf::from),
+        Some(path) => path.strip_prefix('=').map(PathBuf::from),
+        None => None,
+    })
+}
+
+/// The config file given in the arguments or the environment, if any.
+fn config_path(argv: &[String]) -> Option<PathBuf> {
+    config_arg(argv).or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from))
+}
+
+/// Reads the settings of the config file, overridden by the environment.
+fn load_config(argv: &[String]) -> Result<Config, InternalError> {
+    let mut config = Config::load(config_path(argv).as_deref())?;
+
+    config.apply_env(env::vars_os().filter_map(|(name, value)| {
+        Some((name.into_string().ok()?, value.into_string().ok()?))
+    }))?;
+
+    Ok(config)
+}
+
+/// Overrides the settings given in the arguments.
+fn apply_args(config: &mut Config, args: &ArgMatches) -> Result<(), InternalError> {
+    for key in Config::KEYS {
+        // The format of the export has the name already.
+        let name = if key == "format" { "output-format" } else { key };
+
+        if let Some(value) = args.get_one::<String>(name) {
+            config.set(key, value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the default store to the backend chosen in the config.
+fn open_store(config: &Config) -> Result<(), InternalError> {
+    let store = match config.backend {
+        Backend::Files => Store::with_backend(Box::new(FileStorage::open(config.active_dir())?)),
+        Backend::Sqlite => {
+            let database = config.active_database();
+            let path = database.to_string_lossy();
+
+            if let Some(parent) = database.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| InternalError::new(&format!("could not create {}: {}", parent.display(), e)))?;
+            }
+
+            Store::open(&path)?
+        },
+    };
+
+    Store::set_default_store(store.with_hooks(config.hooks.clone()));
+    Store::default_store().init_tables()
+}
+
+/// Whether the application has the command, including the help made by clap.
+fn is_command(command: &Command, name: &str) -> bool {
+    name == "help" || command.find_subcommand(name).is_some()
+}
+
+/// Finds where the command is in the arguments, following
+/// the options and the todo, e.g. 3 in `todo --sort due 3 list`.
+fn command_index(command: &Command, argv: &[String]) -> Option<usize> {
+    let takes_value = |name: &str| command.get_arguments()
+                                          .any(|arg| arg.get_long() == Some(name) && arg.is_takes_value_set());
+    let mut i = 1;
+
+    while i < argv.len() {
+        let word = argv[i].as_str();
+
+        if let Some(option) = word.strip_prefix("--") {
+            i += if takes_value(option) { 2 } else { 1 };
+            continue;
+        }
+
+        if word.starts_with('-') || word.parse::<IdType>().is_ok() {
+            i += 1;
+            continue;
+        }
+
+        return Some(i);
+    }
+
+    None
+}
+
+/// Replaces the alias given as the command by its arguments. The alias
+/// may follow the options and the todo, e.g. `todo --sort due 3 work`,
+/// and the commands can't be replaced by one.
+fn expand_alias(command: &Command, aliases: &BTreeMap<String, String>, argv: Vec<String>) -> Result<Vec<String>, String> {
+    let i = match command_index(command, &argv) {
+        Some(i) => i,
+        None => return Ok(argv),
+    };
+    let word = argv[i].as_str();
+
+    match aliases.get(word) {
+        Some(alias) if !is_command(command, word) => {
+            let words = shlex::split(alias).ok_or_else(|| format!("unbalanced quotes in the alias {}", word))?;
+            Ok(argv[..i].iter().cloned().chain(words).chain(argv[i + 1..].iter().cloned()).collect())
+        },
+        _ => Ok(argv),
+    }
+}
+
+/// Finds the plugin of a command unknown to the application: an
+/// executable named `todo-<command>` in one of the directories of PATH.
+fn find_plugin(command: &Command, argv: &[String]) -> Option<(usize, PathBuf)> {
+    let i = command_index(command, argv)?;
+    let name = argv[i].as_str();
+
+    if is_command(command, name) || name.contains(std::path::is_separator) {
+        return None;
+    }
+
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths).map(|dir| dir.join(format!("todo-{}", name)))
+                            .find(|path| is_executable(path))
+                            .map(|path| (i, path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Prints the error and exits, for the errors happening before a command runs.
+fn exit_with(e: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
 }
 
 impl App {
     /// Used to create an app
     fn new(name: &str, version: &str) -> Self {
-        Self {name: String::from(name), version: String::from(version)}
+        Self {name: String::from(name), version: String::from(version), config: Config::default(), config_path: None, in_shell: false}
     }
 
     /// References the name of this app
@@ -66,31 +817,1377 @@ impl App {
 
     /// Run main routine
     fn run(&mut self) {
-        let args = parse_args(self.name(), self.version());
+        let argv: Vec<String> = env::args().collect();
+        let mut config = load_config(&argv).unwrap_or_else(|e| exit_with(e));
+        self.config_path = config_path(&argv).or_else(Config::default_path);
+
+        let command = build_command(self.name(), self.version()).subcommand(complete_command());
+        let argv = expand_alias(&command, &config.aliases, argv).unwrap_or_else(|e| exit_with(e));
+        let plugin = find_plugin(&command, &argv);
+
+        if let Some(i) = command_index(&command, &argv).filter(|_| plugin.is_none()) {
+            if !is_command(&command, &argv[i]) {
+                exit_with(format!("unknown command '{0}', and there is no todo-{0} plugin on PATH", argv[i]));
+            }
+        }
+
+        // The options given before a plugin are still for the application.
+        let args = match &plugin {
+            Some((i, _)) => command.get_matches_from(&argv[..*i]),
+            None => command.get_matches_from(&argv),
+        };
+
+        if let Err(e) = apply_args(&mut config, &args).and_then(|_| open_store(&config)) {
+            exit_with(e);
+        }
+
+        self.config = config;
+
+        match plugin {
+            Some((i, path)) => {
+                let code = self.run_plugin(&path, args.get_one::<IdType>("id").copied(), &argv[i + 1..]);
+                std::process::exit(code);
+            },
+            None => self.execute(&args),
+        }
+    }
+
+    /// Runs a plugin with the given arguments, passing its input and
+    /// output through, and returns its exit code. The plugin gets the
+    /// settings of the storage in the same environment variables the
+    /// application reads, e.g. `TODO_DATABASE`, so that it can run
+    /// `todo` on the same todos, and everything else in `TODO_CONTEXT`
+    /// as json.
+    fn run_plugin(&self, path: &Path, todo_id: Option<IdType>, args: &[String]) -> i32 {
+        let config = &self.config;
+        let database = match config.backend {
+            Backend::Sqlite => config.active_database(),
+            Backend::Files => config.active_dir(),
+        };
+        let context = serde_json::json!({
+            "version": self.version(),
+            "backend": config.backend,
+            "database": database,
+            "todo_id": todo_id,
+            "args": args,
+            "config": config,
+        });
+
+        let mut plugin = std::process::Command::new(path);
+        plugin.args(args)
+              .env("TODO_WORKSPACE", config.workspace.as_deref().unwrap_or(Config::DEFAULT_WORKSPACE))
+              .env("TODO_BACKEND", config.backend.name())
+              .env("TODO_DATABASE", config.active_database())
+              .env("TODO_DIR", config.active_dir())
+              .env("TODO_CONTEXT", context.to_string());
+
+        if let Ok(exe) = env::current_exe() {
+            plugin.env("TODO_BIN", exe);
+        }
+
+        match plugin.status() {
+            Ok(status) => status.code().unwrap_or(1),
+            Err(e) => {
+                eprintln!("Could not run the plugin {}: {}", path.display(), e);
+                1
+            },
+        }
+    }
+
+    /// Reports the error of a command, exiting with it as `exit_with`
+    /// unless inside of the shell, so that scripts can tell it failed.
+    fn fail(&self, e: impl std::fmt::Display) {
+        if self.in_shell {
+            eprintln!("{}", e);
+        } else {
+            exit_with(e);
+        }
+    }
+
+    /// Shows a date of the storage in the configured format
+    fn date(&self, date: &str) -> String {
+        self.config.format_date(date)
+    }
+
+    /// The mark of a status, coloured when the config allows it
+    fn mark(&self, status: &Status) -> String {
+        let color = match status {
+            _ if !self.config.use_color(io::stdout().is_terminal()) => None,
+            Status::Todo => None,
+            Status::InProgress(_) => Some(33),
+            Status::Blocked { .. } => Some(31),
+            Status::Done(_) => Some(32),
+            Status::Cancelled(_) => Some(90),
+        };
+
+        match color {
+            Some(color) => format!("\x1b[{}m{}\x1b[0m", color, status.mark()),
+            None => String::from(status.mark()),
+        }
+    }
+
+    /// Runs the subcommands of `todo trash`
+    fn trash(&mut self, args: &ArgMatches) {
+        let store = Store::default_store();
+
+        match args.subcommand() {
+            Some(("list", _)) => {
+                for todo in store.trashed_todos() {
+                    println!("todo {}\t{}\tdeleted at {}", todo.id(), todo.name(), self.date(todo.deleted_at().unwrap()));
+                }
+
+                for task in store.trashed_tasks() {
+                    match task.deleted_at() {
+                        Some(date) => println!("task {}\t{}\tdeleted at {}", task.id(), task.what(), self.date(date)),
+                        None => println!("task {}\t{}\twith todo {}", task.id(), task.what(), task.todo_id()),
+                    }
+                }
+            },
+            Some(("restore", restore_matches)) => {
+                let id = *restore_matches.get_one::<IdType>("id").unwrap();
+
+                let res = if restore_matches.is_present("task") {
+                    Task::restore(id).map(|task| format!("Task restored: {}", task.what()))
+                } else {
+                    Todo::restore(id).map(|todo| format!("Todo restored: {}", todo.name()))
+                };
+
+                match res {
+                    Ok(message) => println!("{}", message),
+                    Err(e) => self.fail(format!("Could not restore: {}", e)),
+                }
+            },
+            Some(("purge", purge_matches)) => {
+                let older_than = purge_matches.get_one::<Duration>("older-than")
+                                              .copied()
+                                              .unwrap_or(Duration::ZERO);
+
+                match store.purge(older_than) {
+                    Ok((todos, tasks)) => println!("Purged {} todos and {} tasks", todos, tasks),
+                    Err(e) => self.fail(format!("Could not purge the trash: {}", e)),
+                }
+            },
+            _ => unreachable!("todo trash requires a subcommand"),
+        }
+    }
+
+    /// Runs the subcommands of `todo task <id>`
+    fn task(&mut self, args: &ArgMatches) {
+        let id = *args.get_one::<IdType>("task_id").unwrap();
+        let now = || String::from("CURRENT_TIMESTAMP");
+        let store = Store::default_store();
+
+        let status = match args.subcommand() {
+            Some(("start", _)) => {
+                match store.start_timer(id) {
+                    Ok(entry) => println!("Task {} started at {}", id, self.date(entry.started_at())),
+                    Err(e) => self.fail(format!("Could not start the task: {}", e)),
+                }
+                return;
+            },
+            Some(("stop", _)) => {
+                match store.stop_timer(id) {
+                    Ok(entry) => println!("Task {} stopped after {}", id, format_duration(entry.duration())),
+                    Err(e) => self.fail(format!("Could not stop the task: {}", e)),
+                }
+                return;
+            },
+            Some(("log", log_matches)) => {
+                let duration = *log_matches.get_one::<Duration>("duration").unwrap();
+
+                match store.log_time(id, duration) {
+                    Ok(_) => println!("Logged {} on task {}, {} in total", format_duration(duration), id,
+                                      format_duration(store.task_time(id))),
+                    Err(e) => self.fail(format!("Could not log the time: {}", e)),
+                }
+                return;
+            },
+            Some(("estimate", estimate_matches)) => {
+                let effort = *estimate_matches.get_one::<f64>("effort").unwrap();
+
+                match store.set_task_estimate(id, Some(effort)) {
+                    Ok(task) => println!("Task {} is estimated at {}: {}", id, effort, task.what()),
+                    Err(e) => self.fail(format!("Could not estimate the task: {}", e)),
+                }
+                return;
+            },
+            Some(("due", due_matches)) => {
+                let day = due_matches.get_one::<String>("day").unwrap();
+                let due = if day == "none" { None } else { Some(day.as_str()) };
+
+                match store.set_task_due(id, due) {
+                    Ok(task) => match task.due() {
+                        Some(due) => println!("Task {} is due on {}: {}", id, due, task.what()),
+                        None => println!("Task {} has no due day: {}", id, task.what()),
+                    },
+                    Err(e) => self.fail(format!("Could not set the due day: {}", e)),
+                }
+                return;
+            },
+            Some(("priority", priority_matches)) => {
+                let priority = priority_matches.get_one::<String>("priority").unwrap().parse::<Priority>().ok();
+
+                match store.set_task_priority(id, priority) {
+                    Ok(task) => match task.priority() {
+                        Some(priority) => println!("Task {} has {} priority: {}", id, priority.name(), task.what()),
+                        None => println!("Task {} has no priority: {}", id, task.what()),
+                    },
+                    Err(e) => self.fail(format!("Could not set the priority: {}", e)),
+                }
+                return;
+            },
+            Some(("block", block_matches)) => Status::Blocked {
+                reason: block_matches.get_one::<String>("reason").unwrap().clone(),
+                since: now(),
+            },
+            Some(("done", _)) => Status::Done(now()),
+            Some(("cancel", _)) => Status::Cancelled(now()),
+            Some(("reopen", _)) => Status::Todo,
+            Some(("move", move_matches)) => {
+                let to = *move_matches.get_one::<IdType>("to").unwrap();
+
+                match store.move_task(id, to) {
+                    Ok(task) => println!("Task {} moved to todo {}: {}", id, to, task.what()),
+                    Err(e) => self.fail(format!("Could not move the task: {}", e)),
+                }
+                return;
+            },
+            Some(("copy", copy_matches)) => {
+                let to = *copy_matches.get_one::<IdType>("to").unwrap();
+
+                match store.copy_task(id, to) {
+                    Ok(task) => println!("Task {} copied to todo {} as task {}: {}", id, to, task.id(), task.what()),
+                    Err(e) => self.fail(format!("Could not copy the task: {}", e)),
+                }
+                return;
+            },
+            _ => unreachable!("todo task requires a subcommand"),
+        };
+
+        match Task::update(id, None, Some(status)) {
+            Ok(task) => println!("Task {} is now {}: {}", task.id(), task.status().name(), task.what()),
+            Err(e) => self.fail(format!("Could not change the task: {}", e)),
+        }
+    }
+
+    /// Whether the list command was given any filter
+    fn has_filters(args: &ArgMatches) -> bool {
+        ["status", "text", "priority", "older-than"].iter().any(|name| args.contains_id(name))
+    }
+
+    /// Moves the tasks of a todo, or the ones matching the filters, to another todo
+    fn move_tasks(&self, todo_id: Option<IdType>, args: &ArgMatches) {
+        // Moving every task of every todo is rather a mistake.
+        if todo_id.is_none() && !Self::has_filters(args) {
+            self.fail("Give the todo whose tasks are moved, or filters of the tasks");
+            return;
+        }
+
+        let to = *args.get_one::<IdType>("to").unwrap();
+
+        match Store::default_store().move_tasks(&task_query(todo_id, args), to) {
+            Ok(tasks) => {
+                for task in &tasks {
+                    println!("Task {} moved to todo {}: {}", task.id(), to, task.what());
+                }
+
+                if tasks.is_empty() {
+                    println!("No task to move");
+                }
+            },
+            Err(e) => self.fail(format!("Could not move the tasks: {}", e)),
+        }
+    }
+
+    /// Lists the tasks matching the query, of every todo
+    fn list_tasks(&self, query: TaskQuery) {
+        let mut tasks = Store::default_store().query_tasks(&query);
+        self.config.sort.sort(&mut tasks);
+
+        if self.config.format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&tasks).unwrap());
+            return;
+        }
+
+        if tasks.is_empty() {
+            println!("No tasks found");
+        }
+
+        for task in &tasks {
+            let priority = task.priority().map(|priority| priority.name()).unwrap_or("-");
+            println!("  {}\t{} {:<6} {}\t(todo {})", task.id(), self.mark(task.status()), priority, task.what(), task.todo_id());
+        }
+    }
+
+    /// Runs `todo view <name>` and the subcommands managing the views
+    fn view(&mut self, todo_id: Option<IdType>, args: &ArgMatches) {
+        let store = Store::default_store();
+        // The filters of a view are the arguments of the list command.
+        let list_args = |filters: &str| -> Result<ArgMatches, String> {
+            let words = shlex::split(filters).ok_or("unbalanced quotes in the filters")?;
+            let argv = [self.name().clone()].into_iter()
+                                            .chain(todo_id.map(|id| id.to_string()))
+                                            .chain([String::from("list")])
+                                            .chain(words);
+
+            build_command(self.name(), self.version()).try_get_matches_from(argv).map_err(|e| e.to_string())
+        };
+
+        match args.subcommand() {
+            Some(("add", add_matches)) => {
+                let name = add_matches.get_one::<String>("name").unwrap();
+                let filters = add_matches.get_one::<String>("filters").unwrap();
+
+                if ["add", "list", "remove"].contains(&name.as_str()) {
+                    self.fail(format!("Could not add the view: {} is a command of todo view", name));
+                    return;
+                }
+
+                if let Err(e) = list_args(filters) {
+                    self.fail(format!("Could not add the view: {}", e.trim_end()));
+                    return;
+                }
+
+                match store.save_view(name, filters) {
+                    Ok(view) => println!("View saved: {} = {}", view.name(), view.args()),
+                    Err(e) => self.fail(format!("Could not add the view: {}", e)),
+                }
+            },
+            Some(("list", _)) => for view in store.views() {
+                println!("{}\t{}", view.name(), view.args());
+            },
+            Some(("remove", remove_matches)) => {
+                let name = remove_matches.get_one::<String>("name").unwrap();
+
+                match store.delete_view(name) {
+                    Ok(_) => println!("View removed: {}", name),
+                    Err(e) => self.fail(format!("Could not remove the view: {}", e)),
+                }
+            },
+            _ => {
+                let name = match args.get_one::<String>("name") {
+                    Some(name) => name,
+                    None => {
+                        self.fail("Could not run the view: its name is missing, e.g. todo view stale");
+                        return;
+                    },
+                };
+
+                let matches = store.find_view(name)
+                                   .map_err(|e| e.to_string())
+                                   .and_then(|view| list_args(view.args()));
+
+                match matches {
+                    Ok(matches) => self.execute(&matches),
+                    Err(e) => self.fail(format!("Could not run the view: {}", e.trim_end())),
+                }
+            },
+        }
+    }
+
+    /// Runs `todo today`, `todo week` and `todo overdue`
+    fn agenda(&mut self, view: &str) {
+        let today = chrono::Utc::now().date_naive();
+        let (from, to) = match view {
+            "today" => (Some(today), today),
+            "week" => (Some(today), today + chrono::Days::new(6)),
+            _ => (None, today - chrono::Days::new(1)),
+        };
+
+        let groups = Store::default_store().agenda(from.map(|day| day.to_string()).as_deref(), &to.to_string());
+
+        if self.config.format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&groups).unwrap());
+            return;
+        }
+
+        if groups.is_empty() {
+            println!("Nothing due");
+        }
+
+        for group in &groups {
+            println!("{}", group.due);
+
+            for task in &group.tasks {
+                let priority = task.priority().map(|priority| priority.name()).unwrap_or("-");
+                println!("  {}\t{} {:<6} {}\t(todo {})", task.id(), self.mark(task.status()), priority, task.what(), task.todo_id());
+            }
+        }
+    }
+
+    /// Runs the subcommands of `todo workspace`
+    fn workspace(&mut self, args: &ArgMatches) {
+        let path = match &self.config_path {
+            Some(path) => path.clone(),
+            None => {
+                self.fail("Could not find the config file, give one with --config");
+                return;
+            },
+        };
+        let mut file = match ConfigFile::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                self.fail(format!("Could not read the config: {}", e));
+                return;
+            },
+        };
+
+        let res = match args.subcommand() {
+            Some(("create", create_matches)) => {
+                let name = create_matches.get_one::<String>("name").unwrap();
+                let database = create_matches.get_one::<String>("database").map(Path::new);
+
+                file.create_workspace(name, database)
+                    .and_then(|_| file.save())
+                    .map(|_| format!("Workspace created: {}", name))
+            },
+            Some(("list", _)) => {
+                let config = &self.config;
+                let mark = |name: Option<&str>| if config.workspace.as_deref() == name { "*" } else { " " };
+                let path = |name: Option<&str>| match (config.backend, name) {
+                    (Backend::Sqlite, Some(name)) => config.workspace_database(name),
+                    (Backend::Sqlite, None) => config.database.clone(),
+                    (Backend::Files, Some(name)) => config.workspace_dir(name),
+                    (Backend::Files, None) => config.dir.clone(),
+                };
 
+                println!("{} {}	{}", mark(None), Config::DEFAULT_WORKSPACE, path(None).display());
+
+                for name in config.workspaces.keys() {
+                    println!("{} {}	{}", mark(Some(name)), name, path(Some(name)).display());
+                }
+                return;
+            },
+            Some(("use", use_matches)) => {
+                let name = use_matches.get_one::<String>("name").unwrap();
+
+                file.use_workspace(name)
+                    .and_then(|_| file.save())
+                    .map(|_| format!("Using the workspace {}", name))
+            },
+            Some(("delete", delete_matches)) => {
+                let name = delete_matches.get_one::<String>("name").unwrap();
+                let config = file.config();
+
+                // The files are removed before the workspace is, to keep it if they can't be.
+                file.delete_workspace(name).and_then(|_| {
+                    if !delete_matches.is_present("purge") {
+                        return Ok(format!("Workspace deleted: {}, its todos are kept", name));
+                    }
+
+                    let config = config?;
+                    let files = config.workspace_files(name);
+
+                    if files.is_empty() {
+                        let path = match config.backend {
+                            Backend::Sqlite => config.workspace_database(name),
+                            Backend::Files => config.workspace_dir(name),
+                        };
+                        return Ok(format!("Workspace deleted: {}, its todos are kept in {}, given in the config",
+                                          name, path.display()));
+                    }
+
+                    for path in files {
+                        let res = if path.is_dir() { std::fs::remove_dir_all(&path) } else { std::fs::remove_file(&path) };
+
+                        match res {
+                            // The files of sqlite may not all exist.
+                            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                                return Err(InternalError::new(&format!("could not remove {}: {}", path.display(), e)));
+                            },
+                            _ => (),
+                        }
+                    }
+
+                    Ok(format!("Workspace deleted with its todos: {}", name))
+                }).and_then(|message| file.save().map(|_| message))
+            },
+            _ => unreachable!("todo workspace requires a subcommand"),
+        };
+
+        match res {
+            Ok(message) => println!("{}", message),
+            Err(e) => self.fail(format!("Could not change the workspaces in {}: {}", file.path().display(), e)),
+        }
+    }
+
+    /// Runs the subcommands of `todo report`
+    fn report(&mut self, args: &ArgMatches) {
+        match args.subcommand() {
+            Some(("timesheet", timesheet_matches)) => {
+                let today = chrono::Utc::now().date_naive();
+                let day = |name: &str, default: chrono::NaiveDate| {
+                    timesheet_matches.get_one::<String>(name)
+                                     .cloned()
+                                     .unwrap_or_else(|| default.to_string())
+                };
+                let from = day("from", today - chrono::Days::new(6));
+                let to = day("to", today);
+
+                let rows = Store::default_store().timesheet(&from, &to);
+                let mut total = Duration::ZERO;
+
+                for row in &rows {
+                    println!("{}\t{:>7}\ttask {}\t{}", row.day, format_duration(row.duration), row.task.id(), row.task.what());
+                    total += row.duration;
+                }
+
+                println!("{} to {}: {}", from, to, format_duration(total));
+            },
+            Some(("velocity", velocity_matches)) => {
+                let weeks = *velocity_matches.get_one::<u64>("weeks").unwrap();
+                let today = chrono::Utc::now().date_naive();
+                let since = today.week(chrono::Weekday::Mon).first_day() - chrono::Days::new(7 * (weeks - 1));
+
+                let rows = Store::default_store().velocity(&since.to_string());
+                let effort = rows.iter().map(|row| row.effort).fold(0.0, |a, b| a + b);
+
+                for row in &rows {
+                    println!("{}\t{} task(s)\t{} done", row.week, row.tasks, row.effort);
+                }
+
+                println!("Average over {} week(s): {:.1} done per week", weeks, effort / weeks as f64);
+            },
+            _ => unreachable!("todo report requires a subcommand"),
+        }
+    }
+
+    /// Runs `todo stats`, for every todo or for the one given
+    fn stats(&mut self, todo_id: Option<IdType>, args: &ArgMatches) {
+        let store = Store::default_store();
+        let period: Period = args.get_one::<String>("by").unwrap().parse().unwrap();
+        let oldest = *args.get_one::<usize>("oldest").unwrap();
+        let stats = store.stats(period, oldest);
+
+        let todos: Vec<Todo> = match todo_id {
+            Some(id) => store.find_todo(id).into_iter().collect(),
+            None => store.todos(),
+        };
+        let burndowns: Vec<(Todo, Vec<BurndownPoint>)> = if args.is_present("burndown") {
+            todos.into_iter().map(|todo| {
+                let points = store.burndown(*todo.id(), period);
+                (todo, points)
+            }).collect()
+        } else {
+            Vec::new()
+        };
+
+        if args.is_present("json") || self.config.format == OutputFormat::Json {
+            let mut json = serde_json::to_value(&stats).unwrap();
+
+            if args.is_present("burndown") {
+                json["burndown"] = burndowns.iter()
+                                            .map(|(todo, points)| (todo.id().to_string(), serde_json::json!(points)))
+                                            .collect();
+            }
+
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+            return;
+        }
+
+        println!("Tasks per {}:", if period == Period::Day { "day" } else { "week" });
+        println!("  {:<12} {:>8} {:>10}", "period", "created", "completed");
+        for row in &stats.per_period {
+            println!("  {:<12} {:>8} {:>10}", row.period, row.created, row.completed);
+        }
+
+        match stats.average_completion() {
+            Some(time) => println!("\nAverage time to completion: {}", format_duration(time)),
+            None => println!("\nAverage time to completion: no task done yet"),
+        }
+
+        println!("\nOldest open tasks:");
+        for task in &stats.oldest_open {
+            println!("  {:>4}  {}  todo {:<4} {}", task.id(), self.date(task.created_at()), task.todo_id(), task.what());
+        }
+
+        println!("\nCompletion per todo:");
+        for todo in &stats.todos {
+            println!("  {:>4}  {:<24} {:>3}/{:<3} {:>4.0}%", todo.todo_id, todo.name, todo.done, todo.total, todo.rate * 100.0);
+        }
+
+        for (todo, points) in &burndowns {
+            println!("\nBurndown of {} {}:", todo.id(), todo.name());
+            print!("{}", burndown_chart(points, 8));
+        }
+    }
+
+    /// Runs the command described by the given arguments
+    fn execute(&mut self, args: &ArgMatches) {
         match args.subcommand() {
             Some(("add", add_matches)) => {
+                #[allow(clippy::redundant_closure)]
                 let name = add_matches.get_one::<String>("name")
                                                        .map(|s| String::from(s)).unwrap();
 
+                #[allow(clippy::redundant_closure)]
                 let description = add_matches.get_one::<String>("description")
                                                              .map(|s| String::from(s));
 
-                let todo = Todo::add(name, description).unwrap();
+                match Todo::add(name, description) {
+                    Ok(todo) => println!("Todo added: {:#?}", todo),
+                    Err(e) => self.fail(format!("Could not add the todo: {}", e)),
+                }
+            },
+            Some(("export", export_matches)) => {
+                let columns: Vec<CsvColumn> = match export_matches.get_many::<String>("columns") {
+                    Some(names) => names.map(|name| name.parse().unwrap()).collect(),
+                    None => CsvColumn::ALL.to_vec(),
+                };
+
+                let store = Store::default_store();
+                let res = match export_matches.get_one::<String>("output") {
+                    Some(path) => File::create(path)
+                                       .map_err(|e| InternalError::new(&e.to_string()))
+                                       .and_then(|file| export_csv(&store, file, &columns)),
+                    None => export_csv(&store, io::stdout(), &columns),
+                };
+
+                if let Err(e) = res {
+                    self.fail(format!("Could not export the tasks: {}", e));
+                }
+            },
+            Some(("import", import_matches)) => {
+                let path = import_matches.get_one::<String>("file").unwrap();
+
+                let store = Store::default_store();
+                let res = if path == "-" {
+                    import_csv(&store, io::stdin())
+                } else {
+                    File::open(path)
+                        .map_err(|e| InternalError::new(&e.to_string()))
+                        .and_then(|file| import_csv(&store, file))
+                };
+
+                match res {
+                    Ok((todos, tasks)) => println!("Imported {} todos and {} tasks", todos, tasks),
+                    Err(e) => self.fail(format!("Could not import the file: {}", e)),
+                }
+            },
+            Some(("serve", serve_matches)) => {
+                let address = serve_matches.get_one::<String>("bind").unwrap();
 
-                println!("Todo added: {:#?}", todo);
+                let res = bind(address).and_then(|server| {
+                    println!("Listening on http://{}", address);
+                    serve_on(server)
+                });
+
+                if let Err(e) = res {
+                    self.fail(format!("Could not start the server: {}", e));
+                }
+            },
+            Some(("tui", _)) => {
+                if let Err(e) = run_tui() {
+                    self.fail(format!("Could not run the terminal interface: {}", e));
+                }
+            },
+            Some(("list", list_matches)) if Self::has_filters(list_matches) => {
+                self.list_tasks(task_query(args.get_one::<IdType>("id").copied(), list_matches));
+            },
+            Some(("view", view_matches)) => self.view(args.get_one::<IdType>("id").copied(), view_matches),
+            Some(("move", move_matches)) => self.move_tasks(args.get_one::<IdType>("id").copied(), move_matches),
+            Some(("list", _)) => match args.get_one::<IdType>("id") {
+                Some(id) => match Todo::find(*id) {
+                    Ok(todo) => {
+                        let progress = todo.progress();
+                        let mut tasks = todo.tasks();
+                        self.config.sort.sort(&mut tasks);
+
+                        if self.config.format == OutputFormat::Json {
+                            let json = serde_json::json!({"todo": todo, "progress": progress, "tasks": tasks});
+                            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+                            return;
+                        }
+
+                        println!("{}\t{}\t{}\t{}\t{} effort left", todo.id(), todo.name(), progress,
+                                 format_duration(todo.time_spent()), progress.remaining_effort());
+
+                        for task in tasks {
+                            let mut details = Vec::new();
+
+                            if let Some(priority) = task.priority() {
+                                details.push(format!("{} priority", priority.name()));
+                            }
+
+                            if let Some(due) = task.due() {
+                                details.push(format!("due {}", due));
+                            }
+
+                            if let Some(estimate) = task.estimate() {
+                                details.push(format!("estimate {}", estimate));
+                            }
+
+                            match task.time_spent() {
+                                time if time.is_zero() => (),
+                                time => details.push(format_duration(time)),
+                            }
+
+                            if details.is_empty() {
+                                println!("  {}\t{} {}", task.id(), self.mark(task.status()), task.what());
+                            } else {
+                                println!("  {}\t{} {} ({})", task.id(), self.mark(task.status()), task.what(), details.join(", "));
+                            }
+                        }
+                    },
+                    Err(e) => self.fail(format!("Could not list the todo: {}", e)),
+                },
+                None if self.config.format == OutputFormat::Json => {
+                    let json: Vec<_> = Todo::all().iter()
+                                                  .map(|todo| serde_json::json!({"todo": todo, "progress": todo.progress()}))
+                                                  .collect();
+                    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+                },
+                None => for todo in Todo::all() {
+                    println!("{}\t{}\t{}", todo.id(), todo.progress(), todo.name());
+                },
+            },
+            Some(("set", set_matches)) => {
+                let id = match args.get_one::<IdType>("id") {
+                    Some(id) => *id,
+                    None => {
+                        self.fail("Could not set the todo: the id of the todo is missing, e.g. todo <id> set complete");
+                        return;
+                    },
+                };
+
+                if let Some(("complete", _)) = set_matches.subcommand() {
+                    match Todo::complete(id) {
+                        Ok(tasks) => println!("Todo complete, {} task(s) set as done", tasks.len()),
+                        Err(e) => self.fail(format!("Could not complete the todo: {}", e)),
+                    }
+                }
+            },
+            Some(("drop", _)) => {
+                let res = match args.get_one::<IdType>("id") {
+                    Some(id) => Todo::delete(*id),
+                    None => Err(InternalError::invalid_input("the id of the todo is missing, e.g. todo <id> drop")),
+                };
+
+                match res {
+                    Ok(()) => println!("Todo moved to the trash, `todo undo` brings it back"),
+                    Err(e) => self.fail(format!("Could not drop the todo: {}", e)),
+                }
+            },
+            Some(("archive", _)) => {
+                match args.get_one::<IdType>("id") {
+                    Some(id) => match Todo::archive(*id) {
+                        Ok(todo) => println!("Todo archived: {}", todo.name()),
+                        Err(e) => self.fail(format!("Could not archive the todo: {}", e)),
+                    },
+                    None => for todo in Store::default_store().archived_todos() {
+                        println!("{}\t{}\tarchived at {}", todo.id(), todo.name(), todo.archived_at().unwrap());
+                    },
+                }
+            },
+            Some(("unarchive", _)) => {
+                let res = match args.get_one::<IdType>("id") {
+                    Some(id) => Todo::unarchive(*id),
+                    None => Err(InternalError::invalid_input("the id of the todo is missing, e.g. todo <id> unarchive")),
+                };
+
+                match res {
+                    Ok(todo) => println!("Todo unarchived: {}", todo.name()),
+                    Err(e) => self.fail(format!("Could not unarchive the todo: {}", e)),
+                }
+            },
+            Some(("trash", trash_matches)) => self.trash(trash_matches),
+            Some(("task", task_matches)) => self.task(task_matches),
+            Some(("report", report_matches)) => self.report(report_matches),
+            Some(("workspace", workspace_matches)) => self.workspace(workspace_matches),
+            Some((view @ ("today" | "week" | "overdue"), _)) => self.agenda(view),
+            Some(("stats", stats_matches)) => self.stats(args.get_one::<IdType>("id").copied(), stats_matches),
+            Some((name @ ("undo" | "redo"), journal_matches)) => {
+                let count = *journal_matches.get_one::<usize>("count").unwrap();
+                let store = Store::default_store();
+
+                let res = if name == "undo" { store.undo(count) } else { store.redo(count) };
+
+                match res {
+                    Ok(entries) if entries.is_empty() => println!("Nothing to {}", name),
+                    Ok(entries) => for entry in entries {
+                        println!("{}: {}", name, entry);
+                    },
+                    Err(e) => self.fail(format!("Could not {}: {}", name, e)),
+                }
+            },
+            Some(("log", log_matches)) => {
+                let query = HistoryQuery {
+                    todo_id: log_matches.get_one::<IdType>("id").copied(),
+                    ..HistoryQuery::default()
+                };
+
+                for entry in HistoryEntry::query(&query) {
+                    println!("{}", entry);
+                }
+            },
+            Some(("completions", completions_matches)) => {
+                let shell: Shell = completions_matches.get_one::<String>("shell")
+                                                      .unwrap().parse().unwrap();
+                let mut command = build_command(self.name(), self.version());
+
+                print_completions(&mut command, shell);
+            },
+            Some(("__complete", complete_matches)) => {
+                let mut words: Vec<&str> = complete_matches.get_many::<String>("words")
+                                                           .map(|w| w.map(|s| s.as_str()).collect())
+                                                           .unwrap_or_default();
+                let current = words.pop().unwrap_or_default();
+                let command = build_command(self.name(), self.version());
+
+                for (id, name) in complete_ids(&command, &words, current) {
+                    println!("{}\t{}", id, name);
+                }
+            },
+            Some(("shell", _)) => {
+                if let Err(e) = self.shell() {
+                    exit_with(format!("Could not run the shell: {}", e));
+                }
             },
             _ => ()
         }
 
     }
+
+    /// Runs the interactive shell, reading commands until
+    /// the end of the input or an `exit`.
+    fn shell(&mut self) -> rustyline::Result<()> {
+        let version = self.version().clone();
+        let command = build_command(self.name(), &version);
+        let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::new()?;
+        editor.set_helper(Some(ShellHelper { command: command.clone() }));
+        self.in_shell = true;
+
+        loop {
+            let line = match editor.readline("todo> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            let words = match split_line(&line) {
+                Ok(words) if words.is_empty() => continue,
+                Ok(words) => words,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+
+            editor.add_history_entry(line.as_str())?;
+
+            match words[0].as_str() {
+                "exit" | "quit" => return Ok(()),
+                "shell" => {
+                    eprintln!("Already inside the shell");
+                    continue;
+                }
+                _ => (),
+            }
+
+            let argv = std::iter::once(self.name().clone()).chain(words).collect();
+            let argv = match expand_alias(&command, &self.config.aliases, argv) {
+                Ok(argv) => argv,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+
+            if let Some((i, path)) = find_plugin(&command, &argv) {
+                // The options of a line only change the commands of the application.
+                let todo_id = argv[1..i].iter().find_map(|word| word.parse::<IdType>().ok());
+                self.run_plugin(&path, todo_id, &argv[i + 1..]);
+                continue;
+            }
+
+            match command.clone().try_get_matches_from(argv) {
+                Ok(args) => self.execute_line(&args),
+                Err(e) => {
+                    let _ = e.print();
+                }
+            }
+        }
+    }
+
+    /// Runs a command of the shell with the options given on its line,
+    /// which only last for the line: the storage they choose is opened
+    /// for the command, then the one of the shell again.
+    fn execute_line(&mut self, args: &ArgMatches) {
+        let config = match line_config(&self.config, args) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+
+        let storage = |config: &Config| (config.backend, config.active_database(), config.active_dir());
+        let moved = storage(&config) != storage(&self.config);
+
+        if moved {
+            if let Err(e) = open_store(&config) {
+                eprintln!("Could not open the storage: {}", e);
+                let _ = open_store(&self.config);
+                return;
+            }
+        }
+
+        let shell_config = std::mem::replace(&mut self.config, config);
+        self.execute(args);
+        self.config = shell_config;
+
+        if moved {
+            if let Err(e) = open_store(&self.config) {
+                eprintln!("Could not open the storage of the shell again: {}", e);
+            }
+        }
+    }
 }
 
-fn main() {
-    // XXX
-    Todo::init_table().unwrap();
-    Task::init_table().unwrap();
+/// Splits a line of the shell into words, as a shell would.
+fn split_line(line: &str) -> Result<Vec<String>, String> {
+    shlex::split(line).ok_or_else(|| format!("Unbalanced quotes in: {}", line))
+}
+
+/// The config of a line of the shell: the one of the shell with the
+/// options given on the line, e.g. `--sort due list`. Another config
+/// file can't be read inside of the shell.
+fn line_config(config: &Config, args: &ArgMatches) -> Result<Config, InternalError> {
+    if args.get_one::<String>("config").is_some() {
+        return Err(InternalError::invalid_input("--config can't be given inside of the shell, start it with the option instead"));
+    }
+
+    let mut config = config.clone();
+    apply_args(&mut config, args)?;
+    Ok(config)
+}
+
+/// Value name of the arguments receiving the id of a todo.
+const TODO_ID: &str = "TODO_ID";
+
+/// Value name of the arguments receiving the id of a task.
+const TASK_ID: &str = "TASK_ID";
+
+/// Finds the (sub)command being typed and the argument that
+/// would receive the next word, given the words before it. As
+/// with clap, a subcommand may follow the positional arguments,
+/// e.g. `todo 3 list` or `todo task 5 done`.
+fn completion_target<'c, 'a>(
+    command: &'c Command<'a>,
+    words: &[&str],
+) -> (&'c Command<'a>, Option<&'c Arg<'a>>) {
+    let mut command = command;
+    let mut pending: Option<&Arg> = None;
+    let mut positionals = 0;
+
+    for word in words {
+        if pending.take().is_some() {
+            continue;
+        }
+
+        let option = if let Some(long) = word.strip_prefix("--") {
+            command.get_arguments().find(|a| a.get_long() == Some(long))
+        } else if let Some(short) = word.strip_prefix('-') {
+            let short = short.chars().last();
+            command.get_arguments().find(|a| a.get_short().is_some() && a.get_short() == short)
+        } else {
+            None
+        };
+
+        if let Some(option) = option {
+            if option.is_takes_value_set() {
+                pending = Some(option);
+            }
+        } else if word.starts_with('-') {
+            continue;
+        } else if let Some(sub) = command.find_subcommand(*word) {
+            command = sub;
+            positionals = 0;
+        } else {
+            positionals += 1;
+        }
+    }
+
+    let target = pending.or_else(|| {
+        let args: Vec<&Arg> = command.get_positionals().collect();
+        args.get(positionals)
+            .or_else(|| args.last().filter(|a| a.is_multiple_values_set()))
+            .copied()
+    });
+
+    (command, target)
+}
+
+/// Completes the ids of todos or tasks, as pairs of the id and the name
+/// of the object, when the next word is one. Used by the completion scripts.
+fn complete_ids(command: &Command, words: &[&str], current: &str) -> Vec<(String, String)> {
+    let (_, target) = completion_target(command, words);
+
+    let value_names = target.and_then(|a| a.get_value_names()).unwrap_or_default();
+
+    let ids: Vec<(String, String)> = if value_names.contains(&TODO_ID) {
+        Todo::all()
+            .into_iter()
+            .map(|t| (t.id().to_string(), t.name().clone()))
+            .collect()
+    } else if value_names.contains(&TASK_ID) {
+        Task::all()
+            .into_iter()
+            .map(|t| (t.id().to_string(), t.what().clone()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    ids.into_iter().filter(|(id, _)| id.starts_with(current)).collect()
+}
+
+/// Completes any word: subcommands, flags, possible values and ids.
+fn complete(command: &Command, words: &[&str], current: &str) -> Vec<(String, String)> {
+    let (sub, target) = completion_target(command, words);
+    let mut candidates = complete_ids(command, words, current);
+
+    if current.starts_with('-') {
+        for arg in sub.get_arguments() {
+            if let Some(long) = arg.get_long() {
+                let help = arg.get_help().unwrap_or_default();
+                candidates.push((format!("--{}", long), String::from(help)));
+            }
+        }
+    } else {
+        for cmd in sub.get_subcommands().filter(|c| !c.is_hide_set()) {
+            let about = cmd.get_about().unwrap_or_default();
+            candidates.push((String::from(cmd.get_name()), String::from(about)));
+        }
+
+        for value in target.and_then(|a| a.get_possible_values()).unwrap_or_default() {
+            candidates.push((String::from(value.get_name()), String::new()));
+        }
+    }
+
+    candidates.retain(|(value, _)| value.starts_with(current));
+    candidates
+}
 
-    let mut app = App::new("TodoApp", "2.2.0");
+/// Writes the completion script for the given shell. The static
+/// part is generated from the grammar, and a wrapper asks the
+/// hidden `__complete` command for the ids of todos and tasks.
+fn print_completions(command: &mut Command, shell: Shell) {
+    let bin = "todo";
+    let mut script: Vec<u8> = Vec::new();
+    clap_complete::generate(shell, command, bin, &mut script);
+    let script = String::from_utf8(script).unwrap();
+
+    match shell {
+        Shell::Bash => {
+            print!("{}", script);
+            println!(
+                r#"
+_{bin}_dynamic() {{
+    local IFS=$'\n'
+    local ids=($({bin} __complete -- "${{COMP_WORDS[@]:1:COMP_CWORD}}" 2>/dev/null | cut -f1))
+    if [[ ${{#ids[@]}} -gt 0 ]]; then
+        COMPREPLY=("${{ids[@]}}")
+    else
+        _{bin} "$@"
+    fi
+}}
+complete -F _{bin}_dynamic -o bashdefault -o default {bin}"#
+            );
+        }
+        Shell::Zsh => {
+            let call = format!("_{} \"$@\"", bin);
+            print!("{}", script.trim_end().strip_suffix(&call).unwrap_or(&script));
+            println!(
+                r#"_{bin}_dynamic() {{
+    local -a ids
+    ids=(${{(f)"$({bin} __complete -- "${{(@)words[2,CURRENT]}}" 2>/dev/null | sed 's/	/:/')"}})
+    if (( ${{#ids}} )); then
+        _describe -t ids 'id' ids
+    else
+        _{bin} "$@"
+    fi
+}}
+
+if [ "$funcstack[1]" = "_{bin}" ]; then
+    _{bin}_dynamic "$@"
+else
+    compdef _{bin}_dynamic {bin}
+fi"#
+            );
+        }
+        Shell::Fish => {
+            print!("{}", script);
+            println!(
+                "complete -c {bin} -f -a '({bin} __complete -- (commandline -opc)[2..-1] (commandline -ct))'"
+            );
+        }
+        _ => print!("{}", script),
+    }
+}
+
+/// Completes the subcommands, their flags and the
+/// ids of the todos and tasks on the interactive shell.
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ShellHelper<'a> {
+    command: Command<'a>,
+}
+
+impl<'a> Completer for ShellHelper<'a> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+        let current = &line[start..];
+
+        let mut candidates = complete(&self.command, &words, current);
+
+        if words.is_empty() {
+            for name in ["exit", "quit"].iter().filter(|n| n.starts_with(current)) {
+                candidates.push((String::from(*name), String::new()));
+            }
+        }
+
+        let pairs = candidates
+            .into_iter()
+            .map(|(value, description)| Pair {
+                display: if description.is_empty() {
+                    value.clone()
+                } else {
+                    format!("{} ({})", value, description)
+                },
+                replacement: value,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+fn main() {
+    let mut app = App::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
     app.run();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(candidates: &[(String, String)]) -> Vec<&str> {
+        candidates.iter().map(|(value, _)| value.as_str()).collect()
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        let command = build_command("todo", "0.0.0");
+        let aliases = BTreeMap::from([
+            (String::from("work"), String::from("--sort due list --status open")),
+            (String::from("note"), String::from("add 'a note' -d \"with spaces\"")),
+            (String::from("list"), String::from("today")),
+            (String::from("broken"), String::from("add 'unbalanced")),
+        ]);
+        let argv = |words: &[&str]| words.iter().map(|w| String::from(*w)).collect::<Vec<_>>();
+
+        assert_eq!(
+            expand_alias(&command, &aliases, argv(&["todo", "work"])).unwrap(),
+            argv(&["todo", "--sort", "due", "list", "--status", "open"])
+        );
+        // After the options and the todo, and followed by more arguments.
+        assert_eq!(
+            expand_alias(&command, &aliases, argv(&["todo", "--color", "never", "3", "work", "--text", "x"])).unwrap(),
+            argv(&["todo", "--color", "never", "3", "--sort", "due", "list", "--status", "open", "--text", "x"])
+        );
+        assert_eq!(
+            expand_alias(&command, &aliases, argv(&["todo", "note"])).unwrap(),
+            argv(&["todo", "add", "a note", "-d", "with spaces"])
+        );
+
+        // The commands can't be replaced, and other words are kept.
+        assert_eq!(expand_alias(&command, &aliases, argv(&["todo", "list"])).unwrap(), argv(&["todo", "list"]));
+        assert_eq!(expand_alias(&command, &aliases, argv(&["todo", "3"])).unwrap(), argv(&["todo", "3"]));
+        assert_eq!(expand_alias(&command, &aliases, argv(&["todo", "other"])).unwrap(), argv(&["todo", "other"]));
+
+        assert!(expand_alias(&command, &aliases, argv(&["todo", "broken"])).unwrap_err().contains("broken"));
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line("add 'buy milk' -d \"and eggs\"").unwrap(), ["add", "buy milk", "-d", "and eggs"]);
+        assert_eq!(split_line("  3   list  ").unwrap(), ["3", "list"]);
+        assert_eq!(split_line("add it\\'s").unwrap(), ["add", "it's"]);
+        assert!(split_line("").unwrap().is_empty());
+
+        assert_eq!(split_line("add 'buy milk").unwrap_err(), "Unbalanced quotes in: add 'buy milk");
+        assert!(split_line("add \"buy milk").is_err());
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d").unwrap(), Duration::from_secs(30 * 24 * 60 * 60));
+        assert_eq!(parse_age("1h30m").unwrap(), Duration::from_secs(90 * 60));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+
+        assert_eq!(parse_age("10000000000000000d").unwrap_err(), "the age '10000000000000000d' is too long");
+        assert!(parse_age("18446744073709551615s1s").is_err());
+        assert!(parse_age("99999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn test_line_config() {
+        let command = build_command("todo", "0.0.0");
+        let mut config = Config::default();
+        config.workspaces.insert(String::from("work"), Workspace::default());
+        let args = |words: &[&str]| command.clone().try_get_matches_from(std::iter::once("todo").chain(words.iter().copied())).unwrap();
+
+        let line = line_config(&config, &args(&["--sort", "due", "--workspace", "work", "--output-format", "json", "list"])).unwrap();
+        assert_eq!(line.sort, SortOrder::Due);
+        assert_eq!(line.workspace.as_deref(), Some("work"));
+        assert_eq!(line.format, OutputFormat::Json);
+        assert_eq!(config.sort, SortOrder::Id);
+        assert_eq!(config.workspace, None);
+
+        let line = line_config(&config, &args(&["list", "--backend", "files"])).unwrap();
+        assert_eq!(line.backend, Backend::Files);
+
+        assert_eq!(line_config(&config, &args(&["list"])).unwrap(), config);
+        assert!(line_config(&config, &args(&["--config", "other.toml", "list"])).is_err());
+    }
+
+    #[test]
+    fn test_command_index() {
+        let command = build_command("todo", "0.0.0");
+        let argv = |words: &[&str]| words.iter().map(|w| String::from(*w)).collect::<Vec<_>>();
+
+        assert_eq!(command_index(&command, &argv(&["todo", "list"])), Some(1));
+        assert_eq!(command_index(&command, &argv(&["todo", "--sort", "due", "3", "foo", "--sort"])), Some(4));
+        assert_eq!(command_index(&command, &argv(&["todo", "--sort=due", "-h", "foo"])), Some(3));
+        assert_eq!(command_index(&command, &argv(&["todo", "--workspace", "work", "3"])), None);
+        assert_eq!(command_index(&command, &argv(&["todo"])), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_plugin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let command = build_command("todo", "0.0.0");
+        let argv = |words: &[&str]| words.iter().map(|w| String::from(*w)).collect::<Vec<_>>();
+
+        let dir = env::temp_dir().join(format!("todo-plugins-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for name in ["todo-foo", "todo-list", "todo-data"] {
+            std::fs::write(dir.join(name), "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(dir.join(name), std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        // Files that can't be run aren't plugins.
+        std::fs::set_permissions(dir.join("todo-data"), std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let path = env::var_os("PATH").unwrap_or_default();
+        let paths = std::iter::once(dir.clone()).chain(env::split_paths(&path));
+        env::set_var("PATH", env::join_paths(paths).unwrap());
+
+        let foo = Some((1, dir.join("todo-foo")));
+        assert_eq!(find_plugin(&command, &argv(&["todo", "foo", "--all"])), foo);
+
+        // After the options and the todo, which are still for the application.
+        let foo = Some((5, dir.join("todo-foo")));
+        assert_eq!(find_plugin(&command, &argv(&["todo", "--workspace", "work", "--color", "never", "foo"])), foo);
+        let foo = Some((4, dir.join("todo-foo")));
+        assert_eq!(find_plugin(&command, &argv(&["todo", "--sort", "due", "3", "foo"])), foo);
+
+        // The commands of the application come first.
+        assert_eq!(find_plugin(&command, &argv(&["todo", "list"])), None);
+        assert_eq!(find_plugin(&command, &argv(&["todo", "3", "list"])), None);
+        assert_eq!(find_plugin(&command, &argv(&["todo", "help"])), None);
+
+        assert_eq!(find_plugin(&command, &argv(&["todo", "data"])), None);
+        assert_eq!(find_plugin(&command, &argv(&["todo", "bar"])), None);
+        assert_eq!(find_plugin(&command, &argv(&["todo", "../foo"])), None);
+
+        env::set_var("PATH", path);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_completion_target() {
+        let command = build_command("todo", "0.0.0");
+
+        let (sub, target) = completion_target(&command, &[]);
+        assert_eq!(sub.get_name(), "todo");
+        assert_eq!(target.map(|a| a.get_id()), Some("id"));
+
+        let (sub, target) = completion_target(&command, &["--sort", "due", "task"]);
+        assert_eq!(sub.get_name(), "task");
+        assert_eq!(target.map(|a| a.get_id()), Some("task_id"));
+
+        let (sub, target) = completion_target(&command, &["move", "--to"]);
+        assert_eq!(sub.get_name(), "move");
+        assert_eq!(target.map(|a| a.get_id()), Some("to"));
+
+        let (sub, target) = completion_target(&command, &["task", "3"]);
+        assert_eq!(sub.get_name(), "task");
+        assert!(target.is_none());
+
+        let (sub, target) = completion_target(&command, &["3", "list", "--status"]);
+        assert_eq!(sub.get_name(), "list");
+        assert_eq!(target.map(|a| a.get_id()), Some("status"));
+    }
+
+    #[test]
+    fn test_complete() {
+        let command = build_command("todo", "0.0.0").subcommand(complete_command());
+
+        Store::set_default_store(Store::in_memory().unwrap());
+        let todo = Todo::add("release".into(), None).unwrap();
+        let task = Task::add("ship it".into(), *todo.id()).unwrap();
+        let todo_id = todo.id().to_string();
+        let task_id = task.id().to_string();
+
+        // Subcommands, of the application and of a command.
+        assert_eq!(names(&complete(&command, &[], "li")), ["list"]);
+        assert_eq!(names(&complete(&command, &["task", &task_id], "do")), ["done"]);
+        assert_eq!(names(&complete(&command, &["workspace"], "u")), ["use"]);
+
+        // Options, and their values.
+        assert_eq!(names(&complete(&command, &["list"], "--st")), ["--status"]);
+        assert!(names(&complete(&command, &["list"], "--")).contains(&"--text"));
+        assert_eq!(names(&complete(&command, &["--sort"], "pr")), ["priority"]);
+        assert_eq!(names(&complete(&command, &["list", "--status"], "can")), ["cancelled"]);
+
+        // Ids of todos, with their names, before a command or as the value of an option.
+        let candidates = complete(&command, &["log"], "");
+        assert_eq!(candidates, [(todo_id.clone(), String::from("release"))]);
+        assert_eq!(names(&complete(&command, &["move", "--to"], "")), [todo_id.as_str()]);
+        assert!(complete(&command, &["log"], "9").is_empty());
+
+        // Ids of tasks, with what they are.
+        let candidates = complete(&command, &["task"], &task_id);
+        assert_eq!(candidates, [(task_id.clone(), String::from("ship it"))]);
+
+        // An empty word gets everything that may follow.
+        let candidates = complete(&command, &[], "");
+        let candidates = names(&candidates);
+        assert!(candidates.contains(&todo_id.as_str()));
+        assert!(candidates.contains(&"list"));
+        assert!(candidates.contains(&"task"));
+        assert!(!candidates.contains(&"__complete"));
+        assert!(!candidates.contains(&"--sort"));
+
+        let candidates = complete(&command, &["task"], "");
+        let candidates = names(&candidates);
+        assert!(candidates.contains(&task_id.as_str()));
+        assert!(candidates.contains(&"start"));
+    }
+}
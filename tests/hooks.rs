@@ -0,0 +1,116 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Runs the application, failing instead of waiting forever.
+/// Returns whether it succeeded, with its output.
+fn run(config: &Path, backend: &str, args: &[&str]) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .arg("--config")
+        .arg(config)
+        .args(args)
+        .env("TODO_BACKEND", backend)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let start = Instant::now();
+
+    while child.try_wait().unwrap().is_none() {
+        if start.elapsed() > Duration::from_secs(30) {
+            child.kill().unwrap();
+            panic!("todo {} did not finish", args.join(" "));
+        }
+
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let output = child.wait_with_output().unwrap();
+    let text = String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr);
+    (output.status.success(), text)
+}
+
+fn todo(config: &Path, backend: &str, args: &[&str]) -> String {
+    run(config, backend, args).1
+}
+
+#[test]
+fn test_hook_running_todo() {
+    for backend in ["sqlite", "files"] {
+        let dir = std::env::temp_dir().join(format!("todo-cli-hooks-{}-{}", backend, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = dir.join("config.toml");
+        let hook = dir.join("follow-up");
+
+        // The hook adds a todo with the application itself, on the same storage.
+        fs::write(
+            &hook,
+            format!(
+                "#!/bin/sh\ncat > /dev/null\n'{}' --config '{}' add 'follow up' > /dev/null || {{ echo could not add; exit 1; }}\n",
+                env!("CARGO_BIN_EXE_todo"),
+                config.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+        fs::write(
+            &config,
+            format!(
+                "database = '{}'\ndir = '{}'\n\n[hooks]\non-complete = '{}'\n",
+                dir.join("todo.db").display(),
+                dir.join("files").display(),
+                hook.display()
+            ),
+        )
+        .unwrap();
+
+        let tasks = dir.join("tasks.csv");
+        fs::write(&tasks, "todo_name,what\nrelease,ship it\n").unwrap();
+        todo(&config, backend, &["import", tasks.to_str().unwrap()]);
+
+        let output = todo(&config, backend, &["task", "1", "done"]);
+        assert!(output.contains("Task 1 is now done"), "{}: {}", backend, output);
+        assert!(todo(&config, backend, &["list"]).contains("follow up"), "{}", backend);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
+
+#[test]
+fn test_hook_refusing_fails() {
+    let dir = std::env::temp_dir().join(format!("todo-cli-refused-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let config = dir.join("config.toml");
+    let hook = dir.join("refuse");
+    fs::write(&hook, "#!/bin/sh\ncat > /dev/null\necho not today\nexit 1\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(
+        &config,
+        format!("database = '{}'\n\n[hooks]\non-complete = '{}'\n", dir.join("todo.db").display(), hook.display()),
+    )
+    .unwrap();
+
+    let tasks = dir.join("tasks.csv");
+    fs::write(&tasks, "todo_name,what\nrelease,ship it\n").unwrap();
+    assert!(run(&config, "sqlite", &["import", tasks.to_str().unwrap()]).0);
+
+    // Scripts can tell the change was refused.
+    let (success, output) = run(&config, "sqlite", &["task", "1", "done"]);
+    assert!(!success, "{}", output);
+    assert!(output.contains("not today"), "{}", output);
+    assert!(!run(&config, "sqlite", &["task", "99", "start"]).0);
+    assert!(run(&config, "sqlite", &["task", "1", "start"]).0);
+
+    let _ = fs::remove_dir_all(&dir);
+}